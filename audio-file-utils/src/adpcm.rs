@@ -0,0 +1,85 @@
+//! IMA ADPCM blocks of device files
+//!
+//! Each block starts with a 4 byte header holding the first sample and the step index of the
+//! decoder, followed by two samples per byte, low nibble first.
+
+use audio_codec_algorithms::{AdpcmImaState, decode_adpcm_ima};
+
+/// Size of an IMA ADPCM block in the data chunk
+pub const BLOCK_SIZE: usize = 1024;
+/// Samples per block: the header sample plus two per remaining byte
+pub const SAMPLES_PER_BLOCK: usize = 1 + (BLOCK_SIZE - 4) * 2;
+
+/// Decodes a single block into `out`, stopping early when `out` is full.
+/// A block shorter than its header decodes to nothing, a truncated one to the samples it holds.
+/// Returns the number of samples written.
+pub fn decode_block(block: &[u8], out: &mut [i16]) -> usize {
+    if block.len() < 4 || out.is_empty() {
+        return 0;
+    }
+
+    let mut state = AdpcmImaState::new();
+    state.predictor = i16::from_le_bytes([block[0], block[1]]);
+    state.step_index = block[2].min(88);
+
+    out[0] = state.predictor;
+    let mut count = 1;
+    for b in &block[4..] {
+        for nibble in [*b & 0x0f, *b >> 4] {
+            if count >= out.len() {
+                return count;
+            }
+            out[count] = decode_adpcm_ima(nibble, &mut state);
+            count += 1;
+        }
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use audio_codec_algorithms::encode_adpcm_ima_ms;
+
+    fn sine() -> [i16; SAMPLES_PER_BLOCK] {
+        core::array::from_fn(|i| {
+            (libm::sinf(i as f32 * 2.0 * core::f32::consts::PI / 400.0) * 4000.0) as i16
+        })
+    }
+
+    fn encoded(samples: &[i16]) -> [u8; BLOCK_SIZE] {
+        let mut block = [0u8; BLOCK_SIZE];
+        encode_adpcm_ima_ms(samples, &mut [AdpcmImaState::new()], &mut block).unwrap();
+        block
+    }
+
+    #[test]
+    fn test_decode_block() {
+        let samples = sine();
+        let block = encoded(&samples);
+        let mut out = [0i16; SAMPLES_PER_BLOCK];
+        assert_eq!(decode_block(&block, &mut out), SAMPLES_PER_BLOCK);
+        assert_eq!(out[0], samples[0]);
+        for (decoded, sample) in out.iter().zip(samples) {
+            assert!((*decoded as i32 - sample as i32).abs() < 200);
+        }
+    }
+
+    #[test]
+    fn test_decode_partial_block() {
+        let block = encoded(&sine());
+        let mut full = [0i16; SAMPLES_PER_BLOCK];
+        decode_block(&block, &mut full);
+
+        // A short output buffer gets the start of the block
+        let mut out = [0i16; 100];
+        assert_eq!(decode_block(&block, &mut out), 100);
+        assert_eq!(out, full[..100]);
+
+        // The end of a file cuts the last block short
+        let mut out = [0i16; SAMPLES_PER_BLOCK];
+        assert_eq!(decode_block(&block[..14], &mut out), 21);
+        assert_eq!(out[..21], full[..21]);
+        assert_eq!(decode_block(&block[..3], &mut out), 0);
+    }
+}
@@ -0,0 +1,486 @@
+//! Fixed-point equalizer for small speakers
+//!
+//! Two biquad filters shape the sound: a low shelf that boosts the bass or cuts the lows a
+//! small enclosure cannot reproduce, and a peak for presence. At low volume the ear hears less
+//! bass, so the shelf adds loudness compensation the further the volume is turned down.
+//! Boosts take headroom before the filters, which the volume gain gives back as far as it
+//! attenuates anyway.
+//!
+//! Coefficients follow the RBJ Audio EQ Cookbook. They are computed in floating point when a
+//! setting changes and run in Q28 with 64 bit accumulators, which keeps two filters at 44.1 kHz
+//! well within the budget of a microcontroller without an FPU.
+
+use core::f32::consts::PI;
+
+/// Strongest boost or cut of a band in dB
+pub const MAX_BAND_DB: i8 = 12;
+/// Strongest loudness compensation in dB
+const MAX_LOUDNESS_DB: f32 = 10.0;
+/// Bass boost per dB of volume attenuation
+const LOUDNESS_PER_DB: f32 = 1.0 / 3.0;
+/// Fractional bits of the filter coefficients
+const COEFF_BITS: u32 = 28;
+
+/// Equalizer settings, usually tuned once per enclosure
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EqSettings {
+    /// Gain of the low shelf in dB, negative values cut the lows
+    pub bass_db: i8,
+    /// Corner frequency of the low shelf
+    pub bass_hz: u16,
+    /// Gain of the presence peak in dB
+    pub presence_db: i8,
+    /// Center frequency of the presence peak
+    pub presence_hz: u16,
+    /// Width of the presence peak, higher is narrower
+    pub presence_q: f32,
+    /// Boosts the bass at low volume
+    pub loudness: bool,
+}
+
+impl Default for EqSettings {
+    fn default() -> Self {
+        Self {
+            bass_db: 0,
+            bass_hz: 150,
+            presence_db: 0,
+            presence_hz: 3000,
+            presence_q: 1.0,
+            loudness: true,
+        }
+    }
+}
+
+/// A second order IIR filter in direct form I with Q28 coefficients
+#[derive(Clone, Copy, Debug)]
+pub struct Biquad {
+    b0: i32,
+    b1: i32,
+    b2: i32,
+    a1: i32,
+    a2: i32,
+    x1: i32,
+    x2: i32,
+    y1: i32,
+    y2: i32,
+    /// Fraction cut off the last output, fed back so that rounding errors do not add up
+    /// near the poles of low frequency filters
+    error: i64,
+}
+
+impl Biquad {
+    /// A filter passing the signal unchanged
+    pub fn identity() -> Self {
+        Self::from_coefficients([1.0, 0.0, 0.0], [1.0, 0.0, 0.0])
+    }
+
+    /// Low shelf with `gain_db` below `freq`
+    pub fn low_shelf(freq: f32, gain_db: f32, sample_rate: f32) -> Self {
+        let a = libm::powf(10.0, gain_db / 40.0);
+        let w0 = 2.0 * PI * freq / sample_rate;
+        let (sin, cos) = (libm::sinf(w0), libm::cosf(w0));
+        // Shelf slope 1, the steepest without overshoot
+        let alpha = sin / 2.0 * core::f32::consts::SQRT_2;
+        let sqrt_a = 2.0 * libm::sqrtf(a) * alpha;
+
+        Self::from_coefficients(
+            [
+                a * ((a + 1.0) - (a - 1.0) * cos + sqrt_a),
+                2.0 * a * ((a - 1.0) - (a + 1.0) * cos),
+                a * ((a + 1.0) - (a - 1.0) * cos - sqrt_a),
+            ],
+            [
+                (a + 1.0) + (a - 1.0) * cos + sqrt_a,
+                -2.0 * ((a - 1.0) + (a + 1.0) * cos),
+                (a + 1.0) + (a - 1.0) * cos - sqrt_a,
+            ],
+        )
+    }
+
+    /// Peak of `gain_db` around `freq`, `q` sets its width
+    pub fn peaking(freq: f32, gain_db: f32, q: f32, sample_rate: f32) -> Self {
+        let a = libm::powf(10.0, gain_db / 40.0);
+        let w0 = 2.0 * PI * freq / sample_rate;
+        let (sin, cos) = (libm::sinf(w0), libm::cosf(w0));
+        let alpha = sin / (2.0 * q);
+
+        Self::from_coefficients(
+            [1.0 + alpha * a, -2.0 * cos, 1.0 - alpha * a],
+            [1.0 + alpha / a, -2.0 * cos, 1.0 - alpha / a],
+        )
+    }
+
+    /// Normalizes by `a[0]` and converts to Q28
+    fn from_coefficients(b: [f32; 3], a: [f32; 3]) -> Self {
+        let scale = (1u32 << COEFF_BITS) as f32 / a[0];
+        let fixed = |value: f32| libm::roundf(value * scale) as i32;
+        Self {
+            b0: fixed(b[0]),
+            b1: fixed(b[1]),
+            b2: fixed(b[2]),
+            a1: fixed(a[1]),
+            a2: fixed(a[2]),
+            x1: 0,
+            x2: 0,
+            y1: 0,
+            y2: 0,
+            error: 0,
+        }
+    }
+
+    /// Filters one sample
+    pub fn process(&mut self, sample: i16) -> i16 {
+        let x = sample as i32;
+        let acc = self.b0 as i64 * x as i64
+            + self.b1 as i64 * self.x1 as i64
+            + self.b2 as i64 * self.x2 as i64
+            - self.a1 as i64 * self.y1 as i64
+            - self.a2 as i64 * self.y2 as i64
+            + self.error;
+        let y = acc >> COEFF_BITS;
+        self.error = acc - (y << COEFF_BITS);
+        let y = y.clamp(i16::MIN as i64, i16::MAX as i64) as i32;
+
+        self.x2 = self.x1;
+        self.x1 = x;
+        self.y2 = self.y1;
+        self.y1 = y;
+        y as i16
+    }
+
+    /// Takes over the coefficients of `other` and keeps the filter state, so the sound
+    /// changes without a click
+    fn retune(&mut self, other: &Biquad) {
+        self.b0 = other.b0;
+        self.b1 = other.b1;
+        self.b2 = other.b2;
+        self.a1 = other.a1;
+        self.a2 = other.a2;
+    }
+
+    /// Magnitude response in dB at `freq`, as the quantized coefficients produce it
+    pub fn response_db(&self, freq: f32, sample_rate: f32) -> f32 {
+        let w = 2.0 * PI * freq / sample_rate;
+        let (cos1, sin1) = (libm::cosf(w), libm::sinf(w));
+        let (cos2, sin2) = (libm::cosf(2.0 * w), libm::sinf(2.0 * w));
+        let coeff = |value: i32| value as f32 / (1u32 << COEFF_BITS) as f32;
+
+        let (b0, b1, b2) = (coeff(self.b0), coeff(self.b1), coeff(self.b2));
+        let (a1, a2) = (coeff(self.a1), coeff(self.a2));
+        let num_re = b0 + b1 * cos1 + b2 * cos2;
+        let num_im = -(b1 * sin1 + b2 * sin2);
+        let den_re = 1.0 + a1 * cos1 + a2 * cos2;
+        let den_im = -(a1 * sin1 + a2 * sin2);
+
+        10.0 * libm::log10f(
+            (num_re * num_re + num_im * num_im) / (den_re * den_re + den_im * den_im),
+        )
+    }
+}
+
+/// Low shelf and presence peak with loudness compensation
+pub struct Equalizer {
+    settings: EqSettings,
+    sample_rate: f32,
+    /// Volume attenuation in dB the loudness compensation is tuned for
+    attenuation_db: f32,
+    /// Attenuation before the filters in dB, so boosts do not clip
+    headroom_db: f32,
+    /// Attenuation before the filters, so boosts do not clip
+    pre_gain: i32,
+    /// Part of the headroom the volume gives back after the filters, in Q15
+    makeup_gain: i32,
+    bass: Option<Biquad>,
+    presence: Option<Biquad>,
+}
+
+impl Equalizer {
+    pub fn new(settings: EqSettings, sample_rate: f32) -> Self {
+        let mut equalizer = Self {
+            settings,
+            sample_rate,
+            attenuation_db: 0.0,
+            headroom_db: 0.0,
+            pre_gain: 1 << 15,
+            makeup_gain: 1 << 15,
+            bass: None,
+            presence: None,
+        };
+        equalizer.tune();
+        equalizer
+    }
+
+    pub fn settings(&self) -> &EqSettings {
+        &self.settings
+    }
+
+    /// Adjusts the loudness compensation to the volume, `attenuation_db` below full scale
+    pub fn set_volume_attenuation(&mut self, attenuation_db: f32) {
+        if attenuation_db != self.attenuation_db {
+            self.attenuation_db = attenuation_db;
+            if self.settings.loudness {
+                self.tune();
+            } else {
+                self.update_makeup_gain();
+            }
+        }
+    }
+
+    /// Gain to apply after the filters for the volume gain `volume_gain`, both in Q15.
+    ///
+    /// The headroom for boosts is taken before the filters. As far as the volume attenuates
+    /// anyway, the volume gain gives it back, so boosts do not make the rest quieter.
+    pub fn output_gain(&self, volume_gain: i32) -> i32 {
+        ((volume_gain as i64 * self.makeup_gain as i64) >> 15) as i32
+    }
+
+    /// Bass boost of the loudness compensation in dB
+    fn loudness_db(&self) -> f32 {
+        if self.settings.loudness {
+            (self.attenuation_db * LOUDNESS_PER_DB).clamp(0.0, MAX_LOUDNESS_DB)
+        } else {
+            0.0
+        }
+    }
+
+    fn tune(&mut self) {
+        let max = MAX_BAND_DB as f32;
+        let bass_db = (self.settings.bass_db as f32).clamp(-max, max) + self.loudness_db();
+        let presence_db = (self.settings.presence_db as f32).clamp(-max, max);
+
+        self.bass = (bass_db != 0.0).then(|| {
+            let tuned = Biquad::low_shelf(self.settings.bass_hz as f32, bass_db, self.sample_rate);
+            match self.bass {
+                Some(mut bass) => {
+                    bass.retune(&tuned);
+                    bass
+                }
+                None => tuned,
+            }
+        });
+        self.presence = (presence_db != 0.0).then(|| {
+            let tuned = Biquad::peaking(
+                self.settings.presence_hz as f32,
+                presence_db,
+                self.settings.presence_q.max(0.1),
+                self.sample_rate,
+            );
+            match self.presence {
+                Some(mut presence) => {
+                    presence.retune(&tuned);
+                    presence
+                }
+                None => tuned,
+            }
+        });
+
+        self.headroom_db = bass_db.max(presence_db).max(0.0);
+        self.pre_gain = q15_from_db(-self.headroom_db);
+        self.update_makeup_gain();
+    }
+
+    /// Gives back the headroom the volume attenuation covers
+    fn update_makeup_gain(&mut self) {
+        let absorbed_db = self.headroom_db.min(self.attenuation_db.max(0.0));
+        self.makeup_gain = q15_from_db(absorbed_db);
+    }
+
+    /// Filters `samples` in place
+    pub fn process(&mut self, samples: &mut [i16]) {
+        if self.bass.is_none() && self.presence.is_none() {
+            return;
+        }
+        for sample in samples {
+            let mut value = ((*sample as i32 * self.pre_gain) >> 15) as i16;
+            if let Some(bass) = &mut self.bass {
+                value = bass.process(value);
+            }
+            if let Some(presence) = &mut self.presence {
+                value = presence.process(value);
+            }
+            *sample = value;
+        }
+    }
+}
+
+/// Gain of `db` in Q15
+fn q15_from_db(db: f32) -> i32 {
+    libm::roundf(libm::powf(10.0, db / 20.0) * (1 << 15) as f32) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: f32 = 44100.0;
+
+    /// Gain in dB of a sine at `freq` through `process`, measured after the filter settled
+    fn measured_gain_db(mut process: impl FnMut(&mut [i16]), freq: f32) -> f32 {
+        let input: [i16; 8820] = core::array::from_fn(|i| {
+            (libm::sinf(2.0 * PI * freq * i as f32 / RATE) * 4000.0) as i16
+        });
+        let mut output = input;
+        process(&mut output);
+
+        let rms = |samples: &[i16]| {
+            let sum: f32 = samples.iter().map(|&s| s as f32 * s as f32).sum();
+            libm::sqrtf(sum / samples.len() as f32)
+        };
+        20.0 * libm::log10f(rms(&output[4410..]) / rms(&input[4410..]))
+    }
+
+    fn assert_close(actual: f32, expected: f32, tolerance: f32) {
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "{} is not within {} of {}",
+            actual,
+            tolerance,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_identity() {
+        let mut filter = Biquad::identity();
+        for sample in [0, 1, -1, 12345, i16::MAX, i16::MIN] {
+            assert_eq!(filter.process(sample), sample);
+        }
+        assert_close(filter.response_db(1000.0, RATE), 0.0, 0.001);
+    }
+
+    #[test]
+    fn test_low_shelf_response() {
+        // Reference: RBJ low shelf, +6 dB below 150 Hz, half the gain at the corner
+        let filter = Biquad::low_shelf(150.0, 6.0, RATE);
+        assert_close(filter.response_db(20.0, RATE), 6.0, 0.2);
+        assert_close(filter.response_db(150.0, RATE), 3.0, 0.05);
+        assert_close(filter.response_db(5000.0, RATE), 0.0, 0.05);
+
+        let cut = Biquad::low_shelf(150.0, -12.0, RATE);
+        assert_close(cut.response_db(20.0, RATE), -12.0, 0.5);
+        assert_close(cut.response_db(150.0, RATE), -6.0, 0.05);
+        assert_close(cut.response_db(5000.0, RATE), 0.0, 0.05);
+    }
+
+    #[test]
+    fn test_peaking_response() {
+        // Reference: RBJ peak, full gain at the center, flat far away
+        let filter = Biquad::peaking(3000.0, 6.0, 1.0, RATE);
+        assert_close(filter.response_db(3000.0, RATE), 6.0, 0.01);
+        assert_close(filter.response_db(100.0, RATE), 0.0, 0.05);
+        assert_close(filter.response_db(20000.0, RATE), 0.0, 0.3);
+        assert!(filter.response_db(1500.0, RATE) > 1.0);
+    }
+
+    #[test]
+    fn test_fixed_point_matches_response() {
+        for (freq, gain) in [(40.0, 6.0), (3000.0, 0.0)] {
+            let mut filter = Biquad::low_shelf(150.0, 6.0, RATE);
+            let measured = measured_gain_db(
+                |samples| samples.iter_mut().for_each(|s| *s = filter.process(*s)),
+                freq,
+            );
+            assert_close(measured, gain, 0.3);
+        }
+
+        let mut filter = Biquad::peaking(3000.0, -6.0, 2.0, RATE);
+        let measured = measured_gain_db(
+            |samples| samples.iter_mut().for_each(|s| *s = filter.process(*s)),
+            3000.0,
+        );
+        assert_close(measured, -6.0, 0.2);
+    }
+
+    #[test]
+    fn test_full_scale_does_not_overflow() {
+        let mut filter = Biquad::low_shelf(150.0, 12.0, RATE);
+        let mut output = [0i16; 2000];
+        for (i, sample) in output.iter_mut().enumerate() {
+            let input = if i / 100 % 2 == 0 { i16::MAX } else { i16::MIN };
+            *sample = filter.process(input);
+        }
+        assert!(output.contains(&i16::MAX));
+        assert!(output.contains(&i16::MIN));
+    }
+
+    #[test]
+    fn test_flat_equalizer_passes_through() {
+        let settings = EqSettings {
+            loudness: false,
+            ..Default::default()
+        };
+        let mut equalizer = Equalizer::new(settings, RATE);
+        let mut samples = [100i16, -200, i16::MAX, i16::MIN];
+        equalizer.process(&mut samples);
+        assert_eq!(samples, [100, -200, i16::MAX, i16::MIN]);
+
+        // Loudness compensation only kicks in below full volume
+        let mut equalizer = Equalizer::new(EqSettings::default(), RATE);
+        equalizer.process(&mut samples);
+        assert_eq!(samples, [100, -200, i16::MAX, i16::MIN]);
+    }
+
+    #[test]
+    fn test_volume_change_keeps_filter_state() {
+        let settings = EqSettings {
+            presence_db: 6,
+            ..Default::default()
+        };
+        let mut equalizer = Equalizer::new(settings, RATE);
+        equalizer.set_volume_attenuation(6.0);
+        let mut samples: [i16; 1000] = core::array::from_fn(|i| {
+            (libm::sinf(i as f32 * 2.0 * PI * 3000.0 / RATE) * 8000.0) as i16
+        });
+        equalizer.process(&mut samples);
+
+        let state = |biquad: &Biquad| (biquad.x1, biquad.x2, biquad.y1, biquad.y2, biquad.error);
+        let bass = state(equalizer.bass.as_ref().unwrap());
+        let presence = state(equalizer.presence.as_ref().unwrap());
+        assert_ne!(presence, (0, 0, 0, 0, 0));
+
+        // Loudness retunes the filters on every volume step, without resetting them mid-stream
+        equalizer.set_volume_attenuation(12.0);
+        assert_eq!(state(equalizer.bass.as_ref().unwrap()), bass);
+        assert_eq!(state(equalizer.presence.as_ref().unwrap()), presence);
+    }
+
+    /// Gain in dB of a sine at `freq` through the equalizer and the volume attenuating by
+    /// `attenuation_db`
+    fn measured_output_db(settings: EqSettings, attenuation_db: f32, freq: f32) -> f32 {
+        let mut equalizer = Equalizer::new(settings, RATE);
+        equalizer.set_volume_attenuation(attenuation_db);
+        let gain = equalizer.output_gain(q15_from_db(-attenuation_db));
+        let process = |samples: &mut [i16]| {
+            equalizer.process(samples);
+            for sample in samples {
+                *sample = ((*sample as i32 * gain) >> 15) as i16;
+            }
+        };
+        measured_gain_db(process, freq)
+    }
+
+    #[test]
+    fn test_loudness_boosts_bass_at_low_volume() {
+        let bass = measured_output_db(EqSettings::default(), 18.0, 40.0);
+        let mids = measured_output_db(EqSettings::default(), 18.0, 2000.0);
+
+        // 6 dB more bass than mids, the mids follow the volume
+        assert_close(bass - mids, 6.0, 0.3);
+        assert_close(mids, -18.0, 0.2);
+    }
+
+    #[test]
+    fn test_headroom_beyond_the_volume_attenuation() {
+        let settings = EqSettings {
+            bass_db: 6,
+            loudness: false,
+            ..Default::default()
+        };
+        // The volume covers the headroom of the boost
+        assert_close(measured_output_db(settings, 9.0, 2000.0), -9.0, 0.2);
+        assert_close(measured_output_db(settings, 9.0, 40.0), -3.0, 0.3);
+        // At full volume the boost can only be made room for by cutting the rest
+        assert_close(measured_output_db(settings, 0.0, 2000.0), -6.0, 0.2);
+        assert_close(measured_output_db(settings, 3.0, 2000.0), -6.0, 0.2);
+    }
+}
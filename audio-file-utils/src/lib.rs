@@ -1,2 +1,9 @@
 #![no_std]
+extern crate alloc;
+
+pub mod adpcm;
+pub mod eq;
 pub mod metadata;
+pub mod order;
+pub mod presence;
+pub mod ramp;
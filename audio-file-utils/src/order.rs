@@ -0,0 +1,496 @@
+//! Playing order of a playlist
+//!
+//! [`PlayOrder`] maps positions in the playing order to indices of the files in the playlist.
+//! Without shuffle the two are the same. With shuffle the file playing stays first and the
+//! others follow in random order, and every round of repeating all gets a new order that does
+//! not start with the file just played. Files added, removed or moved while playing keep the
+//! file playing current.
+
+use alloc::vec::Vec;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Repeat {
+    #[default]
+    Off,
+    One,
+    All,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Skip {
+    Next,
+    Previous,
+}
+
+/// Order in which the files of a playlist are played, shuffled or not
+#[derive(Clone, Debug)]
+pub struct PlayOrder {
+    /// File indices in playing order
+    order: Vec<usize>,
+    /// Position in `order`, `order.len()` once the playlist has ended
+    position: usize,
+    shuffled: bool,
+    rng_state: u32,
+}
+
+impl PlayOrder {
+    /// Starts with the file at index `first`, which stays first when shuffled
+    pub fn new(total_files: usize, first: usize, shuffle: bool, seed: u32) -> Self {
+        let mut order = Self {
+            order: (0..total_files).collect(),
+            position: first.min(total_files),
+            shuffled: false,
+            // xorshift gets stuck at zero
+            rng_state: seed | 1,
+        };
+        order.set_shuffle(shuffle);
+        order
+    }
+
+    /// Index of the file to play, `None` once the playlist has ended
+    pub fn current(&self) -> Option<usize> {
+        self.order.get(self.position).copied()
+    }
+
+    /// Switches between playlist and shuffled order, keeping the current file
+    pub fn set_shuffle(&mut self, shuffle: bool) {
+        if shuffle == self.shuffled {
+            return;
+        }
+        self.shuffled = shuffle;
+
+        let current = self.current();
+        self.order = (0..self.order.len()).collect();
+        match current {
+            Some(current) if shuffle => {
+                self.order.swap(0, current);
+                self.shuffle_from(1);
+                self.position = 0;
+            }
+            Some(current) => self.position = current,
+            // Ended, the next round gets shuffled when it starts
+            None => self.position = self.order.len(),
+        }
+    }
+
+    /// Moves on after the current file played to its end
+    pub fn next_after_end(&mut self, repeat: Repeat) {
+        match repeat {
+            Repeat::One => {}
+            Repeat::Off => self.position += 1,
+            Repeat::All => self.next_wrapping(),
+        }
+    }
+
+    /// The file `next_after_end` moves to, `None` when the playlist ends there or the next
+    /// round is not shuffled yet
+    pub fn peek_after_end(&self, repeat: Repeat) -> Option<usize> {
+        match repeat {
+            Repeat::One => self.current(),
+            Repeat::Off => self.order.get(self.position + 1).copied(),
+            Repeat::All if self.position + 1 < self.order.len() => {
+                self.order.get(self.position + 1).copied()
+            }
+            Repeat::All if !self.shuffled => self.order.first().copied(),
+            Repeat::All => None,
+        }
+    }
+
+    /// Moves on after the current file could not be played, also when repeating it
+    pub fn next_after_error(&mut self, repeat: Repeat) {
+        match repeat {
+            Repeat::Off | Repeat::One => self.position += 1,
+            Repeat::All => self.next_wrapping(),
+        }
+    }
+
+    /// Skips to the next or previous file. Without repeating all, skipping stops at the first
+    /// and last file.
+    pub fn skip(&mut self, skip: Skip, repeat: Repeat) {
+        let last = self.order.len().saturating_sub(1);
+        match (skip, repeat) {
+            (Skip::Next, Repeat::All) => self.next_wrapping(),
+            (Skip::Next, _) => self.position = (self.position + 1).min(last),
+            (Skip::Previous, Repeat::All) if self.position == 0 => self.position = last,
+            (Skip::Previous, _) => self.position = self.position.saturating_sub(1),
+        }
+    }
+
+    /// Makes room for `count` files inserted into the playlist at index `at`. With `next` they
+    /// play right after the current file, otherwise after all others.
+    pub fn insert(&mut self, at: usize, count: usize, next: bool) {
+        for index in &mut self.order {
+            if *index >= at {
+                *index += count;
+            }
+        }
+        let slot = if next {
+            (self.position + 1).min(self.order.len())
+        } else {
+            self.order.len()
+        };
+        self.order.splice(slot..slot, at..at + count);
+    }
+
+    /// Removes the file at index `index` of the playlist. Returns whether it was the current
+    /// file, the order then continues with the file after it.
+    pub fn remove(&mut self, index: usize) -> bool {
+        let Some(slot) = self.order.iter().position(|&i| i == index) else {
+            return false;
+        };
+        self.order.remove(slot);
+        for i in &mut self.order {
+            if *i > index {
+                *i -= 1;
+            }
+        }
+        let was_current = slot == self.position;
+        if slot < self.position {
+            self.position -= 1;
+        }
+        was_current
+    }
+
+    /// Moves the file at index `from` of the playlist to index `to`, the current file stays
+    /// current
+    pub fn move_file(&mut self, from: usize, to: usize) {
+        let moved = |index: usize| {
+            if index == from {
+                return to;
+            }
+            let index = if index > from { index - 1 } else { index };
+            if index >= to { index + 1 } else { index }
+        };
+        if self.shuffled {
+            for index in &mut self.order {
+                *index = moved(*index);
+            }
+        } else if self.position < self.order.len() {
+            // Unshuffled, the order is the playlist's
+            self.position = moved(self.position);
+        }
+    }
+
+    /// Drops all files but the current one, which ends up as the only file of the playlist
+    pub fn keep_current(&mut self) {
+        self.order = match self.current() {
+            Some(_) => alloc::vec![0],
+            None => Vec::new(),
+        };
+        self.position = 0;
+    }
+
+    fn next_wrapping(&mut self) {
+        self.position += 1;
+        if self.position >= self.order.len() {
+            self.position = 0;
+            // Every round gets a new order, which does not start with the file just played
+            if self.shuffled && self.order.len() > 1 {
+                let last = self.order[self.order.len() - 1];
+                self.shuffle_from(0);
+                if self.order[0] == last {
+                    self.order.swap(0, 1);
+                }
+            }
+        }
+    }
+
+    /// Fisher-Yates shuffle of `order[start..]`
+    fn shuffle_from(&mut self, start: usize) {
+        for i in (start + 1..self.order.len()).rev() {
+            let j = start + self.next_random() as usize % (i + 1 - start);
+            self.order.swap(i, j);
+        }
+    }
+
+    /// xorshift32, good enough to mix up a playlist
+    fn next_random(&mut self) -> u32 {
+        let mut x = self.rng_state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.rng_state = x;
+        x
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// File indices of a whole round, starting at the current file
+    fn round(order: &PlayOrder) -> Vec<usize> {
+        order.order[order.position..].to_vec()
+    }
+
+    fn is_permutation(files: &[usize], total: usize) -> bool {
+        let mut sorted = files.to_vec();
+        sorted.sort_unstable();
+        sorted == (0..total).collect::<Vec<_>>()
+    }
+
+    #[test]
+    fn test_unshuffled_order() {
+        let order = PlayOrder::new(4, 2, false, 1);
+        assert_eq!(order.current(), Some(2));
+        assert_eq!(round(&order), [2, 3]);
+        assert_eq!(PlayOrder::new(4, 9, false, 1).current(), None);
+    }
+
+    #[test]
+    fn test_shuffle_keeps_current_file() {
+        for seed in 0..20 {
+            let mut order = PlayOrder::new(10, 3, true, seed);
+            assert_eq!(order.current(), Some(3));
+            assert!(is_permutation(&round(&order), 10));
+
+            order.skip(Skip::Next, Repeat::Off);
+            order.skip(Skip::Next, Repeat::Off);
+            let current = order.current();
+            order.set_shuffle(false);
+            assert_eq!(order.current(), current);
+            assert_eq!(round(&order)[0], current.unwrap());
+            order.set_shuffle(true);
+            assert_eq!(order.current(), current);
+            assert!(is_permutation(&round(&order), 10));
+        }
+    }
+
+    #[test]
+    fn test_shuffled_rounds_do_not_repeat_the_last_file() {
+        for seed in 0..20 {
+            let mut order = PlayOrder::new(3, 0, true, seed);
+            for _ in 0..10 {
+                order.next_after_end(Repeat::All);
+                order.next_after_end(Repeat::All);
+                let last = order.current();
+                assert_eq!(order.peek_after_end(Repeat::All), None);
+                order.next_after_end(Repeat::All);
+                assert_eq!(order.position, 0);
+                assert_ne!(order.current(), last);
+                assert!(is_permutation(&order.order, 3));
+            }
+        }
+    }
+
+    #[test]
+    fn test_repeat_off() {
+        let mut order = PlayOrder::new(3, 0, false, 1);
+        order.skip(Skip::Previous, Repeat::Off);
+        assert_eq!(order.current(), Some(0));
+        order.next_after_end(Repeat::Off);
+        assert_eq!(order.peek_after_end(Repeat::Off), Some(2));
+        order.next_after_end(Repeat::Off);
+        assert_eq!(order.peek_after_end(Repeat::Off), None);
+        order.skip(Skip::Next, Repeat::Off);
+        assert_eq!(order.current(), Some(2));
+        order.next_after_end(Repeat::Off);
+        assert_eq!(order.current(), None);
+    }
+
+    #[test]
+    fn test_repeat_one() {
+        let mut order = PlayOrder::new(3, 2, false, 1);
+        assert_eq!(order.peek_after_end(Repeat::One), Some(2));
+        order.next_after_end(Repeat::One);
+        assert_eq!(order.current(), Some(2));
+        // Skipping still moves, stopping at both ends
+        order.skip(Skip::Next, Repeat::One);
+        assert_eq!(order.current(), Some(2));
+        order.skip(Skip::Previous, Repeat::One);
+        order.skip(Skip::Previous, Repeat::One);
+        order.skip(Skip::Previous, Repeat::One);
+        assert_eq!(order.current(), Some(0));
+        // A file that cannot be played is not repeated
+        order.next_after_error(Repeat::One);
+        assert_eq!(order.current(), Some(1));
+    }
+
+    #[test]
+    fn test_repeat_all() {
+        let mut order = PlayOrder::new(3, 0, false, 1);
+        order.skip(Skip::Previous, Repeat::All);
+        assert_eq!(order.current(), Some(2));
+        assert_eq!(order.peek_after_end(Repeat::All), Some(0));
+        order.next_after_end(Repeat::All);
+        assert_eq!(order.current(), Some(0));
+        order.skip(Skip::Previous, Repeat::All);
+        order.skip(Skip::Next, Repeat::All);
+        assert_eq!(order.current(), Some(0));
+        order.next_after_error(Repeat::All);
+        assert_eq!(order.current(), Some(1));
+    }
+
+    #[test]
+    fn test_toggle_shuffle_after_the_end() {
+        let mut order = PlayOrder::new(4, 0, true, 7);
+        for _ in 0..4 {
+            order.next_after_end(Repeat::Off);
+        }
+        assert_eq!(order.current(), None);
+
+        order.set_shuffle(false);
+        assert_eq!(order.current(), None);
+        assert_eq!(order.order, [0, 1, 2, 3]);
+        // Moving files of an ended playlist does not bring it back
+        order.move_file(0, 3);
+        assert_eq!(order.current(), None);
+
+        order.set_shuffle(true);
+        assert_eq!(order.current(), None);
+        order.set_shuffle(false);
+        assert_eq!(order.order, [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_insert_unshuffled() {
+        let mut order = PlayOrder::new(3, 1, false, 1);
+        // Next after the current file, playlist [0, 1, N, 2]
+        order.insert(2, 1, true);
+        assert_eq!(order.current(), Some(1));
+        assert_eq!(round(&order), [1, 2, 3]);
+        // Appended, playlist [0, 1, N, 2, A, A]
+        order.insert(4, 2, false);
+        assert_eq!(round(&order), [1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_insert_shuffled() {
+        for seed in 0..20 {
+            let mut order = PlayOrder::new(5, 2, true, seed);
+            let before = round(&order);
+            // Inserted into the playlist after file 2, next in the order
+            order.insert(3, 2, true);
+            let shifted: Vec<usize> = before
+                .iter()
+                .map(|&i| if i >= 3 { i + 2 } else { i })
+                .collect();
+            assert_eq!(order.current(), Some(2));
+            assert_eq!(round(&order)[1..3], [3, 4]);
+            assert_eq!(round(&order)[3..], shifted[1..]);
+
+            // Appended, last in the order
+            order.insert(7, 1, false);
+            assert_eq!(order.current(), Some(2));
+            assert_eq!(round(&order).last(), Some(&7));
+            assert!(is_permutation(&order.order, 8));
+        }
+    }
+
+    #[test]
+    fn test_insert_after_the_end() {
+        let mut order = PlayOrder::new(2, 0, false, 1);
+        order.next_after_end(Repeat::Off);
+        order.next_after_end(Repeat::Off);
+        order.insert(2, 1, false);
+        assert_eq!(order.current(), Some(2));
+    }
+
+    #[test]
+    fn test_move_current_file() {
+        let mut order = PlayOrder::new(4, 1, false, 1);
+        order.move_file(1, 3);
+        assert_eq!(order.current(), Some(3));
+        order.move_file(3, 0);
+        assert_eq!(order.current(), Some(0));
+
+        let mut order = PlayOrder::new(4, 1, true, 3);
+        let rest = round(&order)[1..].to_vec();
+        order.move_file(1, 3);
+        assert_eq!(order.current(), Some(3));
+        // The others keep their place in the order, with indices of the moved playlist
+        let moved: Vec<usize> = rest
+            .iter()
+            .map(|&i| if i > 1 { i - 1 } else { i })
+            .collect();
+        assert_eq!(round(&order)[1..], moved);
+    }
+
+    #[test]
+    fn test_move_across_current_file() {
+        // Playlist [0, 1, 2, 3] playing 2, moving 0 behind it makes [1, 2, 0, 3]
+        let mut order = PlayOrder::new(4, 2, false, 1);
+        order.move_file(0, 2);
+        assert_eq!(order.current(), Some(1));
+        order.skip(Skip::Next, Repeat::Off);
+        assert_eq!(order.current(), Some(2));
+        // Playing 0 now, moving 3 in front of the playlist makes [3, 1, 2, 0]
+        order.move_file(3, 0);
+        assert_eq!(order.current(), Some(3));
+
+        // Moving files around the current one keeps it current when shuffled too
+        let mut order = PlayOrder::new(5, 2, true, 5);
+        order.move_file(0, 4);
+        assert_eq!(order.current(), Some(1));
+        order.move_file(4, 0);
+        assert_eq!(order.current(), Some(2));
+        assert!(is_permutation(&order.order, 5));
+    }
+
+    #[test]
+    fn test_remove() {
+        // Before the current file
+        let mut order = PlayOrder::new(4, 2, false, 1);
+        assert!(!order.remove(1));
+        assert_eq!(order.current(), Some(1));
+        assert_eq!(round(&order), [1, 2]);
+
+        // After the current file
+        assert!(!order.remove(2));
+        assert_eq!(order.current(), Some(1));
+        assert_eq!(round(&order), [1]);
+
+        // The current file, the order continues with the next one
+        let mut order = PlayOrder::new(4, 2, false, 1);
+        assert!(order.remove(2));
+        assert_eq!(order.current(), Some(2));
+        assert_eq!(round(&order), [2]);
+
+        // The last file while it plays ends the playlist
+        assert!(order.remove(2));
+        assert_eq!(order.current(), None);
+        assert!(!order.remove(5));
+    }
+
+    #[test]
+    fn test_remove_shuffled() {
+        for seed in 0..20 {
+            let mut order = PlayOrder::new(5, 2, true, seed);
+            order.skip(Skip::Next, Repeat::Off);
+            let previous = order.order[0];
+            let current = order.current().unwrap();
+            let next = order.order[2];
+            let shift = |i: usize, removed: usize| if i > removed { i - 1 } else { i };
+
+            assert!(!order.remove(previous));
+            let current = shift(current, previous);
+            let next = shift(next, previous);
+            assert_eq!(order.current(), Some(current));
+
+            assert!(!order.remove(next));
+            let current = shift(current, next);
+            assert_eq!(order.current(), Some(current));
+
+            let after = round(&order)[1..].to_vec();
+            assert!(order.remove(current));
+            let after: Vec<usize> = after.iter().map(|&i| shift(i, current)).collect();
+            assert_eq!(round(&order), after);
+            assert!(is_permutation(&order.order, 2));
+        }
+    }
+
+    #[test]
+    fn test_keep_current() {
+        let mut order = PlayOrder::new(4, 2, true, 9);
+        order.keep_current();
+        assert_eq!(order.current(), Some(0));
+        assert_eq!(order.peek_after_end(Repeat::Off), None);
+        order.next_after_end(Repeat::Off);
+        assert_eq!(order.current(), None);
+
+        // After the end nothing is left
+        order.keep_current();
+        assert_eq!(order.current(), None);
+        assert!(order.order.is_empty());
+    }
+}
@@ -0,0 +1,154 @@
+//! Fob on the RFID reader
+//!
+//! The reader is polled, and a single read fails now and then while the fob lies on it.
+//! [`Presence`] turns the readings into arrivals and removals of fobs, counting a fob as removed
+//! only after several readings in a row without it.
+
+use heapless::Vec;
+
+/// Readings in a row without the fob before it counts as removed
+pub const TAG_LEFT_MISSES: u8 = 2;
+
+/// Result of polling the reader once
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Reading<'a, T> {
+    Found(&'a T),
+    NotFound,
+    /// The reader failed, the fob may still be there
+    Error,
+}
+
+/// Change of the fob on the reader
+#[derive(Clone, Debug, PartialEq)]
+pub enum TagEvent<T> {
+    Arrived(T),
+    Left(T),
+}
+
+/// Events of a single reading, a fob swapped for another one leaves and the other arrives
+pub type TagEvents<T> = Vec<TagEvent<T>, 2>;
+
+/// Fob on the reader, debouncing removals
+#[derive(Clone, Debug)]
+pub struct Presence<T> {
+    fob: Option<T>,
+    misses: u8,
+}
+
+impl<T> Default for Presence<T> {
+    fn default() -> Self {
+        Self {
+            fob: None,
+            misses: 0,
+        }
+    }
+}
+
+impl<T: Clone + PartialEq> Presence<T> {
+    /// Fob on the reader, also while it missed fewer than `TAG_LEFT_MISSES` readings
+    pub fn fob(&self) -> Option<&T> {
+        self.fob.as_ref()
+    }
+
+    pub fn update(&mut self, reading: Reading<'_, T>) -> TagEvents<T> {
+        let mut events = Vec::new();
+        match reading {
+            Reading::Found(fob) => {
+                self.misses = 0;
+                if self.fob.as_ref() == Some(fob) {
+                    return events;
+                }
+                if let Some(left) = self.fob.replace(fob.clone()) {
+                    let _ = events.push(TagEvent::Left(left));
+                }
+                let _ = events.push(TagEvent::Arrived(fob.clone()));
+            }
+            Reading::NotFound => {
+                self.misses = self.misses.saturating_add(1);
+                if self.misses >= TAG_LEFT_MISSES
+                    && let Some(left) = self.fob.take()
+                {
+                    let _ = events.push(TagEvent::Left(left));
+                }
+            }
+            Reading::Error => {}
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const A: &u32 = &0xa;
+    const B: &u32 = &0xb;
+
+    #[test]
+    fn test_arrived_once() {
+        let mut presence = Presence::default();
+        let events = presence.update(Reading::Found(A));
+        assert_eq!(events.as_slice(), [TagEvent::Arrived(0xa)]);
+        for _ in 0..5 {
+            assert!(presence.update(Reading::Found(A)).is_empty());
+        }
+        assert_eq!(presence.fob(), Some(A));
+    }
+
+    #[test]
+    fn test_left_after_misses() {
+        let mut presence = Presence::default();
+        presence.update(Reading::Found(A));
+        for _ in 1..TAG_LEFT_MISSES {
+            assert!(presence.update(Reading::NotFound).is_empty());
+        }
+        let events = presence.update(Reading::NotFound);
+        assert_eq!(events.as_slice(), [TagEvent::Left(0xa)]);
+        assert_eq!(presence.fob(), None);
+        assert!(presence.update(Reading::NotFound).is_empty());
+
+        // A single miss does not count, the fob is still there
+        presence.update(Reading::Found(A));
+        assert!(presence.update(Reading::NotFound).is_empty());
+        assert!(presence.update(Reading::Found(A)).is_empty());
+        assert!(presence.update(Reading::NotFound).is_empty());
+        assert_eq!(presence.fob(), Some(A));
+    }
+
+    #[test]
+    fn test_errors_are_ignored() {
+        let mut presence = Presence::default();
+        presence.update(Reading::Found(A));
+        for _ in 0..5 {
+            assert!(presence.update(Reading::Error).is_empty());
+        }
+        assert_eq!(presence.fob(), Some(A));
+
+        // Errors between misses neither count as one nor reset the count
+        assert!(presence.update(Reading::NotFound).is_empty());
+        assert!(presence.update(Reading::Error).is_empty());
+        let events = presence.update(Reading::NotFound);
+        assert_eq!(events.as_slice(), [TagEvent::Left(0xa)]);
+    }
+
+    #[test]
+    fn test_swap() {
+        let mut presence = Presence::default();
+        presence.update(Reading::Found(A));
+        let events = presence.update(Reading::Found(B));
+        assert_eq!(
+            events.as_slice(),
+            [TagEvent::Left(0xa), TagEvent::Arrived(0xb)]
+        );
+        assert_eq!(presence.fob(), Some(B));
+
+        // Swapped slowly, the first fob leaves before the other arrives
+        for _ in 1..TAG_LEFT_MISSES {
+            presence.update(Reading::NotFound);
+        }
+        let events = presence.update(Reading::NotFound);
+        assert_eq!(events.as_slice(), [TagEvent::Left(0xb)]);
+        let events = presence.update(Reading::Found(A));
+        assert_eq!(events.as_slice(), [TagEvent::Arrived(0xa)]);
+    }
+}
@@ -0,0 +1,150 @@
+//! Gain ramps for click-free transitions
+//!
+//! Changing the gain of a signal from one sample to the next is heard as a click, and the
+//! amplifier pops when it is switched off while the signal is not silent. [`GainRamp`] moves
+//! the gain to a new target linearly over a few milliseconds instead, so pausing, stopping,
+//! skipping and volume changes fade.
+
+/// Gain of 1.0 in Q15
+pub const UNITY_GAIN: i32 = 1 << 15;
+
+/// A Q15 gain that follows its target with a linear ramp
+#[derive(Clone, Copy, Debug)]
+pub struct GainRamp {
+    /// Gain where the current ramp started
+    start: i32,
+    target: i32,
+    /// Length of the current ramp in samples
+    length: u32,
+    /// Samples of the current ramp already applied
+    done: u32,
+    /// Length of the ramps started by `set_target`
+    ramp_samples: u32,
+}
+
+impl GainRamp {
+    /// A ramp resting at `gain`, changes of the target take `ramp_samples` samples
+    pub fn new(gain: i32, ramp_samples: u32) -> Self {
+        Self {
+            start: gain,
+            target: gain,
+            length: 0,
+            done: 0,
+            ramp_samples,
+        }
+    }
+
+    /// Gain applied to the next sample
+    pub fn gain(&self) -> i32 {
+        if self.is_settled() {
+            self.target
+        } else {
+            let delta = (self.target - self.start) as i64 * self.done as i64 / self.length as i64;
+            self.start + delta as i32
+        }
+    }
+
+    pub fn target(&self) -> i32 {
+        self.target
+    }
+
+    /// Whether the gain reached its target
+    pub fn is_settled(&self) -> bool {
+        self.done >= self.length
+    }
+
+    /// Ramps from the current gain to `target` over the default length. A ramp already
+    /// heading for `target` keeps going.
+    pub fn set_target(&mut self, target: i32) {
+        self.ramp_to(target, self.ramp_samples);
+    }
+
+    /// Ramps from the current gain to `target` over `samples` samples, zero jumps right away.
+    /// A ramp already heading for `target` keeps going.
+    pub fn ramp_to(&mut self, target: i32, samples: u32) {
+        if target == self.target {
+            return;
+        }
+        self.start = self.gain();
+        self.target = target;
+        self.length = samples;
+        self.done = 0;
+    }
+
+    /// Multiplies `samples` by the gain, moving along the ramp by one step per sample
+    pub fn apply(&mut self, samples: &mut [i16]) {
+        for sample in samples {
+            let gain = self.gain();
+            *sample =
+                ((*sample as i32 * gain) >> 15).clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+            if self.done < self.length {
+                self.done += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settled_gain() {
+        let mut ramp = GainRamp::new(UNITY_GAIN / 2, 100);
+        assert!(ramp.is_settled());
+        let mut samples = [1000i16, -1000, i16::MAX, i16::MIN];
+        ramp.apply(&mut samples);
+        assert_eq!(samples, [500, -500, 16383, -16384]);
+
+        let mut samples = [i16::MAX, i16::MIN];
+        GainRamp::new(UNITY_GAIN, 100).apply(&mut samples);
+        assert_eq!(samples, [i16::MAX, i16::MIN]);
+    }
+
+    #[test]
+    fn test_ramp_reaches_target() {
+        let mut ramp = GainRamp::new(0, 100);
+        ramp.set_target(UNITY_GAIN);
+        assert!(!ramp.is_settled());
+        assert_eq!(ramp.gain(), 0);
+
+        let mut samples = [10000i16; 120];
+        ramp.apply(&mut samples);
+        assert!(ramp.is_settled());
+        assert_eq!(samples[0], 0);
+        assert_eq!(samples[50], 5000);
+        assert!(samples[..100].windows(2).all(|pair| pair[0] <= pair[1]));
+        assert!(samples[100..].iter().all(|&sample| sample == 10000));
+    }
+
+    #[test]
+    fn test_retarget_continues_from_current_gain() {
+        let mut ramp = GainRamp::new(0, 100);
+        ramp.set_target(UNITY_GAIN);
+        ramp.apply(&mut [0; 50]);
+        let halfway = ramp.gain();
+        assert_eq!(halfway, UNITY_GAIN / 2);
+
+        // Same target, the ramp keeps its pace
+        ramp.set_target(UNITY_GAIN);
+        assert_eq!(ramp.gain(), halfway);
+
+        // Fading out starts where the fade-in got to, without a jump
+        ramp.ramp_to(0, 10);
+        assert_eq!(ramp.gain(), halfway);
+        let mut samples = [20000i16; 12];
+        ramp.apply(&mut samples);
+        assert_eq!(samples[0], 10000);
+        assert_eq!(samples[10..], [0, 0]);
+    }
+
+    #[test]
+    fn test_zero_length_jumps() {
+        let mut ramp = GainRamp::new(UNITY_GAIN, 100);
+        ramp.ramp_to(0, 0);
+        assert!(ramp.is_settled());
+        let mut samples = [1234i16; 4];
+        ramp.apply(&mut samples);
+        assert_eq!(samples, [0; 4]);
+    }
+}
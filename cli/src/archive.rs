@@ -0,0 +1,313 @@
+//! Backup archive format
+//!
+//! A plain tar file containing the raw device files as `FILES/<NAME>.WAV` followed by
+//! `manifest.json`, which lists every file with its size and SHA-256 checksum, the fob
+//! associations and the device configuration (without the WiFi password).
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::{Read, Write};
+
+use crate::client::{FileMetadata, PublicConfig};
+use crate::layout::{FILE_DIR, FILE_EXT};
+
+pub const MANIFEST_NAME: &str = "manifest.json";
+pub const ARCHIVE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BackupManifest {
+    pub version: u32,
+    pub files: Vec<BackupFile>,
+    pub associations: Vec<BackupAssociation>,
+    pub config: Option<PublicConfig>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BackupFile {
+    pub name: String,
+    pub size: u64,
+    pub sha256: String,
+    pub metadata: FileMetadata,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BackupAssociation {
+    pub fob: String,
+    pub files: Vec<String>,
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn file_path(name: &str) -> String {
+    format!("{}/{}{}", FILE_DIR, name, FILE_EXT)
+}
+
+/// Extracts the device file name from an archive path
+fn file_name(path: &str) -> Option<&str> {
+    path.strip_prefix(FILE_DIR)?
+        .strip_prefix('/')?
+        .strip_suffix(FILE_EXT)
+}
+
+pub struct ArchiveWriter<W: Write> {
+    builder: tar::Builder<W>,
+    files: Vec<BackupFile>,
+}
+
+impl<W: Write> ArchiveWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            builder: tar::Builder::new(writer),
+            files: Vec::new(),
+        }
+    }
+
+    fn append(&mut self, path: &str, data: &[u8]) -> std::io::Result<()> {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        self.builder.append_data(&mut header, path, data)
+    }
+
+    pub fn add_file(
+        &mut self,
+        name: &str,
+        metadata: FileMetadata,
+        data: &[u8],
+    ) -> std::io::Result<()> {
+        self.append(&file_path(name), data)?;
+        self.files.push(BackupFile {
+            name: name.to_string(),
+            size: data.len() as u64,
+            sha256: sha256_hex(data),
+            metadata,
+        });
+        Ok(())
+    }
+
+    /// Writes the manifest and finishes the archive
+    pub fn finish(
+        mut self,
+        associations: Vec<BackupAssociation>,
+        config: Option<PublicConfig>,
+    ) -> Result<W, Box<dyn std::error::Error>> {
+        let manifest = BackupManifest {
+            version: ARCHIVE_VERSION,
+            files: std::mem::take(&mut self.files),
+            associations,
+            config,
+        };
+        let json = serde_json::to_vec_pretty(&manifest)?;
+        self.append(MANIFEST_NAME, &json)?;
+        Ok(self.builder.into_inner()?)
+    }
+}
+
+/// Reads the manifest of an archive
+pub fn read_manifest<R: Read>(reader: R) -> Result<BackupManifest, Box<dyn std::error::Error>> {
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if entry.path()?.to_str() == Some(MANIFEST_NAME) {
+            let manifest: BackupManifest = serde_json::from_reader(&mut entry)?;
+            if manifest.version != ARCHIVE_VERSION {
+                return Err(format!("Unsupported backup version {}", manifest.version).into());
+            }
+            return Ok(manifest);
+        }
+    }
+    Err(format!("{} missing in archive", MANIFEST_NAME).into())
+}
+
+/// Opens an archive for reading its files with [`files`]
+pub fn open<R: Read>(reader: R) -> tar::Archive<R> {
+    tar::Archive::new(reader)
+}
+
+/// Iterates over the files of an archive listed in `manifest`, verifying their checksums.
+/// Fails at the end if files listed in the manifest are missing from the archive.
+pub fn files<'a, R: Read>(
+    archive: &'a mut tar::Archive<R>,
+    manifest: &'a BackupManifest,
+) -> Result<Files<'a, R>, Box<dyn std::error::Error>> {
+    Ok(Files {
+        entries: archive.entries()?,
+        manifest,
+        seen: 0,
+        done: false,
+    })
+}
+
+/// A file from the archive with its verified content
+pub type ArchiveFile<'a> = (&'a BackupFile, Vec<u8>);
+
+pub struct Files<'a, R: Read> {
+    entries: tar::Entries<'a, R>,
+    manifest: &'a BackupManifest,
+    seen: usize,
+    done: bool,
+}
+
+impl<'a, R: Read> Files<'a, R> {
+    fn read_entry(
+        &self,
+        mut entry: tar::Entry<'a, R>,
+    ) -> Result<Option<ArchiveFile<'a>>, Box<dyn std::error::Error>> {
+        let path = entry.path()?.to_string_lossy().into_owned();
+        let Some(name) = file_name(&path) else {
+            return Ok(None);
+        };
+        let file = self
+            .manifest
+            .files
+            .iter()
+            .find(|file| file.name == name)
+            .ok_or_else(|| format!("{} is not listed in the manifest", path))?;
+
+        let mut data = Vec::with_capacity(file.size as usize);
+        entry.read_to_end(&mut data)?;
+        if data.len() as u64 != file.size || sha256_hex(&data) != file.sha256 {
+            return Err(format!("Checksum mismatch for {}", path).into());
+        }
+
+        Ok(Some((file, data)))
+    }
+}
+
+impl<'a, R: Read> Iterator for Files<'a, R> {
+    type Item = Result<ArchiveFile<'a>, Box<dyn std::error::Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        loop {
+            let result = match self.entries.next() {
+                Some(Ok(entry)) => self.read_entry(entry),
+                Some(Err(e)) => Err(e.into()),
+                None => {
+                    self.done = true;
+                    if self.seen != self.manifest.files.len() {
+                        return Some(Err(format!(
+                            "Archive contains {} of {} files listed in the manifest",
+                            self.seen,
+                            self.manifest.files.len()
+                        )
+                        .into()));
+                    }
+                    return None;
+                }
+            };
+
+            match result {
+                Ok(None) => continue,
+                Ok(Some(file)) => {
+                    self.seen += 1;
+                    return Some(Ok(file));
+                }
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn metadata() -> FileMetadata {
+        FileMetadata {
+            artist: "Artist".to_string(),
+            title: "Song".to_string(),
+            album: "Album".to_string(),
+            duration: 1,
+        }
+    }
+
+    fn build_archive() -> Vec<u8> {
+        let mut writer = ArchiveWriter::new(Vec::new());
+        writer.add_file("ABCDEFGH", metadata(), b"first").unwrap();
+        writer.add_file("IJKLMNOP", metadata(), b"second").unwrap();
+        writer
+            .finish(
+                vec![BackupAssociation {
+                    fob: "1a2b3c4d".to_string(),
+                    files: vec!["IJKLMNOP".to_string(), "ABCDEFGH".to_string()],
+                }],
+                Some(PublicConfig {
+                    ssid: "home".to_string(),
+                    ..Default::default()
+                }),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let archive = build_archive();
+        let manifest = read_manifest(Cursor::new(&archive)).unwrap();
+        assert_eq!(manifest.files.len(), 2);
+        assert_eq!(manifest.files[0].size, 5);
+        assert_eq!(manifest.associations[0].files[0], "IJKLMNOP");
+        assert_eq!(manifest.config.as_ref().unwrap().ssid, "home");
+
+        let mut reader = open(Cursor::new(&archive));
+        let files = files(&mut reader, &manifest)
+            .unwrap()
+            .map(|result| result.map(|(file, data)| (file.name.clone(), data)))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            files,
+            vec![
+                ("ABCDEFGH".to_string(), b"first".to_vec()),
+                ("IJKLMNOP".to_string(), b"second".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_corrupted_file_is_detected() {
+        let archive = build_archive();
+        let mut manifest = read_manifest(Cursor::new(&archive)).unwrap();
+        manifest.files[1].sha256 = sha256_hex(b"something else");
+
+        let mut reader = open(Cursor::new(&archive));
+        let result = files(&mut reader, &manifest)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_missing_file_is_detected() {
+        let archive = build_archive();
+        let mut manifest = read_manifest(Cursor::new(&archive)).unwrap();
+        manifest.files.push(manifest.files[0].clone());
+        manifest.files[2].name = "QRSTUVWX".to_string();
+
+        let mut reader = open(Cursor::new(&archive));
+        let result = files(&mut reader, &manifest)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_sha256_hex() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+}
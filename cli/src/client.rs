@@ -0,0 +1,467 @@
+//! Minimal client for the device REST API (see `API.md`)
+use clap::Args;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::discovery;
+use crate::layout::{DeviceConfig, EqConfig, RescanConfig, TagPresenceConfig, UnknownFobAction};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const UPLOAD_CHUNK_SIZE: usize = 128 * 1024;
+const MAX_RETRIES: u32 = 3;
+
+#[derive(Args, Clone)]
+pub struct DeviceArgs {
+    /// Device address (IP address, hostname or base URL), or `auto` to discover it via mDNS
+    #[arg(long, default_value = "phoniesp32.local")]
+    pub device: String,
+}
+
+impl DeviceArgs {
+    pub async fn client(&self) -> Result<DeviceClient, Box<dyn std::error::Error>> {
+        if self.device != "auto" {
+            return Ok(DeviceClient::new(&self.device));
+        }
+
+        let devices =
+            discovery::discover(discovery::MDNS_ADDR.into(), discovery::DEFAULT_TIMEOUT).await?;
+        match devices.as_slice() {
+            [] => Err("No device found, pass its address with --device".into()),
+            [device] => Ok(DeviceClient::new(&device.address.to_string())),
+            _ => Err(format!(
+                "Found {} devices ({}), pass the address of one with --device",
+                devices.len(),
+                devices
+                    .iter()
+                    .map(|device| device.address.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+            .into()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FileMetadata {
+    pub artist: String,
+    pub title: String,
+    pub album: String,
+    pub duration: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileEntry {
+    pub name: String,
+    pub metadata: FileMetadata,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Association {
+    pub fob: String,
+    pub files: Vec<FileEntry>,
+}
+
+#[derive(Serialize)]
+struct AssociationRequest<'a> {
+    fob: &'a str,
+    files: &'a [String],
+}
+
+/// Device configuration as reported by the device, without secrets
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct PublicConfig {
+    pub ssid: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_volume: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub startup_volume: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eq: Option<EqConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unknown_fob: Option<UnknownFobAction>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag_presence: Option<TagPresenceConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rescan: Option<RescanConfig>,
+}
+
+impl PublicConfig {
+    /// Configuration to write back to the device, with the password it does not report
+    pub fn into_device_config(self, password: String) -> DeviceConfig {
+        let PublicConfig {
+            ssid,
+            max_volume,
+            startup_volume,
+            eq,
+            unknown_fob,
+            tag_presence,
+            rescan,
+        } = self;
+        DeviceConfig {
+            ssid,
+            password,
+            max_volume,
+            startup_volume,
+            eq,
+            unknown_fob,
+            tag_presence,
+            rescan,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PlaybackStatus {
+    pub position_seconds: u32,
+    /// `Playing`, `Paused` or `Stopped`
+    pub state: String,
+    pub index_in_playlist: usize,
+    pub playlist_name: Option<String>,
+    /// Only reported by firmware versions with volume in the status
+    #[serde(default)]
+    pub volume: Option<u8>,
+    #[serde(default)]
+    pub max_volume: Option<u8>,
+    /// Fob waiting for a playlist in learn mode
+    #[serde(default)]
+    pub learning_fob: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PlaylistFile {
+    pub file: String,
+    pub metadata: FileMetadata,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CurrentPlaylist {
+    pub playlist_name: String,
+    pub files: Vec<PlaylistFile>,
+}
+
+#[derive(Deserialize)]
+struct LastFob {
+    last_fob: Option<String>,
+}
+
+/// Playback commands without parameters (`POST /api/playback/<command>`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlaybackCommand {
+    Pause,
+    Stop,
+    Next,
+    Previous,
+    VolumeUp,
+    VolumeDown,
+}
+
+impl PlaybackCommand {
+    fn path(self) -> &'static str {
+        match self {
+            PlaybackCommand::Pause => "pause",
+            PlaybackCommand::Stop => "stop",
+            PlaybackCommand::Next => "next",
+            PlaybackCommand::Previous => "previous",
+            PlaybackCommand::VolumeUp => "volume_up",
+            PlaybackCommand::VolumeDown => "volume_down",
+        }
+    }
+}
+
+pub struct DeviceClient {
+    base_url: String,
+    http: reqwest::Client,
+}
+
+impl DeviceClient {
+    pub fn new(device: &str) -> Self {
+        let device = device.trim_end_matches('/');
+        let base_url = if device.starts_with("http://") || device.starts_with("https://") {
+            device.to_string()
+        } else {
+            format!("http://{}", device)
+        };
+
+        Self {
+            base_url,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    pub async fn list_files(&self) -> Result<Vec<FileEntry>, Box<dyn std::error::Error>> {
+        let response = self
+            .http
+            .get(self.url("/api/files"))
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(response.json().await?)
+    }
+
+    pub async fn download_file(&self, name: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let response = self
+            .http
+            .get(self.url(&format!("/api/files/{}/data", name)))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    /// Current size of a file on the device, `None` if it does not exist
+    pub async fn file_size(&self, name: &str) -> Result<Option<u64>, Box<dyn std::error::Error>> {
+        let response = self
+            .http
+            .head(self.url(&format!("/api/files/{}", name)))
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let response = response.error_for_status()?;
+        let offset = response
+            .headers()
+            .get("Upload-Offset")
+            .ok_or("Upload-Offset header missing")?
+            .to_str()?
+            .parse()?;
+        Ok(Some(offset))
+    }
+
+    /// Create (or truncate) a file for chunked upload
+    pub async fn create_file(&self, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.http
+            .post(self.url(&format!("/api/files/{}", name)))
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    pub async fn upload_chunk(
+        &self,
+        name: &str,
+        offset: u64,
+        data: Vec<u8>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.http
+            .patch(self.url(&format!("/api/files/{}", name)))
+            .timeout(REQUEST_TIMEOUT)
+            .header("Upload-Offset", offset.to_string())
+            .body(data)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    /// Upload `data` in chunks, starting at `offset` of a file created with [`Self::create_file`].
+    /// Failed chunks are retried where the device left off.
+    pub async fn upload(
+        &self,
+        name: &str,
+        data: &[u8],
+        mut offset: u64,
+        progress: impl Fn(u64),
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut attempt = 0;
+        while offset < data.len() as u64 {
+            let end = (offset as usize + UPLOAD_CHUNK_SIZE).min(data.len());
+            let chunk = data[offset as usize..end].to_vec();
+
+            match self.upload_chunk(name, offset, chunk).await {
+                Ok(()) => {
+                    progress((end as u64) - offset);
+                    offset = end as u64;
+                    attempt = 0;
+                }
+                Err(e) if attempt < MAX_RETRIES => {
+                    // Wait before retry (exponential backoff), then continue where the device left off
+                    tokio::time::sleep(Duration::from_millis(100 * 2_u64.pow(attempt))).await;
+                    attempt += 1;
+
+                    if let Some(size) = self.file_size(name).await.ok().flatten()
+                        && size <= offset
+                    {
+                        offset = size;
+                    } else {
+                        return Err(format!("Uploading {}: {}", name, e).into());
+                    }
+                }
+                Err(e) => return Err(format!("Uploading {}: {}", name, e).into()),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Delete a file, returns `false` if it did not exist
+    pub async fn delete_file(&self, name: &str) -> Result<bool, Box<dyn std::error::Error>> {
+        let response = self
+            .http
+            .delete(self.url(&format!("/api/files/{}", name)))
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(false);
+        }
+
+        response.error_for_status()?;
+        Ok(true)
+    }
+
+    pub async fn list_associations(&self) -> Result<Vec<Association>, Box<dyn std::error::Error>> {
+        let response = self
+            .http
+            .get(self.url("/api/associations"))
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(response.json().await?)
+    }
+
+    pub async fn associate(
+        &self,
+        fob: &str,
+        files: &[String],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.http
+            .post(self.url("/api/associations"))
+            .timeout(REQUEST_TIMEOUT)
+            .json(&AssociationRequest { fob, files })
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    /// Configuration without secrets, `None` if the device has no configuration
+    pub async fn get_config(&self) -> Result<Option<PublicConfig>, Box<dyn std::error::Error>> {
+        let response = self
+            .http
+            .get(self.url("/api/config"))
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        Ok(Some(response.error_for_status()?.json().await?))
+    }
+
+    pub async fn put_config(
+        &self,
+        config: &DeviceConfig,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.http
+            .put(self.url("/api/config"))
+            .timeout(REQUEST_TIMEOUT)
+            .json(config)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    pub async fn playback_status(&self) -> Result<PlaybackStatus, Box<dyn std::error::Error>> {
+        let response = self
+            .http
+            .get(self.url("/api/playback/status"))
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(response.json().await?)
+    }
+
+    /// Playlist being played, `None` if playback is stopped
+    pub async fn current_playlist(
+        &self,
+    ) -> Result<Option<CurrentPlaylist>, Box<dyn std::error::Error>> {
+        let response = self
+            .http
+            .get(self.url("/api/playback/current_playlist"))
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(response.json().await?)
+    }
+
+    pub async fn last_fob(&self) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let response = self
+            .http
+            .get(self.url("/api/last_fob"))
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(response.json::<LastFob>().await?.last_fob)
+    }
+
+    pub async fn playback_command(
+        &self,
+        command: PlaybackCommand,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.http
+            .post(self.url(&format!("/api/playback/{}", command.path())))
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_public_config_into_device_config() {
+        let public = PublicConfig {
+            ssid: "home".to_string(),
+            max_volume: Some(20),
+            startup_volume: Some(8),
+            eq: Some(EqConfig::default()),
+            unknown_fob: Some(UnknownFobAction::Learn),
+            tag_presence: Some(TagPresenceConfig::default()),
+            rescan: Some(RescanConfig::default()),
+        };
+        let config = public.clone().into_device_config("secret".to_string());
+        assert_eq!(config.password, "secret");
+
+        // Every reported setting is written back
+        let mut written = serde_json::to_value(&config).unwrap();
+        written.as_object_mut().unwrap().remove("password");
+        assert_eq!(written, serde_json::to_value(&public).unwrap());
+    }
+
+    #[test]
+    fn test_base_url() {
+        assert_eq!(
+            DeviceClient::new("192.168.42.1").base_url(),
+            "http://192.168.42.1"
+        );
+        assert_eq!(
+            DeviceClient::new("http://phoniesp32.local/").base_url(),
+            "http://phoniesp32.local"
+        );
+    }
+}
@@ -0,0 +1,106 @@
+use clap::Args;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::path::PathBuf;
+
+use crate::archive::{ArchiveWriter, BackupAssociation};
+use crate::client::{DeviceArgs, DeviceClient};
+
+const DOWNLOAD_ATTEMPTS: usize = 3;
+
+#[derive(Args)]
+#[command(about = "Back up files, associations and configuration of a device")]
+pub struct BackupCommand {
+    #[command(flatten)]
+    pub device: DeviceArgs,
+    /// Output archive path (tar)
+    pub output: PathBuf,
+}
+
+impl BackupCommand {
+    pub async fn execute(self) -> Result<(), Box<dyn std::error::Error>> {
+        let client = self.device.client().await?;
+
+        let files = client.list_files().await?;
+        let associations = client.list_associations().await?;
+        let config = client.get_config().await?;
+
+        // Write to a temporary file so an interrupted backup never leaves a truncated archive
+        let partial = self.output.with_extension("partial");
+        let mut writer =
+            ArchiveWriter::new(std::io::BufWriter::new(std::fs::File::create(&partial)?));
+
+        let pb = ProgressBar::new(files.len() as u64);
+        pb.set_style(
+            ProgressStyle::default_bar()
+                .template(
+                    "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}",
+                )
+                .unwrap()
+                .progress_chars("#>-"),
+        );
+
+        for file in files {
+            pb.set_message(file.name.clone());
+            let data = download(&client, &file.name).await?;
+            writer.add_file(&file.name, file.metadata, &data)?;
+            pb.inc(1);
+        }
+        pb.finish_with_message("done");
+
+        let associations = associations
+            .into_iter()
+            .map(|association| BackupAssociation {
+                fob: association.fob,
+                files: association
+                    .files
+                    .into_iter()
+                    .map(|file| file.name)
+                    .collect(),
+            })
+            .collect::<Vec<_>>();
+        let association_count = associations.len();
+
+        writer
+            .finish(associations, config)?
+            .into_inner()?
+            .sync_all()?;
+        std::fs::rename(&partial, &self.output)?;
+
+        println!(
+            "Backed up {} files and {} associations from {} to {}",
+            pb.position(),
+            association_count,
+            client.base_url(),
+            self.output.display()
+        );
+        Ok(())
+    }
+}
+
+/// Downloads a file, retrying when the transfer is cut short
+async fn download(
+    client: &DeviceClient,
+    name: &str,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let mut last_error = None;
+    for _ in 0..DOWNLOAD_ATTEMPTS {
+        let result = async {
+            let data = client.download_file(name).await?;
+            let expected = client.file_size(name).await?.ok_or("File disappeared")?;
+            if data.len() as u64 != expected {
+                return Err(
+                    format!("Incomplete download ({} of {} bytes)", data.len(), expected).into(),
+                );
+            }
+            Ok::<_, Box<dyn std::error::Error>>(data)
+        }
+        .await;
+
+        match result {
+            Ok(data) => return Ok(data),
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    Err(format!("Downloading {}: {}", name, last_error.unwrap()).into())
+}
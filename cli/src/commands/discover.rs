@@ -0,0 +1,35 @@
+use clap::Args;
+use comfy_table::{Table, presets::UTF8_FULL};
+use std::time::Duration;
+
+use crate::discovery::{self, MDNS_ADDR};
+
+#[derive(Args)]
+#[command(about = "Find devices on the local network via mDNS")]
+pub struct DiscoverCommand {
+    /// How long to wait for answers, in milliseconds
+    #[arg(long, default_value_t = discovery::DEFAULT_TIMEOUT.as_millis() as u64)]
+    pub timeout: u64,
+}
+
+impl DiscoverCommand {
+    pub async fn execute(self) -> Result<(), Box<dyn std::error::Error>> {
+        let devices =
+            discovery::discover(MDNS_ADDR.into(), Duration::from_millis(self.timeout)).await?;
+
+        if devices.is_empty() {
+            println!("No devices found");
+            return Ok(());
+        }
+
+        let mut table = Table::new();
+        table.load_preset(UTF8_FULL);
+        table.set_header(vec!["IP Address", "Hostname"]);
+        for device in devices {
+            table.add_row(vec![device.address.to_string(), device.hostname]);
+        }
+        println!("{}", table);
+
+        Ok(())
+    }
+}
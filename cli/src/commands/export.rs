@@ -0,0 +1,73 @@
+use clap::{Args, ValueEnum};
+use comfy_table::{Table, presets::UTF8_FULL};
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum ExportFormat {
+    Wav,
+    Flac,
+}
+
+impl ExportFormat {
+    fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "wav" => Some(ExportFormat::Wav),
+            "flac" => Some(ExportFormat::Flac),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Args)]
+#[command(about = "Decode a device file back to PCM WAV or FLAC")]
+pub struct ExportCommand {
+    /// Device WAV file (IMA ADPCM)
+    pub input_file: PathBuf,
+    /// Output file path
+    #[arg(short, long)]
+    pub output: PathBuf,
+    /// Output format, derived from the output file extension by default
+    #[arg(long, value_enum)]
+    pub format: Option<ExportFormat>,
+}
+
+impl ExportCommand {
+    pub async fn execute(self) -> Result<(), Box<dyn std::error::Error>> {
+        use transcoder::{decode_ima_adpcm_wav, encode_flac, encode_pcm_wav};
+
+        let format = self
+            .format
+            .or_else(|| ExportFormat::from_path(&self.output))
+            .ok_or("Cannot derive the format from the output file name, pass --format")?;
+
+        let input_data = std::fs::read(&self.input_file)?;
+        let audio = decode_ima_adpcm_wav(&input_data).await?;
+
+        let output_data = match format {
+            ExportFormat::Wav => encode_pcm_wav(&audio).await?,
+            ExportFormat::Flac => encode_flac(&audio)?,
+        };
+        std::fs::write(&self.output, &output_data)?;
+
+        let mut table = Table::new();
+        table.load_preset(UTF8_FULL);
+
+        table.add_row(vec!["Output File", &self.output.display().to_string()]);
+        table.add_row(vec!["Artist", audio.metadata.artist.as_ref()]);
+        table.add_row(vec!["Title", audio.metadata.title.as_ref()]);
+        table.add_row(vec!["Album", audio.metadata.album.as_ref()]);
+        table.add_row(vec![
+            "Duration",
+            &format!(
+                "{:.2} s",
+                audio.samples.len() as f64 / audio.sample_rate as f64
+            ),
+        ]);
+        table.add_row(vec!["File Size", &format!("{} bytes", output_data.len())]);
+
+        println!("{}", table);
+
+        Ok(())
+    }
+}
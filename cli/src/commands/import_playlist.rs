@@ -0,0 +1,203 @@
+use clap::Args;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use transcoder::{compute_filename, decode_and_normalize, extract_track_number};
+
+use crate::client::{DeviceArgs, DeviceClient};
+use crate::commands::transcode::{album_order, collect_sources};
+use crate::import::read_playlist;
+use crate::layout::validate_name;
+
+#[derive(Args)]
+#[command(about = "Import a playlist or folder from this computer and associate it with a fob")]
+pub struct ImportPlaylistCommand {
+    #[command(flatten)]
+    pub device: DeviceArgs,
+    /// M3U/M3U8 or PLS playlist, or a folder of audio files
+    pub playlist: PathBuf,
+    /// Fob to play the playlist with
+    #[arg(long)]
+    pub fob: String,
+}
+
+/// Outcome of an import
+#[derive(Debug, PartialEq)]
+struct Imported {
+    /// Device file names in playlist order
+    files: Vec<String>,
+    /// Number of files that had to be transcoded and uploaded
+    uploaded: usize,
+}
+
+impl ImportPlaylistCommand {
+    pub async fn execute(self) -> Result<(), Box<dyn std::error::Error>> {
+        validate_name(&self.fob)?;
+
+        let sources = if self.playlist.is_dir() {
+            folder_sources(&self.playlist)?
+        } else {
+            read_playlist(&self.playlist)?
+        };
+        check_sources(&sources)?;
+
+        let client = self.device.client().await?;
+        let imported = import(&client, &sources, &self.fob).await?;
+
+        println!(
+            "Associated {} files with fob {} ({} uploaded, {} already on the device)",
+            imported.files.len(),
+            self.fob,
+            imported.uploaded,
+            sources.len() - imported.uploaded
+        );
+        Ok(())
+    }
+}
+
+/// Audio files in a folder and its subfolders, ordered by track number and then file name
+fn folder_sources(dir: &Path) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let mut sources = collect_sources(dir)?
+        .into_iter()
+        .map(|source| {
+            let track = extract_track_number(&std::fs::read(&source)?);
+            Ok((source, track))
+        })
+        .collect::<std::io::Result<Vec<_>>>()?;
+    sources.sort_by(|a, b| album_order((&a.0, a.1), (&b.0, b.1)));
+
+    Ok(sources.into_iter().map(|(source, _)| source).collect())
+}
+
+/// Fails before anything is uploaded if entries are missing
+fn check_sources(sources: &[PathBuf]) -> Result<(), Box<dyn std::error::Error>> {
+    if sources.is_empty() {
+        return Err("No audio files to import".into());
+    }
+
+    let missing: Vec<String> = sources
+        .iter()
+        .filter(|source| !source.is_file())
+        .map(|source| format!("  {}", source.display()))
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!(
+            "{} playlist entries not found:\n{}",
+            missing.len(),
+            missing.join("\n")
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Uploads the files missing on the device and associates all of them with `fob`, in order
+async fn import(
+    client: &DeviceClient,
+    sources: &[PathBuf],
+    fob: &str,
+) -> Result<Imported, Box<dyn std::error::Error>> {
+    let pb = ProgressBar::new(sources.len() as u64);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}")
+            .unwrap()
+            .progress_chars("#>-"),
+    );
+
+    let mut files = Vec::with_capacity(sources.len());
+    let mut seen = HashSet::new();
+    let mut uploaded = 0;
+    for source in sources {
+        pb.set_message(source.display().to_string());
+
+        // File names are derived from the source content, so tracks uploaded before can be
+        // recognized without transcoding them again
+        let input = std::fs::read(source)?;
+        let name = compute_filename(&input)
+            .trim_end_matches(".wav")
+            .to_string();
+
+        if seen.insert(name.clone()) && client.file_size(&name).await?.is_none() {
+            let result = decode_and_normalize(input.into(), |_, _| {})
+                .await
+                .map_err(|e| format!("Transcoding {}: {}", source.display(), e))?;
+
+            client.create_file(&name).await?;
+            if let Err(e) = client.upload(&name, &result.data, 0, |_| {}).await {
+                // Do not leave a truncated file behind that would be skipped next time
+                let _ = client.delete_file(&name).await;
+                return Err(e);
+            }
+            uploaded += 1;
+        }
+
+        files.push(name);
+        pb.inc(1);
+    }
+    pb.finish_and_clear();
+
+    client.associate(fob, &files).await?;
+    Ok(Imported { files, uploaded })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{TempDir, serve};
+    use crate::mock::{Card, MockDevice};
+
+    const TRACK: &[u8] = include_bytes!("../../../transcoder/src/test_data/test_22050hz.wav");
+
+    #[tokio::test]
+    async fn test_import_uploads_missing_files_in_order() {
+        let root = TempDir::new("import");
+        std::fs::create_dir_all(root.join("Music/Album")).unwrap();
+        std::fs::create_dir_all(root.join("Playlists")).unwrap();
+
+        let first = root.join("Music/Album/first.wav");
+        let mut other = TRACK.to_vec();
+        let index = other.len() - 100;
+        other[index] = other[index].wrapping_add(1);
+        std::fs::write(&first, TRACK).unwrap();
+        std::fs::write(root.join("Music/second.wav"), &other).unwrap();
+
+        let playlist = root.join("Playlists/mix.m3u8");
+        std::fs::write(
+            &playlist,
+            format!(
+                "#EXTM3U\n../Music/second.wav\n{}\n../Music/second.wav\n",
+                first.display()
+            ),
+        )
+        .unwrap();
+
+        let address = serve(MockDevice::new(Card::new(root.join("sdcard")), None).router()).await;
+        let client = DeviceClient::new(&address);
+
+        let sources = read_playlist(&playlist).unwrap();
+        check_sources(&sources).unwrap();
+        let imported = import(&client, &sources, "1a2b3c4d").await.unwrap();
+        assert_eq!(imported.uploaded, 2);
+        assert_eq!(imported.files[0], imported.files[2]);
+        assert_ne!(imported.files[0], imported.files[1]);
+
+        let associations = client.list_associations().await.unwrap();
+        let associated: Vec<_> = associations[0]
+            .files
+            .iter()
+            .map(|file| file.name.clone())
+            .collect();
+        assert_eq!(associated, imported.files);
+
+        // Importing a folder reuses the uploaded files
+        let sources = folder_sources(&root.join("Music")).unwrap();
+        assert_eq!(sources, vec![root.join("Music/second.wav"), first]);
+        let again = import(&client, &sources, "5e6f7a8b").await.unwrap();
+        assert_eq!(again.uploaded, 0);
+        assert_eq!(client.list_files().await.unwrap().len(), 2);
+
+        std::fs::write(&playlist, "missing.mp3\n").unwrap();
+        assert!(check_sources(&read_playlist(&playlist).unwrap()).is_err());
+    }
+}
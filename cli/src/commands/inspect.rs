@@ -0,0 +1,489 @@
+use clap::Args;
+use comfy_table::{Table, presets::UTF8_FULL};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::layout::{
+    CONFIG_FILE, DeviceConfig, FILE_DIR, FILE_EXT, PLAYLIST_DIR, PLAYLIST_EXT, WAV_HEADER_SIZE,
+    audio_file_from_path, duration_from_size, is_device_wav,
+};
+use crate::wav::{MAX_STEP_INDEX, Wav};
+
+/// Limits of the WiFi driver
+const MAX_SSID_LEN: usize = 32;
+const MAX_PASSWORD_LEN: usize = 64;
+
+#[derive(Args)]
+#[command(about = "Inspect a device WAV file or audit a mounted SD card")]
+pub struct InspectCommand {
+    /// WAV file or SD card mount point
+    pub path: PathBuf,
+}
+
+impl InspectCommand {
+    pub async fn execute(self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.path.is_dir() {
+            audit_card(&self.path)
+        } else {
+            inspect_file(&self.path)
+        }
+    }
+}
+
+fn inspect_file(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let data = std::fs::read(path)?;
+    let wav = Wav::parse(&data)?;
+
+    let mut chunks = Table::new();
+    chunks.load_preset(UTF8_FULL);
+    chunks.set_header(vec!["Chunk", "Offset", "Size", "Type"]);
+    for chunk in &wav.chunks {
+        chunks.add_row(vec![
+            format!("'{}'", chunk.id),
+            chunk.offset.to_string(),
+            chunk.size.to_string(),
+            chunk.list_type.clone().unwrap_or_default(),
+        ]);
+    }
+    println!("RIFF size {} (file size {})", wav.riff_size, data.len());
+    println!("{}", chunks);
+
+    let format = &wav.format;
+    let mut fmt = Table::new();
+    fmt.load_preset(UTF8_FULL);
+    fmt.add_row(vec![
+        "Format".to_string(),
+        format!("0x{:04x} ({})", format.format_tag, format.format_name()),
+    ]);
+    fmt.add_row(vec!["Channels".to_string(), format.channels.to_string()]);
+    fmt.add_row(vec![
+        "Sample rate".to_string(),
+        format.sample_rate.to_string(),
+    ]);
+    fmt.add_row(vec!["Byte rate".to_string(), format.byte_rate.to_string()]);
+    fmt.add_row(vec![
+        "Block align".to_string(),
+        format.block_align.to_string(),
+    ]);
+    fmt.add_row(vec![
+        "Bits per sample".to_string(),
+        format.bits_per_sample.to_string(),
+    ]);
+    if let Some(samples_per_block) = format.samples_per_block {
+        fmt.add_row(vec![
+            "Samples per block".to_string(),
+            samples_per_block.to_string(),
+        ]);
+    }
+    println!("{}", fmt);
+
+    let mut info = Table::new();
+    info.load_preset(UTF8_FULL);
+    info.set_header(vec!["Tag", "Value"]);
+    for (id, value) in &wav.info {
+        info.add_row(vec![id, value]);
+    }
+    println!("{}", info);
+
+    let mut summary = Table::new();
+    summary.load_preset(UTF8_FULL);
+    summary.add_row(vec![
+        "Duration".to_string(),
+        format!("{:.2} s", wav.duration()),
+    ]);
+    summary.add_row(vec![
+        "Duration (firmware)".to_string(),
+        format!("{} s", duration_from_size(data.len() as u64)),
+    ]);
+
+    if let Some(stats) = wav.block_stats(&data) {
+        summary.add_row(vec!["Blocks".to_string(), stats.blocks.to_string()]);
+        if stats.partial_block > 0 {
+            summary.add_row(vec![
+                "Partial last block".to_string(),
+                format!("{} bytes", stats.partial_block),
+            ]);
+        }
+        if stats.blocks > 0 {
+            summary.add_row(vec![
+                "Predictor".to_string(),
+                format!("{} .. {}", stats.predictor.0, stats.predictor.1),
+            ]);
+            summary.add_row(vec![
+                "Step index".to_string(),
+                format!(
+                    "{} .. {} (mean {:.1})",
+                    stats.step_index.0, stats.step_index.1, stats.mean_step_index
+                ),
+            ]);
+        }
+        summary.add_row(vec![
+            format!("Step index > {}", MAX_STEP_INDEX),
+            stats.invalid_step_index.to_string(),
+        ]);
+        summary.add_row(vec![
+            "Non-zero reserved byte".to_string(),
+            stats.nonzero_reserved.to_string(),
+        ]);
+    }
+    println!("{}", summary);
+
+    if !is_device_wav(&data) {
+        println!("Not in device format (44.1 kHz mono IMA ADPCM, 1024 byte blocks)");
+    } else if wav.data.start as u64 != WAV_HEADER_SIZE {
+        println!(
+            "Data starts at offset {}, the firmware expects {}",
+            wav.data.start, WAV_HEADER_SIZE
+        );
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Finding {
+    severity: Severity,
+    location: String,
+    message: String,
+}
+
+#[derive(Default)]
+struct Audit {
+    files: usize,
+    playlists: usize,
+    findings: Vec<Finding>,
+}
+
+impl Audit {
+    fn error(&mut self, location: impl Into<String>, message: impl Into<String>) {
+        self.push(Severity::Error, location.into(), message.into());
+    }
+
+    fn warning(&mut self, location: impl Into<String>, message: impl Into<String>) {
+        self.push(Severity::Warning, location.into(), message.into());
+    }
+
+    fn push(&mut self, severity: Severity, location: String, message: String) {
+        self.findings.push(Finding {
+            severity,
+            location,
+            message,
+        });
+    }
+}
+
+/// Finds a directory entry ignoring case, like FAT does
+fn find_entry(dir: &Path, name: &str) -> Option<PathBuf> {
+    std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .find(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .eq_ignore_ascii_case(name)
+        })
+        .map(|entry| entry.path())
+}
+
+/// Sorted file names of a directory
+fn list_files(dir: &Path) -> std::io::Result<Vec<String>> {
+    let mut names = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            names.push(entry.file_name().to_string_lossy().into_owned());
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// Base name as seen through the firmware's 8.3 short names, if usable
+fn short_basename(fname: &str, ext: &str) -> Option<String> {
+    let (base, extension) = fname.rsplit_once('.')?;
+    if !extension.eq_ignore_ascii_case(&ext[1..]) || base.is_empty() || base.len() > 8 {
+        return None;
+    }
+    base.chars()
+        .all(|c| c.is_ascii_alphanumeric() || "_-~!#$%&'()@^`{}".contains(c))
+        .then(|| base.to_ascii_uppercase())
+}
+
+fn audit(root: &Path) -> std::io::Result<Audit> {
+    let mut audit = Audit::default();
+
+    // Audio files
+    let mut files = BTreeSet::new();
+    match find_entry(root, FILE_DIR) {
+        Some(dir) => {
+            for fname in list_files(&dir)? {
+                let location = format!("{}/{}", FILE_DIR, fname);
+                let Some(name) = short_basename(&fname, FILE_EXT) else {
+                    if fname.to_ascii_uppercase().ends_with(FILE_EXT) {
+                        audit.error(
+                            location,
+                            "name is not a valid 8.3 name, playlists cannot reference it",
+                        );
+                    } else {
+                        audit.warning(location, "not a WAV file, ignored by the firmware");
+                    }
+                    continue;
+                };
+
+                let mut header = vec![0u8; WAV_HEADER_SIZE as usize];
+                let len = read_prefix(&dir.join(&fname), &mut header)?;
+                if !is_device_wav(&header[..len]) {
+                    audit.error(
+                        location,
+                        "not in device format (44.1 kHz mono IMA ADPCM), transcode it first",
+                    );
+                    continue;
+                }
+                if let Err(e) = Wav::parse(&header[..len]) {
+                    audit.error(location, e);
+                    continue;
+                }
+
+                audit.files += 1;
+                files.insert(name);
+            }
+        }
+        None => audit.error(FILE_DIR, "directory missing"),
+    }
+
+    // Playlists
+    let mut referenced = BTreeSet::new();
+    match find_entry(root, PLAYLIST_DIR) {
+        Some(dir) => {
+            for fname in list_files(&dir)? {
+                let location = format!("{}/{}", PLAYLIST_DIR, fname);
+                if short_basename(&fname, PLAYLIST_EXT).is_none() {
+                    if fname.to_ascii_uppercase().ends_with(PLAYLIST_EXT) {
+                        audit.error(location, "name is not a valid fob id, no fob can play it");
+                    } else {
+                        audit.warning(location, "not a playlist, ignored by the firmware");
+                    }
+                    continue;
+                }
+
+                audit.playlists += 1;
+                let content = std::fs::read(dir.join(&fname))?;
+                let Ok(content) = String::from_utf8(content) else {
+                    audit.error(location, "invalid UTF-8, the firmware cannot read it");
+                    continue;
+                };
+
+                let mut playable = 0;
+                for (number, line) in content.lines().enumerate() {
+                    let line = line.trim();
+                    if line.starts_with('#') || line.is_empty() {
+                        continue;
+                    }
+
+                    let line_location = format!("{}:{}", location, number + 1);
+                    match audio_file_from_path(line) {
+                        None => audit.error(
+                            line_location,
+                            format!(
+                                "'{}' is skipped, entries must look like ..\\{}\\NAME{}",
+                                line, FILE_DIR, FILE_EXT
+                            ),
+                        ),
+                        Some(name) if !files.contains(&name.to_ascii_uppercase()) => {
+                            audit.error(line_location, format!("'{}' does not exist", line))
+                        }
+                        Some(name) => {
+                            playable += 1;
+                            referenced.insert(name.to_ascii_uppercase());
+                        }
+                    }
+                }
+
+                if playable == 0 {
+                    audit.warning(location, "no playable entries, the fob does nothing");
+                }
+            }
+        }
+        None => audit.warning(PLAYLIST_DIR, "directory missing, no fob is associated"),
+    }
+
+    for orphan in files.difference(&referenced) {
+        audit.warning(
+            format!("{}/{}{}", FILE_DIR, orphan, FILE_EXT),
+            "not referenced by any playlist",
+        );
+    }
+
+    // Configuration
+    if let Some(path) = find_entry(root, CONFIG_FILE) {
+        let content = std::fs::read(path)?;
+        match serde_json::from_slice::<DeviceConfig>(&content) {
+            Ok(config) => {
+                if config.ssid.is_empty() || config.ssid.len() > MAX_SSID_LEN {
+                    audit.error(
+                        CONFIG_FILE,
+                        format!("SSID must be 1 to {} bytes", MAX_SSID_LEN),
+                    );
+                }
+                if config.password.len() > MAX_PASSWORD_LEN {
+                    audit.error(
+                        CONFIG_FILE,
+                        format!("password must be at most {} bytes", MAX_PASSWORD_LEN),
+                    );
+                }
+            }
+            Err(e) => audit.error(
+                CONFIG_FILE,
+                format!("invalid, the device falls back to access point mode: {}", e),
+            ),
+        }
+    }
+
+    audit
+        .findings
+        .sort_by(|a, b| a.severity.partial_cmp(&b.severity).unwrap());
+    Ok(audit)
+}
+
+fn read_prefix(path: &Path, buf: &mut [u8]) -> std::io::Result<usize> {
+    use std::io::Read;
+
+    let mut file = std::fs::File::open(path)?;
+    let mut len = 0;
+    while len < buf.len() {
+        match file.read(&mut buf[len..])? {
+            0 => break,
+            n => len += n,
+        }
+    }
+    Ok(len)
+}
+
+fn audit_card(root: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let audit = audit(root)?;
+
+    println!("{} audio files, {} playlists", audit.files, audit.playlists);
+    if audit.findings.is_empty() {
+        println!("No problems found");
+        return Ok(());
+    }
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_header(vec!["Severity", "Location", "Problem"]);
+    for finding in &audit.findings {
+        table.add_row(vec![
+            finding.severity.to_string(),
+            finding.location.clone(),
+            finding.message.clone(),
+        ]);
+    }
+    println!("{}", table);
+
+    let errors = audit
+        .findings
+        .iter()
+        .filter(|finding| finding.severity == Severity::Error)
+        .count();
+    if errors > 0 {
+        return Err(format!("{} errors found", errors).into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::TempDir;
+    use crate::wav::tests::device_wav;
+
+    fn card(name: &str) -> TempDir {
+        let root = TempDir::new(&format!("inspect-{}", name));
+        std::fs::create_dir_all(root.join(FILE_DIR)).unwrap();
+        std::fs::create_dir_all(root.join(PLAYLIST_DIR)).unwrap();
+        root
+    }
+
+    fn locations(audit: &Audit, severity: Severity) -> Vec<&str> {
+        audit
+            .findings
+            .iter()
+            .filter(|finding| finding.severity == severity)
+            .map(|finding| finding.location.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_clean_card() {
+        let root = card("clean");
+        std::fs::write(root.join("FILES/ABCDEFGH.WAV"), device_wav(1, "A")).unwrap();
+        std::fs::write(
+            root.join("FOBS/1A2B3C4D.M3U"),
+            "#EXTM3U\r\n#EXTINF:0,A - Song\r\n..\\FILES\\ABCDEFGH.WAV\r\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("config.jsn"),
+            r#"{"SSID":"home","PASSWORD":"secret"}"#,
+        )
+        .unwrap();
+
+        let audit = audit(&root).unwrap();
+        assert_eq!(audit.files, 1);
+        assert_eq!(audit.playlists, 1);
+        assert_eq!(audit.findings, vec![]);
+    }
+
+    #[test]
+    fn test_problems_are_reported() {
+        let root = card("broken");
+        std::fs::write(root.join("FILES/ABCDEFGH.WAV"), device_wav(1, "A")).unwrap();
+        std::fs::write(root.join("FILES/ORPHAN.WAV"), device_wav(1, "B")).unwrap();
+        std::fs::write(root.join("FILES/LONGNAME1.WAV"), device_wav(1, "C")).unwrap();
+        std::fs::write(root.join("FILES/RAW.WAV"), b"RIFF\0\0\0\0WAVEfmt ").unwrap();
+        std::fs::write(
+            root.join("FOBS/1A2B3C4D.M3U"),
+            "#EXTM3U\r\n\
+             ..\\FILES\\ABCDEFGH.WAV\r\n\
+             ../FILES/ABCDEFGH.WAV\r\n\
+             ..\\FILES\\MISSING.WAV\r\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("FOBS/DEADBEEF.M3U"), "#EXTM3U\r\n").unwrap();
+        std::fs::write(root.join("config.jsn"), r#"{"ssid":"home"}"#).unwrap();
+
+        let audit = audit(&root).unwrap();
+        assert_eq!(audit.files, 2);
+        assert_eq!(
+            locations(&audit, Severity::Error),
+            vec![
+                "FILES/LONGNAME1.WAV",
+                "FILES/RAW.WAV",
+                "FOBS/1A2B3C4D.M3U:3",
+                "FOBS/1A2B3C4D.M3U:4",
+                "config.jsn",
+            ]
+        );
+        assert_eq!(
+            locations(&audit, Severity::Warning),
+            vec!["FOBS/DEADBEEF.M3U", "FILES/ORPHAN.WAV"]
+        );
+    }
+}
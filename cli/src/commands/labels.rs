@@ -0,0 +1,219 @@
+use clap::Args;
+use std::path::{Path, PathBuf};
+use transcoder::{extract_cover_art, extract_metadata};
+
+use crate::client::{DeviceArgs, DeviceClient};
+use crate::labels::{Cover, Label, LabelSize, Paper, Track, layout, render_pdf, render_svg};
+use crate::manifest::Manifest;
+
+/// Image files next to the audio files that are used as cover art, without extension
+const COVER_NAMES: &[&str] = &["cover", "folder", "front", "albumart"];
+const COVER_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png"];
+
+#[derive(Args)]
+#[command(about = "Generate printable labels showing what each fob plays")]
+pub struct LabelsCommand {
+    #[command(flatten)]
+    pub device: DeviceArgs,
+    /// Read the fobs from a library manifest instead of the device
+    #[arg(long)]
+    pub manifest: Option<PathBuf>,
+    /// Output file (.svg or .pdf). SVG output gets one file per page.
+    #[arg(long)]
+    pub out: PathBuf,
+    /// Label size
+    #[arg(long, value_enum, default_value_t = LabelSize::Card)]
+    pub size: LabelSize,
+    /// Paper size
+    #[arg(long, value_enum, default_value_t = Paper::A4)]
+    pub paper: Paper,
+}
+
+impl LabelsCommand {
+    pub async fn execute(self) -> Result<(), Box<dyn std::error::Error>> {
+        let labels = match &self.manifest {
+            Some(manifest) => labels_from_manifest(&Manifest::load(manifest)?)?,
+            None => labels_from_device(&self.device.client().await?).await?,
+        };
+        if labels.is_empty() {
+            return Err("No fobs with playlists found".into());
+        }
+
+        let pages = layout(&labels, self.size, self.paper);
+        let written = write_pages(&self.out, &pages, self.paper)?;
+
+        println!(
+            "{} labels on {} pages written to {}",
+            labels.len(),
+            pages.len(),
+            written
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        Ok(())
+    }
+}
+
+/// Writes the pages in the format given by the extension of `out`, returns the written files
+fn write_pages(
+    out: &Path,
+    pages: &[crate::labels::Page],
+    paper: Paper,
+) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let extension = out
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("pdf") => {
+            std::fs::write(out, render_pdf(pages, paper))?;
+            Ok(vec![out.to_path_buf()])
+        }
+        Some("svg") => {
+            let stem = out.file_stem().unwrap_or_default().to_string_lossy();
+            let mut written = Vec::with_capacity(pages.len());
+            for (i, page) in pages.iter().enumerate() {
+                // cards.svg, cards-2.svg, ...
+                let path = match i {
+                    0 => out.to_path_buf(),
+                    _ => out.with_file_name(format!("{}-{}.svg", stem, i + 1)),
+                };
+                std::fs::write(&path, render_svg(page, paper))?;
+                written.push(path);
+            }
+            Ok(written)
+        }
+        _ => Err(format!(
+            "Unsupported output format: {} (expected .svg or .pdf)",
+            out.display()
+        )
+        .into()),
+    }
+}
+
+/// Titles and tracks from the files on the device. Device files carry no cover art.
+async fn labels_from_device(
+    client: &DeviceClient,
+) -> Result<Vec<Label>, Box<dyn std::error::Error>> {
+    let associations = client.list_associations().await?;
+    Ok(associations
+        .into_iter()
+        .filter(|association| !association.files.is_empty())
+        .map(|association| {
+            let tracks: Vec<Track> = association
+                .files
+                .into_iter()
+                .map(|file| Track {
+                    artist: file.metadata.artist,
+                    title: file.metadata.title,
+                    album: file.metadata.album,
+                })
+                .collect();
+            Label::from_tracks(&association.fob, None, &tracks, None)
+        })
+        .collect())
+}
+
+/// Titles, tracks and cover art from the source files of a manifest
+fn labels_from_manifest(manifest: &Manifest) -> Result<Vec<Label>, Box<dyn std::error::Error>> {
+    let mut labels = Vec::with_capacity(manifest.fobs.len());
+    for fob in manifest.fobs.iter().filter(|fob| !fob.files.is_empty()) {
+        let mut tracks = Vec::with_capacity(fob.files.len());
+        let mut cover = None;
+        for file in &fob.files {
+            let data =
+                std::fs::read(file).map_err(|e| format!("Reading {}: {}", file.display(), e))?;
+            let metadata = extract_metadata(&data);
+            tracks.push(Track {
+                artist: metadata.artist.to_string(),
+                title: metadata.title.to_string(),
+                album: metadata.album.to_string(),
+            });
+
+            if cover.is_none() {
+                cover = extract_cover_art(&data)
+                    .or_else(|| cover_file(file.parent()?))
+                    .and_then(|image| Cover::thumbnail(&image));
+            }
+        }
+        labels.push(Label::from_tracks(
+            &fob.id,
+            fob.title.clone(),
+            &tracks,
+            cover,
+        ));
+    }
+    Ok(labels)
+}
+
+/// Content of a cover image in `dir`, like `cover.jpg` or `Folder.png`
+fn cover_file(dir: &Path) -> Option<Vec<u8>> {
+    std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .find(|path| {
+            let stem = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_ascii_lowercase());
+            let extension = path
+                .extension()
+                .map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+            stem.is_some_and(|stem| COVER_NAMES.contains(&stem.as_str()))
+                && extension.is_some_and(|extension| COVER_EXTENSIONS.contains(&extension.as_str()))
+        })
+        .and_then(|path| std::fs::read(path).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::TempDir;
+
+    #[test]
+    fn test_labels_from_manifest_with_cover_file() {
+        let root = TempDir::new("labels");
+        std::fs::create_dir_all(root.join("album")).unwrap();
+        std::fs::copy(
+            "../transcoder/src/test_data/test_metadata.mp3",
+            root.join("album/01.mp3"),
+        )
+        .unwrap();
+        image::RgbImage::from_pixel(400, 300, image::Rgb([200, 30, 30]))
+            .save(root.join("album/Cover.PNG"))
+            .unwrap();
+
+        let manifest = Manifest::parse(
+            r#"
+            [[fobs]]
+            id = "1a2b3c4d"
+            files = ["album/01.mp3"]
+
+            [[fobs]]
+            id = "5e6f7a8b"
+            title = "Bedtime"
+            files = ["album/01.mp3", "album/01.mp3"]
+
+            [[fobs]]
+            id = "empty"
+            files = []
+            "#,
+            &root,
+        )
+        .unwrap();
+
+        let labels = labels_from_manifest(&manifest).unwrap();
+        assert_eq!(labels.len(), 2);
+        assert_eq!(labels[1].title, "Bedtime");
+        assert_eq!(labels[1].tracks.len(), 2);
+
+        let cover = labels[0].cover.as_ref().unwrap();
+        assert_eq!((cover.width, cover.height), (256, 192));
+        assert_eq!(&cover.jpeg[..2], &[0xff, 0xd8]);
+
+        let pages = layout(&labels, LabelSize::Fob, Paper::A4);
+        let written = write_pages(&root.join("labels.svg"), &pages, Paper::A4).unwrap();
+        assert_eq!(written, vec![root.join("labels.svg")]);
+        assert!(write_pages(&root.join("labels.png"), &pages, Paper::A4).is_err());
+    }
+}
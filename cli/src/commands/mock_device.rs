@@ -0,0 +1,45 @@
+use clap::Args;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+use crate::mock::{Card, MockDevice};
+
+#[derive(Args)]
+#[command(about = "Serve the device API from a local directory, without hardware")]
+pub struct MockDeviceCommand {
+    /// Directory with the SD card contents (FILES/, FOBS/, config.jsn)
+    #[arg(long, default_value = "./sdcard")]
+    pub root: PathBuf,
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    pub bind: SocketAddr,
+    /// Directory with the built web UI (e.g. firmware/public or web/dist/public)
+    #[arg(long)]
+    pub web_dir: Option<PathBuf>,
+}
+
+impl MockDeviceCommand {
+    pub async fn execute(self) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::create_dir_all(&self.root)?;
+        let listener = tokio::net::TcpListener::bind(self.bind).await?;
+
+        println!(
+            "Mock device serving {} on http://{}",
+            self.root.display(),
+            listener.local_addr()?
+        );
+        println!(
+            "Simulate fob scans with: curl -X POST http://{}/mock/fob -H 'Content-Type: application/json' -d '{{\"fob\":\"1a2b3c4d\"}}'",
+            listener.local_addr()?
+        );
+        println!(
+            "removals with: curl -X DELETE http://{}/mock/fob and play/pause presses with: curl -X POST http://{}/mock/play_pause",
+            listener.local_addr()?,
+            listener.local_addr()?
+        );
+
+        let device = MockDevice::new(Card::new(self.root), self.web_dir);
+        axum::serve(listener, device.router()).await?;
+        Ok(())
+    }
+}
@@ -1 +1,12 @@
+pub mod backup;
+pub mod discover;
+pub mod export;
+pub mod import_playlist;
+pub mod inspect;
+pub mod labels;
+pub mod mock_device;
+pub mod monitor;
+pub mod podcast;
+pub mod restore;
+pub mod sd_image;
 pub mod transcode;
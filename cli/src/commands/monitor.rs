@@ -0,0 +1,348 @@
+use clap::Args;
+use ratatui::{
+    DefaultTerminal, Frame,
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Gauge, List, ListItem, ListState, Paragraph},
+};
+use std::time::{Duration, Instant};
+
+use crate::client::{CurrentPlaylist, DeviceArgs, DeviceClient, PlaybackCommand, PlaybackStatus};
+use crate::layout::MAX_VOLUME;
+
+#[derive(Args)]
+#[command(about = "Live playback dashboard and remote control")]
+pub struct MonitorCommand {
+    #[command(flatten)]
+    pub device: DeviceArgs,
+    /// How often to poll the device, in milliseconds
+    #[arg(long, default_value_t = 1000)]
+    pub interval: u64,
+}
+
+/// Everything shown on the dashboard, as of the last poll
+#[derive(Default)]
+struct Snapshot {
+    device: String,
+    status: Option<PlaybackStatus>,
+    playlist: Option<CurrentPlaylist>,
+    last_fob: Option<String>,
+    error: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Action {
+    Command(PlaybackCommand),
+    Quit,
+}
+
+fn action_for_key(key: KeyCode) -> Option<Action> {
+    match key {
+        KeyCode::Char(' ') | KeyCode::Char('p') => Some(Action::Command(PlaybackCommand::Pause)),
+        KeyCode::Char('s') => Some(Action::Command(PlaybackCommand::Stop)),
+        KeyCode::Char('n') | KeyCode::Right => Some(Action::Command(PlaybackCommand::Next)),
+        KeyCode::Char('b') | KeyCode::Left => Some(Action::Command(PlaybackCommand::Previous)),
+        KeyCode::Char('+') | KeyCode::Char('=') | KeyCode::Up => {
+            Some(Action::Command(PlaybackCommand::VolumeUp))
+        }
+        KeyCode::Char('-') | KeyCode::Down => Some(Action::Command(PlaybackCommand::VolumeDown)),
+        KeyCode::Char('q') | KeyCode::Esc => Some(Action::Quit),
+        _ => None,
+    }
+}
+
+impl MonitorCommand {
+    pub async fn execute(self) -> Result<(), Box<dyn std::error::Error>> {
+        let client = self.device.client().await?;
+
+        let mut terminal = ratatui::init();
+        let result = self.run(&client, &mut terminal).await;
+        ratatui::restore();
+        result
+    }
+
+    async fn run(
+        &self,
+        client: &DeviceClient,
+        terminal: &mut DefaultTerminal,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let interval = Duration::from_millis(self.interval);
+        let mut snapshot = Snapshot {
+            device: client.base_url().to_string(),
+            ..Default::default()
+        };
+
+        loop {
+            // Keep showing the last known state while the device is unreachable
+            match tokio::try_join!(
+                client.playback_status(),
+                client.current_playlist(),
+                client.last_fob()
+            ) {
+                Ok((status, playlist, last_fob)) => {
+                    snapshot.status = Some(status);
+                    snapshot.playlist = playlist;
+                    snapshot.last_fob = last_fob;
+                    snapshot.error = None;
+                }
+                Err(e) => snapshot.error = Some(e.to_string()),
+            }
+            terminal.draw(|frame| render(frame, &snapshot))?;
+
+            let deadline = Instant::now() + interval;
+            while let Some(timeout) = deadline.checked_duration_since(Instant::now()) {
+                if !tokio::task::block_in_place(|| event::poll(timeout))? {
+                    break;
+                }
+                let Event::Key(key) = event::read()? else {
+                    continue;
+                };
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+
+                match action_for_key(key.code) {
+                    Some(Action::Quit) => return Ok(()),
+                    Some(Action::Command(command)) => {
+                        if let Err(e) = client.playback_command(command).await {
+                            snapshot.error = Some(e.to_string());
+                        }
+                        // Show the effect right away
+                        break;
+                    }
+                    None => {}
+                }
+            }
+        }
+    }
+}
+
+fn format_time(seconds: u32) -> String {
+    format!("{:02}:{:02}", seconds / 60, seconds % 60)
+}
+
+fn render(frame: &mut Frame, snapshot: &Snapshot) {
+    let [header, now_playing, progress, playlist, footer] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Length(3),
+        Constraint::Length(3),
+        Constraint::Min(3),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    let status = snapshot.status.as_ref();
+    let state = status.map_or("Unknown", |status| status.state.as_str());
+    let volume = status
+        .and_then(|status| Some((status.volume?, status.max_volume.unwrap_or(MAX_VOLUME))))
+        .map_or("n/a".to_string(), |(volume, max_volume)| {
+            format!("{}/{}", volume, max_volume)
+        });
+    let mut header_spans = vec![
+        Span::styled(state, Style::default().add_modifier(Modifier::BOLD)),
+        Span::raw(format!(
+            "   Playlist: {}   Volume: {}   Last fob: {}",
+            status
+                .and_then(|status| status.playlist_name.as_deref())
+                .unwrap_or("-"),
+            volume,
+            snapshot.last_fob.as_deref().unwrap_or("-"),
+        )),
+    ];
+    if let Some(fob) = status.and_then(|status| status.learning_fob.as_deref()) {
+        header_spans.push(Span::styled(
+            format!("   Learning fob {}", fob),
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+    let header_line = Line::from(header_spans);
+    frame.render_widget(
+        Paragraph::new(header_line).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" PhoniESP32 {} ", snapshot.device)),
+        ),
+        header,
+    );
+
+    let index = status.map_or(0, |status| status.index_in_playlist);
+    let current = snapshot
+        .playlist
+        .as_ref()
+        .and_then(|playlist| playlist.files.get(index));
+    let title = current.map_or("Nothing playing".to_string(), |file| {
+        format!(
+            "{} - {} ({})",
+            file.metadata.artist, file.metadata.title, file.metadata.album
+        )
+    });
+    frame.render_widget(
+        Paragraph::new(title).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Now playing "),
+        ),
+        now_playing,
+    );
+
+    let position = status.map_or(0, |status| status.position_seconds);
+    let duration = current.map_or(0, |file| file.metadata.duration);
+    let ratio = if duration > 0 {
+        (position as f64 / duration as f64).min(1.0)
+    } else {
+        0.0
+    };
+    frame.render_widget(
+        Gauge::default()
+            .block(Block::default().borders(Borders::ALL))
+            .gauge_style(Style::default().fg(Color::Cyan))
+            .ratio(ratio)
+            .label(format!(
+                "{} / {}",
+                format_time(position),
+                format_time(duration)
+            )),
+        progress,
+    );
+
+    let items: Vec<ListItem> = snapshot
+        .playlist
+        .iter()
+        .flat_map(|playlist| playlist.files.iter().enumerate())
+        .map(|(i, file)| {
+            ListItem::new(format!(
+                "{:>3}. {} - {}  [{}]  {}",
+                i + 1,
+                file.metadata.artist,
+                file.metadata.title,
+                format_time(file.metadata.duration),
+                file.file
+            ))
+        })
+        .collect();
+    let mut list_state = ListState::default().with_selected(current.map(|_| index));
+    frame.render_stateful_widget(
+        List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(format!(
+                " Playlist {}",
+                snapshot
+                    .playlist
+                    .as_ref()
+                    .map_or(String::new(), |playlist| format!("{} ", playlist.playlist_name))
+            )))
+            .highlight_style(
+                Style::default()
+                    .fg(Color::Black)
+                    .bg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol("▶ "),
+        playlist,
+        &mut list_state,
+    );
+
+    let footer_line = match &snapshot.error {
+        Some(error) => Line::styled(format!("Error: {}", error), Style::default().fg(Color::Red)),
+        None => Line::raw(
+            "space pause · s stop · ←/b previous · →/n next · ↑/+ volume up · ↓/- volume down · q quit",
+        ),
+    };
+    frame.render_widget(Paragraph::new(footer_line), footer);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{FileMetadata, PlaylistFile};
+    use ratatui::{Terminal, backend::TestBackend};
+
+    fn file(name: &str, title: &str, duration: u32) -> PlaylistFile {
+        PlaylistFile {
+            file: name.to_string(),
+            metadata: FileMetadata {
+                artist: "Artist".to_string(),
+                title: title.to_string(),
+                album: "Album".to_string(),
+                duration,
+            },
+        }
+    }
+
+    fn rendered(snapshot: &Snapshot) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(100, 16)).unwrap();
+        terminal.draw(|frame| render(frame, snapshot)).unwrap();
+
+        let buffer = terminal.backend().buffer();
+        (0..buffer.area.height)
+            .map(|y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_render_highlights_current_file() {
+        let snapshot = Snapshot {
+            device: "http://127.0.0.1:8080".to_string(),
+            status: Some(PlaybackStatus {
+                position_seconds: 65,
+                state: "Playing".to_string(),
+                index_in_playlist: 1,
+                playlist_name: Some("1A2B3C4D".to_string()),
+                volume: None,
+                max_volume: None,
+                learning_fob: None,
+            }),
+            playlist: Some(CurrentPlaylist {
+                playlist_name: "1A2B3C4D".to_string(),
+                files: vec![
+                    file("AAAAAAAA", "First", 30),
+                    file("BBBBBBBB", "Second", 130),
+                ],
+            }),
+            last_fob: Some("1a2b3c4d".to_string()),
+            error: None,
+        };
+
+        let lines = rendered(&snapshot);
+        assert!(
+            lines[1].contains("Playing   Playlist: 1A2B3C4D   Volume: n/a   Last fob: 1a2b3c4d")
+        );
+        assert!(lines[4].contains("Artist - Second (Album)"));
+        assert!(lines[7].contains("01:05 / 02:10"));
+        assert!(lines[9].contains("Playlist 1A2B3C4D"));
+        assert!(lines[10].contains("    1. Artist - First  [00:30]  AAAAAAAA"));
+        assert!(lines[11].contains("▶   2. Artist - Second  [02:10]  BBBBBBBB"));
+    }
+
+    #[test]
+    fn test_render_stopped_with_error() {
+        let snapshot = Snapshot {
+            error: Some("connection refused".to_string()),
+            ..Default::default()
+        };
+
+        let lines = rendered(&snapshot);
+        assert!(lines[1].contains("Unknown"));
+        assert!(lines[4].contains("Nothing playing"));
+        assert!(lines[15].contains("Error: connection refused"));
+    }
+
+    #[test]
+    fn test_key_bindings() {
+        assert_eq!(
+            action_for_key(KeyCode::Char(' ')),
+            Some(Action::Command(PlaybackCommand::Pause))
+        );
+        assert_eq!(
+            action_for_key(KeyCode::Up),
+            Some(Action::Command(PlaybackCommand::VolumeUp))
+        );
+        assert_eq!(action_for_key(KeyCode::Char('q')), Some(Action::Quit));
+        assert_eq!(action_for_key(KeyCode::Char('x')), None);
+    }
+}
@@ -0,0 +1,365 @@
+use audio_file_utils::metadata::Metadata;
+use clap::{Args, Subcommand};
+use comfy_table::{Table, presets::UTF8_FULL};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use transcoder::{Profile, decode_and_normalize_with_profile};
+
+use crate::client::{DeviceArgs, DeviceClient};
+use crate::commands::transcode::update_metadata_in_buffer;
+use crate::layout::validate_name;
+use crate::podcast::{Subscription, Subscriptions, SyncedEpisode, parse_feed, truncate};
+
+/// Maximum length of INFO chunk values
+const MAX_TAG_LENGTH: usize = 31;
+
+#[derive(Args)]
+#[command(about = "Subscribe fobs to podcasts and sync the newest episodes")]
+pub struct PodcastCommand {
+    /// Subscription file (defaults to $XDG_CONFIG_HOME/pecli/podcasts.json)
+    #[arg(long, global = true)]
+    pub subscriptions: Option<PathBuf>,
+    #[command(subcommand)]
+    pub action: PodcastAction,
+}
+
+#[derive(Subcommand)]
+pub enum PodcastAction {
+    /// Subscribe a fob to a feed
+    Add {
+        /// URL of the RSS feed
+        feed_url: String,
+        /// Fob to play the episodes with
+        #[arg(long)]
+        fob: String,
+        /// Number of episodes to keep on the device
+        #[arg(long, default_value_t = 5)]
+        keep: usize,
+    },
+    /// Remove a subscription, files on the device are kept
+    Remove {
+        #[arg(long)]
+        fob: String,
+    },
+    /// List subscriptions
+    List,
+    /// Download new episodes, upload them and rewrite the playlists of subscribed fobs
+    Sync {
+        #[command(flatten)]
+        device: DeviceArgs,
+    },
+}
+
+impl PodcastCommand {
+    pub async fn execute(self) -> Result<(), Box<dyn std::error::Error>> {
+        let path = self
+            .subscriptions
+            .unwrap_or_else(Subscriptions::default_path);
+        let mut subscriptions = Subscriptions::load(&path)?;
+
+        match self.action {
+            PodcastAction::Add {
+                feed_url,
+                fob,
+                keep,
+            } => {
+                validate_name(&fob)?;
+                if keep == 0 {
+                    return Err("--keep must be at least 1".into());
+                }
+                if subscriptions.find(&fob).is_some() {
+                    return Err(format!("Fob {} already has a subscription", fob).into());
+                }
+
+                subscriptions.subscriptions.push(Subscription {
+                    feed_url,
+                    fob,
+                    keep,
+                    episodes: Vec::new(),
+                });
+                subscriptions.save(&path)?;
+                println!("Subscription added, run `pecli podcast sync` to download episodes");
+            }
+            PodcastAction::Remove { fob } => {
+                let count = subscriptions.subscriptions.len();
+                subscriptions
+                    .subscriptions
+                    .retain(|subscription| !subscription.fob.eq_ignore_ascii_case(&fob));
+                if subscriptions.subscriptions.len() == count {
+                    return Err(format!("No subscription for fob {}", fob).into());
+                }
+                subscriptions.save(&path)?;
+            }
+            PodcastAction::List => {
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL);
+                table.set_header(vec!["Fob", "Feed", "Keep", "Episodes on device"]);
+                for subscription in &subscriptions.subscriptions {
+                    table.add_row(vec![
+                        subscription.fob.clone(),
+                        subscription.feed_url.clone(),
+                        subscription.keep.to_string(),
+                        subscription.episodes.len().to_string(),
+                    ]);
+                }
+                println!("{}", table);
+            }
+            PodcastAction::Sync { device } => {
+                let client = device.client().await?;
+                sync(&client, &mut subscriptions, &path).await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Syncs all subscriptions, continuing with the next one if a feed fails
+async fn sync(
+    client: &DeviceClient,
+    subscriptions: &mut Subscriptions,
+    path: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let http = reqwest::Client::new();
+    let mut failed = 0;
+
+    for index in 0..subscriptions.subscriptions.len() {
+        let subscription = &subscriptions.subscriptions[index];
+        println!("Syncing {} ({})", subscription.fob, subscription.feed_url);
+
+        let episodes = match sync_subscription(client, &http, subscription).await {
+            Ok(episodes) => episodes,
+            Err(e) => {
+                eprintln!("{}: {}", subscription.fob, e);
+                failed += 1;
+                continue;
+            }
+        };
+
+        let current: HashSet<_> = episodes.iter().map(|episode| &episode.file).collect();
+        let stale: Vec<String> = subscription
+            .episodes
+            .iter()
+            .map(|episode| episode.file.clone())
+            .filter(|file| !current.contains(file))
+            .collect();
+
+        subscriptions.subscriptions[index].episodes = episodes;
+        subscriptions.save(path)?;
+
+        // Files may also be used by other fobs
+        let referenced: HashSet<String> = client
+            .list_associations()
+            .await?
+            .into_iter()
+            .flat_map(|association| association.files)
+            .map(|file| file.name.to_ascii_uppercase())
+            .collect();
+        for file in stale {
+            if !referenced.contains(&file.to_ascii_uppercase()) {
+                client.delete_file(&file).await?;
+                println!("  Deleted old episode {}", file);
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(format!("{} subscriptions failed to sync", failed).into());
+    }
+    Ok(())
+}
+
+/// Makes sure the newest episodes are on the device and the fob plays them, newest first
+async fn sync_subscription(
+    client: &DeviceClient,
+    http: &reqwest::Client,
+    subscription: &Subscription,
+) -> Result<Vec<SyncedEpisode>, Box<dyn std::error::Error>> {
+    let xml = http
+        .get(&subscription.feed_url)
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    let feed = parse_feed(&xml)?;
+    if feed.episodes.is_empty() {
+        return Err("Feed has no audio episodes".into());
+    }
+
+    let mut synced = Vec::new();
+    for episode in feed.episodes.iter().take(subscription.keep) {
+        let previous = subscription
+            .episodes
+            .iter()
+            .find(|synced| synced.guid == episode.guid);
+        if let Some(previous) = previous
+            && client.file_size(&previous.file).await?.is_some()
+        {
+            synced.push(previous.clone());
+            continue;
+        }
+
+        println!("  Downloading {}", episode.title);
+        let input = http
+            .get(&episode.url)
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+        let mut result =
+            decode_and_normalize_with_profile(input.to_vec().into(), Profile::Speech, |_, _| {})
+                .await?;
+
+        let artist = feed.author.as_deref().unwrap_or(&feed.title);
+        let metadata = Metadata {
+            artist: truncate(artist, MAX_TAG_LENGTH)
+                .try_into()
+                .unwrap_or_default(),
+            title: truncate(&episode.title, MAX_TAG_LENGTH)
+                .try_into()
+                .unwrap_or_default(),
+            album: truncate(&feed.title, MAX_TAG_LENGTH)
+                .try_into()
+                .unwrap_or_default(),
+        };
+        update_metadata_in_buffer(&mut result.data, &metadata).await?;
+
+        let name = result.filename.trim_end_matches(".wav").to_string();
+        if client.file_size(&name).await? != Some(result.data.len() as u64) {
+            client.create_file(&name).await?;
+            client.upload(&name, &result.data, 0, |_| {}).await?;
+        }
+        println!("  Uploaded {} as {}", episode.title, name);
+
+        synced.push(SyncedEpisode {
+            guid: episode.guid.clone(),
+            title: episode.title.clone(),
+            file: name,
+        });
+    }
+
+    let files: Vec<String> = synced.iter().map(|episode| episode.file.clone()).collect();
+    client.associate(&subscription.fob, &files).await?;
+    Ok(synced)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{TempDir, serve};
+    use crate::mock::{Card, MockDevice};
+    use crate::podcast::tests::feed_xml;
+    use axum::{Router, extract::State, routing::get};
+    use std::sync::{Arc, Mutex};
+
+    const EPISODE: &[u8] = include_bytes!("../../../transcoder/src/test_data/test_22050hz.wav");
+
+    /// Same audio with different content, so each episode gets its own file name
+    fn episode_audio(n: usize) -> Vec<u8> {
+        let mut audio = EPISODE.to_vec();
+        let index = audio.len() - 100;
+        audio[index] = audio[index].wrapping_add(n as u8);
+        audio
+    }
+
+    #[tokio::test]
+    async fn test_sync_keeps_newest_episodes() {
+        let root = TempDir::new("podcast");
+        let device = serve(MockDevice::new(Card::new(root.join("sdcard")), None).router()).await;
+        let client = DeviceClient::new(&device);
+
+        // Fixture feed server, the feed can be changed between syncs
+        let feed = Arc::new(Mutex::new(String::new()));
+        let feed_server = serve(
+            Router::new()
+                .route(
+                    "/feed.xml",
+                    get(|State(feed): State<Arc<Mutex<String>>>| async move {
+                        feed.lock().unwrap().clone()
+                    }),
+                )
+                .route(
+                    "/episode/{n}",
+                    get(
+                        |axum::extract::Path(n): axum::extract::Path<usize>| async move {
+                            episode_audio(n)
+                        },
+                    ),
+                )
+                .with_state(feed.clone()),
+        )
+        .await;
+        let episode_url = |n: usize| format!("http://{}/episode/{}", feed_server, n);
+
+        *feed.lock().unwrap() = feed_xml(&[
+            (
+                "ep1",
+                "Episode 1",
+                "Mon, 06 Jan 2025 08:00:00 +0000",
+                &episode_url(1),
+            ),
+            (
+                "ep2",
+                "Episode 2 with a very long title",
+                "Mon, 13 Jan 2025 08:00:00 +0000",
+                &episode_url(2),
+            ),
+        ]);
+
+        let path = root.join("podcasts.json");
+        let mut subscriptions = Subscriptions {
+            subscriptions: vec![Subscription {
+                feed_url: format!("http://{}/feed.xml", feed_server),
+                fob: "1a2b3c4d".to_string(),
+                keep: 1,
+                episodes: Vec::new(),
+            }],
+        };
+        sync(&client, &mut subscriptions, &path).await.unwrap();
+
+        let first = subscriptions.subscriptions[0].episodes.clone();
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].guid, "ep2");
+        let files = client.list_files().await.unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].metadata.artist, "Story Teller");
+        assert_eq!(files[0].metadata.title, "Episode 2 with a very long titl");
+        assert_eq!(files[0].metadata.album, "Bedtime Stories");
+
+        // A new episode replaces the old one on the device and in the playlist
+        *feed.lock().unwrap() = feed_xml(&[
+            (
+                "ep3",
+                "Episode 3",
+                "Mon, 20 Jan 2025 08:00:00 +0000",
+                &episode_url(3),
+            ),
+            (
+                "ep2",
+                "Episode 2 with a very long title",
+                "Mon, 13 Jan 2025 08:00:00 +0000",
+                &episode_url(2),
+            ),
+        ]);
+        let mut subscriptions = Subscriptions::load(&path).unwrap();
+        sync(&client, &mut subscriptions, &path).await.unwrap();
+
+        let second = &subscriptions.subscriptions[0].episodes;
+        assert_eq!(second[0].guid, "ep3");
+        assert_ne!(second[0].file, first[0].file);
+        let files: Vec<_> = client
+            .list_files()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|file| file.name)
+            .collect();
+        assert_eq!(files, vec![second[0].file.clone()]);
+
+        let associations = client.list_associations().await.unwrap();
+        assert_eq!(associations[0].files.len(), 1);
+        assert_eq!(associations[0].files[0].name, second[0].file);
+    }
+}
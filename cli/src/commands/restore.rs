@@ -0,0 +1,136 @@
+use clap::Args;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+
+use crate::archive;
+use crate::client::DeviceArgs;
+
+#[derive(Args)]
+#[command(about = "Restore a backup archive to a device")]
+pub struct RestoreCommand {
+    #[command(flatten)]
+    pub device: DeviceArgs,
+    /// Backup archive created with `pecli backup`
+    pub archive: PathBuf,
+    /// WiFi password to restore the WiFi configuration with (not stored in backups)
+    #[arg(long)]
+    pub wifi_password: Option<String>,
+}
+
+/// How to continue uploading a file, given its size on the device
+#[derive(Debug, PartialEq)]
+enum UploadAction {
+    /// The file is already complete
+    Skip,
+    /// The file was partially uploaded, continue at the given offset
+    Resume(u64),
+    /// The file is missing or cannot be resumed
+    Create,
+}
+
+impl UploadAction {
+    /// File names are derived from the file content, so a shorter file with the same name
+    /// is an interrupted upload of the same data
+    fn for_sizes(remote_size: Option<u64>, size: u64) -> Self {
+        match remote_size {
+            Some(remote) if remote == size => UploadAction::Skip,
+            Some(remote) if remote > 0 && remote < size => UploadAction::Resume(remote),
+            _ => UploadAction::Create,
+        }
+    }
+}
+
+impl RestoreCommand {
+    pub async fn execute(self) -> Result<(), Box<dyn std::error::Error>> {
+        let client = self.device.client().await?;
+
+        let manifest = archive::read_manifest(BufReader::new(File::open(&self.archive)?))?;
+        let total_size = manifest.files.iter().map(|file| file.size).sum();
+
+        let pb = ProgressBar::new(total_size);
+        pb.set_style(
+            ProgressStyle::default_bar()
+                .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} {msg}")
+                .unwrap()
+                .progress_chars("#>-"),
+        );
+
+        let mut reader = archive::open(BufReader::new(File::open(&self.archive)?));
+        let mut skipped = 0;
+        for result in archive::files(&mut reader, &manifest)? {
+            let (file, data) = result?;
+            pb.set_message(file.name.clone());
+
+            let action = UploadAction::for_sizes(client.file_size(&file.name).await?, file.size);
+            let offset = match action {
+                UploadAction::Skip => {
+                    skipped += 1;
+                    pb.inc(file.size);
+                    continue;
+                }
+                UploadAction::Resume(offset) => offset,
+                UploadAction::Create => {
+                    client.create_file(&file.name).await?;
+                    0
+                }
+            };
+
+            pb.inc(offset);
+            client
+                .upload(&file.name, &data, offset, |n| pb.inc(n))
+                .await?;
+        }
+        pb.finish_with_message("done");
+
+        for association in &manifest.associations {
+            client
+                .associate(&association.fob, &association.files)
+                .await?;
+        }
+
+        println!(
+            "Restored {} files ({} already present) and {} associations",
+            manifest.files.len(),
+            skipped,
+            manifest.associations.len()
+        );
+
+        match (manifest.config, self.wifi_password) {
+            (Some(config), Some(password)) => {
+                client
+                    .put_config(&config.into_device_config(password))
+                    .await?;
+                println!("Restored WiFi configuration");
+            }
+            (Some(config), None) => println!(
+                "WiFi configuration for '{}' not restored, pass --wifi-password to restore it",
+                config.ssid
+            ),
+            (None, _) => {}
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upload_action() {
+        assert_eq!(UploadAction::for_sizes(None, 100), UploadAction::Create);
+        assert_eq!(UploadAction::for_sizes(Some(0), 100), UploadAction::Create);
+        assert_eq!(
+            UploadAction::for_sizes(Some(40), 100),
+            UploadAction::Resume(40)
+        );
+        assert_eq!(UploadAction::for_sizes(Some(100), 100), UploadAction::Skip);
+        assert_eq!(
+            UploadAction::for_sizes(Some(120), 100),
+            UploadAction::Create
+        );
+    }
+}
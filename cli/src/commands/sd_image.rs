@@ -0,0 +1,349 @@
+use clap::Args;
+use fatfs::{FatType, FileSystem, FormatVolumeOptions, FsOptions};
+use fscommon::{BufStream, StreamSlice};
+use std::collections::HashMap;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::layout::{
+    CONFIG_FILE, DeviceConfig, FILE_DIR, FILE_EXT, PLAYLIST_DIR, PLAYLIST_EXT, PlaylistEntry,
+    PlaylistMode, duration_from_size, is_device_wav, render_playlist,
+};
+use crate::manifest::Manifest;
+
+const SECTOR_SIZE: u64 = 512;
+/// First partition starts at 1 MiB, like most partitioning tools do
+const PARTITION_START_LBA: u32 = 2048;
+const PARTITION_TYPE_FAT32_LBA: u8 = 0x0C;
+const VOLUME_LABEL: [u8; 11] = *b"PHONIESP32 ";
+
+#[derive(Args)]
+#[command(about = "Build a ready-to-flash SD card image from a library manifest")]
+pub struct SdImageCommand {
+    /// Library manifest (TOML) with Wi-Fi credentials and fob playlists
+    #[arg(long)]
+    pub manifest: PathBuf,
+    /// Image size, e.g. 512M or 4G
+    #[arg(long, value_parser = parse_size)]
+    pub size: u64,
+    /// Put the filesystem into an MBR partition instead of using the raw device
+    #[arg(long)]
+    pub mbr: bool,
+    /// Output image path
+    pub output: PathBuf,
+}
+
+impl SdImageCommand {
+    pub async fn execute(self) -> Result<(), Box<dyn std::error::Error>> {
+        use comfy_table::{Table, presets::UTF8_FULL};
+
+        let manifest = Manifest::load(&self.manifest)?;
+
+        let mut image = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&self.output)?;
+        image.set_len(self.size)?;
+
+        let builder = SdImageBuilder::format(&mut image, self.size, self.mbr)?;
+
+        let mut entries: HashMap<&Path, PlaylistEntry> = HashMap::new();
+        for source in manifest.sources() {
+            let entry = builder.add_audio_file(source).await?;
+            entries.insert(source, entry);
+        }
+
+        let mut table = Table::new();
+        table.load_preset(UTF8_FULL);
+        table.set_header(vec!["Fob", "Files"]);
+
+        for fob in &manifest.fobs {
+            let playlist: Vec<PlaylistEntry> = fob
+                .files
+                .iter()
+                .map(|f| entries[f.as_path()].clone())
+                .collect();
+            builder.add_playlist(&fob.id, &playlist)?;
+            table.add_row(vec![fob.id.clone(), playlist.len().to_string()]);
+        }
+
+        if let Some(ref config) = manifest.wifi {
+            builder.set_config(config)?;
+        }
+
+        builder.finish()?;
+        image.sync_all()?;
+
+        println!("{}", table);
+        println!(
+            "Wrote {} with {} files and {} fobs",
+            self.output.display(),
+            entries.len(),
+            manifest.fobs.len()
+        );
+
+        Ok(())
+    }
+}
+
+type ImageStream<IO> = BufStream<StreamSlice<IO>>;
+
+pub struct SdImageBuilder<IO: Read + Write + Seek> {
+    fs: FileSystem<ImageStream<IO>>,
+}
+
+impl<IO: Read + Write + Seek> SdImageBuilder<IO> {
+    /// Format `disk` as FAT32, optionally inside an MBR partition spanning the rest of the disk
+    pub fn format(mut disk: IO, size: u64, mbr: bool) -> io::Result<Self> {
+        let start = if mbr {
+            write_mbr(&mut disk, size)?;
+            PARTITION_START_LBA as u64 * SECTOR_SIZE
+        } else {
+            0
+        };
+
+        let end = size / SECTOR_SIZE * SECTOR_SIZE;
+        let mut volume = StreamSlice::new(disk, start, end)?;
+        fatfs::format_volume(
+            &mut volume,
+            FormatVolumeOptions::new()
+                .fat_type(FatType::Fat32)
+                .volume_label(VOLUME_LABEL),
+        )?;
+        volume.seek(SeekFrom::Start(0))?;
+
+        let fs = FileSystem::new(BufStream::new(volume), FsOptions::new())?;
+        fs.root_dir().create_dir(FILE_DIR)?;
+        fs.root_dir().create_dir(PLAYLIST_DIR)?;
+
+        Ok(Self { fs })
+    }
+
+    /// Transcode `source` (unless it already is in device format) and store it under `FILES/`
+    pub async fn add_audio_file(
+        &self,
+        source: &Path,
+    ) -> Result<PlaylistEntry, Box<dyn std::error::Error>> {
+        use indicatif::{ProgressBar, ProgressStyle};
+        use transcoder::{compute_filename, decode_and_normalize};
+
+        let input_data =
+            std::fs::read(source).map_err(|e| format!("Reading {}: {}", source.display(), e))?;
+
+        let data = if is_device_wav(&input_data) {
+            input_data.into_boxed_slice()
+        } else {
+            let pb = ProgressBar::new(100);
+            pb.set_style(
+                ProgressStyle::default_bar()
+                    .template("{spinner:.green} [{bar:40.cyan/blue}] {percent:>3}% {msg}")
+                    .unwrap()
+                    .progress_chars("#>-"),
+            );
+            pb.set_message(source.display().to_string());
+
+            let result = decode_and_normalize(input_data.into(), |current, _total| {
+                pb.set_position(current as u64);
+            })
+            .await
+            .map_err(|e| format!("Transcoding {}: {}", source.display(), e))?;
+            pb.finish();
+
+            result.data
+        };
+
+        let filename = compute_filename(&data);
+        let name = filename.trim_end_matches(".wav").to_string();
+        let metadata = audio_file_utils::metadata::extract_metadata(&mut &data[..])
+            .await
+            .unwrap_or_default();
+
+        self.write_file(FILE_DIR, &format!("{}{}", name, FILE_EXT), &data)?;
+
+        Ok(PlaylistEntry {
+            name,
+            metadata,
+            duration: duration_from_size(data.len() as u64),
+        })
+    }
+
+    /// Write `FOBS/<fob>.M3U` in the format used by the firmware
+    pub fn add_playlist(&self, fob: &str, entries: &[PlaylistEntry]) -> io::Result<()> {
+        self.write_file(
+            PLAYLIST_DIR,
+            &format!("{}{}", fob, PLAYLIST_EXT),
+            render_playlist(entries, &PlaylistMode::default()).as_bytes(),
+        )
+    }
+
+    pub fn set_config(&self, config: &DeviceConfig) -> io::Result<()> {
+        let mut file = self.fs.root_dir().create_file(CONFIG_FILE)?;
+        file.truncate()?;
+        file.write_all(&serde_json::to_vec(config)?)?;
+        Ok(())
+    }
+
+    pub fn finish(self) -> io::Result<()> {
+        self.fs.unmount()
+    }
+
+    fn write_file(&self, dir: &str, name: &str, data: &[u8]) -> io::Result<()> {
+        let dir = self.fs.root_dir().open_dir(dir)?;
+        let mut file = dir.create_file(name)?;
+        file.truncate()?;
+        file.write_all(data)?;
+        file.flush()
+    }
+}
+
+/// Write an MBR with a single FAT32 (LBA) partition starting at 1 MiB,
+/// matching what `PartitionSlice` in the firmware looks for
+fn write_mbr<IO: Write + Seek>(disk: &mut IO, size: u64) -> io::Result<()> {
+    let total_sectors = size / SECTOR_SIZE;
+    if total_sectors <= PARTITION_START_LBA as u64 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "image too small for a partition table",
+        ));
+    }
+    let sector_count = u32::try_from(total_sectors - PARTITION_START_LBA as u64)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "image too large for MBR"))?;
+
+    let mut mbr = [0u8; SECTOR_SIZE as usize];
+    let entry = &mut mbr[0x1BE..0x1BE + 16];
+    entry[0] = 0x00; // not bootable
+    entry[1..4].copy_from_slice(&[0xFE, 0xFF, 0xFF]); // CHS start: use LBA
+    entry[4] = PARTITION_TYPE_FAT32_LBA;
+    entry[5..8].copy_from_slice(&[0xFE, 0xFF, 0xFF]); // CHS end: use LBA
+    entry[8..12].copy_from_slice(&PARTITION_START_LBA.to_le_bytes());
+    entry[12..16].copy_from_slice(&sector_count.to_le_bytes());
+    mbr[0x1FE..].copy_from_slice(&0xAA55u16.to_le_bytes());
+
+    disk.seek(SeekFrom::Start(0))?;
+    disk.write_all(&mbr)?;
+    disk.seek(SeekFrom::Start(0))?;
+    Ok(())
+}
+
+/// Parse sizes like `4G`, `512M`, `64MiB` or plain bytes
+fn parse_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("Invalid size '{}'", s))?;
+    let multiplier: u64 = match unit.to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        _ => return Err(format!("Unknown size unit '{}'", unit)),
+    };
+
+    number
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("Size '{}' is too large", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fatfs::ReadWriteSeek;
+    use std::io::Cursor;
+
+    const IMAGE_SIZE: u64 = 40 << 20;
+
+    fn read_file<IO: ReadWriteSeek>(fs: &FileSystem<IO>, path: &str) -> Vec<u8> {
+        let mut data = Vec::new();
+        fs.root_dir()
+            .open_file(path)
+            .unwrap()
+            .read_to_end(&mut data)
+            .unwrap();
+        data
+    }
+
+    async fn build_image(mbr: bool) -> (Cursor<Vec<u8>>, PlaylistEntry) {
+        let source = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../transcoder/src/test_data/test_22050hz.wav");
+
+        let mut disk = Cursor::new(vec![0u8; IMAGE_SIZE as usize]);
+        let builder = SdImageBuilder::format(&mut disk, IMAGE_SIZE, mbr).unwrap();
+        let entry = builder.add_audio_file(&source).await.unwrap();
+        builder
+            .add_playlist("1a2b3c4d", std::slice::from_ref(&entry))
+            .unwrap();
+        builder
+            .set_config(&DeviceConfig {
+                ssid: "home".to_string(),
+                password: "secret".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        builder.finish().unwrap();
+        disk.set_position(0);
+
+        (disk, entry)
+    }
+
+    #[tokio::test]
+    async fn test_image_contains_files_playlists_and_config() {
+        let (disk, entry) = build_image(false).await;
+        let fs = FileSystem::new(disk, FsOptions::new()).unwrap();
+        assert_eq!(fs.fat_type(), FatType::Fat32);
+
+        let wav = read_file(&fs, &format!("FILES/{}.WAV", entry.name));
+        assert!(is_device_wav(&wav));
+        assert_eq!(entry.name.len(), 8);
+
+        let m3u = String::from_utf8(read_file(&fs, "FOBS/1a2b3c4d.M3U")).unwrap();
+        assert_eq!(m3u, render_playlist(&[entry], &PlaylistMode::default()));
+
+        let config: DeviceConfig = serde_json::from_slice(&read_file(&fs, "config.jsn")).unwrap();
+        assert_eq!(config.ssid, "home");
+        assert_eq!(config.password, "secret");
+    }
+
+    #[tokio::test]
+    async fn test_image_with_mbr_partition() {
+        let (disk, entry) = build_image(true).await;
+        let data = disk.into_inner();
+
+        assert_eq!(&data[0x1FE..0x200], &[0x55, 0xAA]);
+        let entry_bytes = &data[0x1BE..0x1BE + 16];
+        assert_eq!(entry_bytes[4], PARTITION_TYPE_FAT32_LBA);
+        let start_lba = u32::from_le_bytes(entry_bytes[8..12].try_into().unwrap());
+        let sectors = u32::from_le_bytes(entry_bytes[12..16].try_into().unwrap());
+        assert_eq!(start_lba, PARTITION_START_LBA);
+        assert_eq!(
+            sectors as u64,
+            IMAGE_SIZE / SECTOR_SIZE - PARTITION_START_LBA as u64
+        );
+
+        let partition = StreamSlice::new(
+            Cursor::new(data),
+            start_lba as u64 * SECTOR_SIZE,
+            IMAGE_SIZE,
+        )
+        .unwrap();
+        let fs = FileSystem::new(partition, FsOptions::new()).unwrap();
+        let m3u = read_file(&fs, "FOBS/1a2b3c4d.M3U");
+        assert_eq!(
+            m3u,
+            render_playlist(&[entry], &PlaylistMode::default()).into_bytes()
+        );
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("4G").unwrap(), 4 << 30);
+        assert_eq!(parse_size("512M").unwrap(), 512 << 20);
+        assert_eq!(parse_size("64MiB").unwrap(), 64 << 20);
+        assert_eq!(parse_size("1000").unwrap(), 1000);
+        assert!(parse_size("4T").is_err());
+        assert!(parse_size("G").is_err());
+    }
+}
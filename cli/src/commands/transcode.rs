@@ -1,12 +1,31 @@
 use clap::Args;
+use serde::Serialize;
 use std::io::{Cursor, Read, Seek, SeekFrom};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::client::FileMetadata;
+use crate::layout::duration_from_size;
+
+/// Extensions of the formats the transcoder can decode
+const AUDIO_EXTENSIONS: &[&str] = &["aac", "flac", "mp3", "oga", "ogg", "wav"];
+const MANIFEST_NAME: &str = "manifest.json";
 
 #[derive(Args)]
-#[command(about = "Transcode an audio file")]
+#[command(about = "Transcode an audio file or a directory tree")]
 pub struct TranscodeCommand {
-    /// Input audio file path
+    /// Input audio file path, or directory with --recursive
     pub input_file: PathBuf,
+    /// Transcode all audio files below the input directory
+    #[arg(short, long)]
+    pub recursive: bool,
+    /// Output directory
+    #[arg(long, default_value = ".")]
+    pub out: PathBuf,
+    /// Number of files to transcode in parallel (defaults to the number of CPU cores)
+    #[arg(short, long)]
+    pub jobs: Option<usize>,
     /// Override artist metadata
     #[arg(long)]
     pub artist: Option<String>,
@@ -14,12 +33,32 @@ pub struct TranscodeCommand {
     #[arg(long)]
     pub album: Option<String>,
     /// Override title metadata
-    #[arg(long)]
+    #[arg(long, conflicts_with = "recursive")]
     pub title: Option<String>,
 }
 
+/// Result of a batch run, written to `manifest.json` in the output directory
+#[derive(Serialize, Debug)]
+pub struct BatchManifest {
+    pub files: Vec<BatchEntry>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct BatchEntry {
+    pub source: PathBuf,
+    /// Output file name, missing if transcoding failed
+    pub output: Option<String>,
+    pub track: Option<u32>,
+    pub metadata: Option<FileMetadata>,
+    pub error: Option<String>,
+}
+
 impl TranscodeCommand {
     pub async fn execute(self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.recursive {
+            return self.execute_batch();
+        }
+
         use comfy_table::{Table, presets::UTF8_FULL};
         use indicatif::{ProgressBar, ProgressStyle};
         use transcoder::{decode_and_normalize, extract_metadata};
@@ -48,14 +87,14 @@ impl TranscodeCommand {
         // Update metadata in transcoded buffer
         let transcoded_metadata = extract_metadata(&result.data);
         let final_metadata = self.override_metadata(transcoded_metadata)?;
-        self.update_metadata_in_buffer(&mut result.data, &final_metadata)
-            .await?;
+        update_metadata_in_buffer(&mut result.data, &final_metadata).await?;
         let actual_metadata = audio_file_utils::metadata::extract_metadata(&mut &result.data[..])
             .await
             .unwrap_or_default();
 
         // Write output file with updated metadata
-        std::fs::write(&result.filename, &result.data)?;
+        std::fs::create_dir_all(&self.out)?;
+        std::fs::write(self.out.join(&result.filename), &result.data)?;
 
         // Display metadata table
         let mut table = Table::new();
@@ -72,53 +111,6 @@ impl TranscodeCommand {
         Ok(())
     }
 
-    /// Update metadata inline in the WAV buffer by rewriting the LIST INFO chunk
-    async fn update_metadata_in_buffer(
-        &self,
-        data: &mut [u8],
-        metadata: &audio_file_utils::metadata::Metadata,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        use audio_file_utils::metadata::{INFO_CHUNK_SIZE, write_info_chunk};
-
-        let data_len = data.len();
-        let mut cursor = Cursor::new(data);
-
-        // Seek to the LIST section 40 bytes from start (based on web implementation)
-        cursor.seek(SeekFrom::Start(40))?;
-
-        // Read and validate "LIST" tag
-        let mut list_tag = [0u8; 4];
-        cursor.read_exact(&mut list_tag)?;
-        if &list_tag != b"LIST" {
-            return Err("LIST tag not found at expected position".into());
-        }
-
-        // Read and validate length = 124 (INFO_CHUNK_SIZE)
-        let mut length_bytes = [0u8; 4];
-        cursor.read_exact(&mut length_bytes)?;
-        let length = u32::from_le_bytes(length_bytes);
-        if length != INFO_CHUNK_SIZE as u32 {
-            return Err("Unexpected LIST chunk length".into());
-        }
-
-        // Get the position where INFO chunk should be written (after LIST header)
-        let info_start = cursor.position();
-        let info_end = info_start + INFO_CHUNK_SIZE as u64;
-
-        if info_end as usize > data_len {
-            return Err("INFO chunk extends beyond buffer".into());
-        }
-
-        let info_buffer = &mut cursor.get_mut()[info_start as usize..info_end as usize];
-
-        // Write the metadata to the INFO chunk
-        write_info_chunk(info_buffer, metadata)
-            .await
-            .map_err(|_| std::io::Error::other("Failed to write info chunk"))?;
-
-        Ok(())
-    }
-
     /// Override transcoded metadata with command line parameters, if provided
     fn override_metadata(
         &self,
@@ -158,3 +150,289 @@ impl TranscodeCommand {
         })
     }
 }
+
+impl TranscodeCommand {
+    fn execute_batch(&self) -> Result<(), Box<dyn std::error::Error>> {
+        use indicatif::{ProgressBar, ProgressStyle};
+
+        if !self.input_file.is_dir() {
+            return Err(format!("{} is not a directory", self.input_file.display()).into());
+        }
+
+        let sources = collect_sources(&self.input_file)?;
+        std::fs::create_dir_all(&self.out)?;
+
+        let jobs = self
+            .jobs
+            .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()))
+            .clamp(1, sources.len().max(1));
+
+        let pb = ProgressBar::new(sources.len() as u64);
+        pb.set_style(
+            ProgressStyle::default_bar()
+                .template(
+                    "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}",
+                )
+                .unwrap()
+                .progress_chars("#>-"),
+        );
+
+        let next = AtomicUsize::new(0);
+        let entries = Mutex::new(Vec::with_capacity(sources.len()));
+        std::thread::scope(|scope| {
+            for _ in 0..jobs {
+                scope.spawn(|| {
+                    let runtime = tokio::runtime::Builder::new_current_thread()
+                        .build()
+                        .expect("creating runtime");
+
+                    while let Some(source) = sources.get(next.fetch_add(1, Ordering::Relaxed)) {
+                        let entry = runtime.block_on(self.transcode_file(source));
+                        if let Some(error) = &entry.error {
+                            pb.println(format!("{}: {}", source.display(), error));
+                        }
+                        entries.lock().unwrap().push(entry);
+                        pb.inc(1);
+                    }
+                });
+            }
+        });
+        pb.finish_and_clear();
+
+        let mut entries = entries.into_inner().unwrap();
+        sort_entries(&mut entries);
+
+        let failed = entries.iter().filter(|entry| entry.error.is_some()).count();
+        let manifest_path = self.out.join(MANIFEST_NAME);
+        std::fs::write(
+            &manifest_path,
+            serde_json::to_vec_pretty(&BatchManifest { files: entries })?,
+        )?;
+
+        println!(
+            "Transcoded {} of {} files, manifest written to {}",
+            sources.len() - failed,
+            sources.len(),
+            manifest_path.display()
+        );
+        if failed > 0 {
+            return Err(format!("{} files failed to transcode", failed).into());
+        }
+        Ok(())
+    }
+
+    async fn transcode_file(&self, source: &Path) -> BatchEntry {
+        let mut entry = BatchEntry {
+            source: source.to_path_buf(),
+            output: None,
+            track: None,
+            metadata: None,
+            error: None,
+        };
+
+        match self.transcode_to_output(source, &mut entry).await {
+            Ok(()) => {}
+            Err(e) => entry.error = Some(e.to_string()),
+        }
+        entry
+    }
+
+    async fn transcode_to_output(
+        &self,
+        source: &Path,
+        entry: &mut BatchEntry,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        use transcoder::{decode_and_normalize, extract_metadata, extract_track_number};
+
+        let input_data = std::fs::read(source)?;
+        entry.track = extract_track_number(&input_data);
+
+        let mut result = decode_and_normalize(input_data.into(), |_, _| {}).await?;
+
+        let transcoded_metadata = extract_metadata(&result.data);
+        let final_metadata = self.override_metadata(transcoded_metadata)?;
+        update_metadata_in_buffer(&mut result.data, &final_metadata).await?;
+
+        std::fs::write(self.out.join(&result.filename), &result.data)?;
+
+        entry.metadata = Some(FileMetadata {
+            artist: final_metadata.artist.to_string(),
+            title: final_metadata.title.to_string(),
+            album: final_metadata.album.to_string(),
+            duration: duration_from_size(result.data.len() as u64),
+        });
+        entry.output = Some(result.filename);
+        Ok(())
+    }
+}
+
+/// Update metadata inline in the WAV buffer by rewriting the LIST INFO chunk
+pub(crate) async fn update_metadata_in_buffer(
+    data: &mut [u8],
+    metadata: &audio_file_utils::metadata::Metadata,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use audio_file_utils::metadata::{INFO_CHUNK_SIZE, write_info_chunk};
+
+    let data_len = data.len();
+    let mut cursor = Cursor::new(data);
+
+    // Seek to the LIST section 40 bytes from start (based on web implementation)
+    cursor.seek(SeekFrom::Start(40))?;
+
+    // Read and validate "LIST" tag
+    let mut list_tag = [0u8; 4];
+    cursor.read_exact(&mut list_tag)?;
+    if &list_tag != b"LIST" {
+        return Err("LIST tag not found at expected position".into());
+    }
+
+    // Read and validate length = 124 (INFO_CHUNK_SIZE)
+    let mut length_bytes = [0u8; 4];
+    cursor.read_exact(&mut length_bytes)?;
+    let length = u32::from_le_bytes(length_bytes);
+    if length != INFO_CHUNK_SIZE as u32 {
+        return Err("Unexpected LIST chunk length".into());
+    }
+
+    // Get the position where INFO chunk should be written (after LIST header)
+    let info_start = cursor.position();
+    let info_end = info_start + INFO_CHUNK_SIZE as u64;
+
+    if info_end as usize > data_len {
+        return Err("INFO chunk extends beyond buffer".into());
+    }
+
+    let info_buffer = &mut cursor.get_mut()[info_start as usize..info_end as usize];
+
+    // Write the metadata to the INFO chunk
+    write_info_chunk(info_buffer, metadata)
+        .await
+        .map_err(|_| std::io::Error::other("Failed to write info chunk"))?;
+
+    Ok(())
+}
+
+/// All audio files below `dir`, sorted by path
+pub(crate) fn collect_sources(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut sources = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if hidden {
+            continue;
+        }
+
+        if path.is_dir() {
+            sources.extend(collect_sources(&path)?);
+        } else if path.extension().is_some_and(|extension| {
+            AUDIO_EXTENSIONS.contains(&extension.to_string_lossy().to_ascii_lowercase().as_str())
+        }) {
+            sources.push(path);
+        }
+    }
+    sources.sort();
+    Ok(sources)
+}
+
+fn sort_entries(entries: &mut [BatchEntry]) {
+    entries.sort_by(|a, b| album_order((&a.source, a.track), (&b.source, b.track)));
+}
+
+/// Orders files by directory (album), then by track number, then by file name.
+/// Files without a track number go after numbered ones.
+pub(crate) fn album_order(a: (&Path, Option<u32>), b: (&Path, Option<u32>)) -> std::cmp::Ordering {
+    let (a_path, a_track) = a;
+    let (b_path, b_track) = b;
+    a_path
+        .parent()
+        .cmp(&b_path.parent())
+        .then(
+            a_track
+                .unwrap_or(u32::MAX)
+                .cmp(&b_track.unwrap_or(u32::MAX)),
+        )
+        .then(a_path.file_name().cmp(&b_path.file_name()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::TempDir;
+
+    fn entry(source: &str, track: Option<u32>) -> BatchEntry {
+        BatchEntry {
+            source: PathBuf::from(source),
+            output: None,
+            track,
+            metadata: None,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_sort_entries_by_album_and_track() {
+        let mut entries = vec![
+            entry("music/b/01 intro.mp3", None),
+            entry("music/a/10 ten.mp3", Some(10)),
+            entry("music/a/2 two.mp3", Some(2)),
+            entry("music/a/bonus.mp3", None),
+            entry("music/b/z.mp3", Some(1)),
+        ];
+        sort_entries(&mut entries);
+
+        let order: Vec<_> = entries
+            .iter()
+            .map(|entry| entry.source.to_str().unwrap())
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                "music/a/2 two.mp3",
+                "music/a/10 ten.mp3",
+                "music/a/bonus.mp3",
+                "music/b/z.mp3",
+                "music/b/01 intro.mp3",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_batch_keeps_going_past_failures() {
+        let root = TempDir::new("transcode");
+        let input = root.join("in");
+        std::fs::create_dir_all(input.join("album")).unwrap();
+        std::fs::copy(
+            "../transcoder/src/test_data/test_22050hz.wav",
+            input.join("album/track.wav"),
+        )
+        .unwrap();
+        std::fs::write(input.join("album/broken.mp3"), b"not an mp3").unwrap();
+        std::fs::write(input.join("cover.jpg"), b"not audio").unwrap();
+
+        let command = TranscodeCommand {
+            input_file: input,
+            recursive: true,
+            out: root.join("out"),
+            jobs: Some(2),
+            artist: None,
+            album: Some("Album".to_string()),
+            title: None,
+        };
+        assert!(command.execute_batch().is_err());
+
+        let manifest: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(root.join("out/manifest.json")).unwrap())
+                .unwrap();
+        let files = manifest["files"].as_array().unwrap();
+        assert_eq!(files.len(), 2);
+        assert!(files[0]["source"].as_str().unwrap().ends_with("broken.mp3"));
+        assert!(files[0]["error"].is_string());
+        assert!(files[1]["error"].is_null());
+        assert_eq!(files[1]["metadata"]["album"], "Album");
+
+        let output = files[1]["output"].as_str().unwrap();
+        assert!(root.join("out").join(output).exists());
+    }
+}
@@ -0,0 +1,225 @@
+//! Device discovery via mDNS
+//!
+//! The firmware's `MdnsResponder` only answers A queries for its hostname, so discovery sends a
+//! one-shot (legacy unicast) query for that name and collects every answer that arrives before
+//! the timeout. Responders reply directly to the querying socket.
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+
+pub const HOSTNAME: &str = "phoniesp32.local";
+pub const MDNS_ADDR: SocketAddrV4 = SocketAddrV4::new(Ipv4Addr::new(224, 0, 0, 251), 5353);
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(2);
+
+const TYPE_A: u16 = 1;
+const CLASS_IN: u16 = 1;
+const FLAG_RESPONSE: u16 = 0x8000;
+const MAX_NAME_JUMPS: usize = 16;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscoveredDevice {
+    pub address: Ipv4Addr,
+    pub hostname: String,
+}
+
+/// Sends a query to `target` (usually [`MDNS_ADDR`]) and collects answers until `timeout`
+pub async fn discover(
+    target: SocketAddr,
+    timeout: Duration,
+) -> Result<Vec<DiscoveredDevice>, Box<dyn std::error::Error>> {
+    let bind_addr: SocketAddr = if target.ip().is_loopback() {
+        (Ipv4Addr::LOCALHOST, 0).into()
+    } else {
+        (Ipv4Addr::UNSPECIFIED, 0).into()
+    };
+    let socket = UdpSocket::bind(bind_addr).await?;
+
+    let id = std::process::id() as u16;
+    socket.send_to(&build_query(id, HOSTNAME), target).await?;
+
+    let mut devices: Vec<DiscoveredDevice> = Vec::new();
+    let mut buf = [0u8; 1500];
+    let deadline = tokio::time::Instant::now() + timeout;
+    while let Ok(result) = tokio::time::timeout_at(deadline, socket.recv_from(&mut buf)).await {
+        let (len, _) = result?;
+        for (hostname, address) in parse_response(&buf[..len]).unwrap_or_default() {
+            if hostname.eq_ignore_ascii_case(HOSTNAME)
+                && !devices.iter().any(|device| device.address == address)
+            {
+                devices.push(DiscoveredDevice { address, hostname });
+            }
+        }
+    }
+
+    devices.sort_by_key(|device| device.address);
+    Ok(devices)
+}
+
+/// Encodes a DNS query for the A record of `hostname`
+pub fn build_query(id: u16, hostname: &str) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(12 + hostname.len() + 6);
+    packet.extend_from_slice(&id.to_be_bytes());
+    packet.extend_from_slice(&0u16.to_be_bytes()); // flags: standard query
+    packet.extend_from_slice(&1u16.to_be_bytes()); // questions
+    packet.extend_from_slice(&[0; 6]); // answer, authority and additional records
+    encode_name(&mut packet, hostname);
+    packet.extend_from_slice(&TYPE_A.to_be_bytes());
+    packet.extend_from_slice(&CLASS_IN.to_be_bytes());
+    packet
+}
+
+pub fn encode_name(packet: &mut Vec<u8>, name: &str) {
+    for label in name.trim_end_matches('.').split('.') {
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label.as_bytes());
+    }
+    packet.push(0);
+}
+
+/// Extracts all A records (name and address) from a DNS response
+pub fn parse_response(packet: &[u8]) -> Option<Vec<(String, Ipv4Addr)>> {
+    let flags = read_u16(packet, 2)?;
+    if flags & FLAG_RESPONSE == 0 {
+        return None;
+    }
+
+    let questions = read_u16(packet, 4)?;
+    let records = read_u16(packet, 6)? as usize
+        + read_u16(packet, 8)? as usize
+        + read_u16(packet, 10)? as usize;
+
+    let mut offset = 12;
+    for _ in 0..questions {
+        let (_, next) = read_name(packet, offset)?;
+        offset = next + 4;
+    }
+
+    let mut answers = Vec::new();
+    for _ in 0..records {
+        let (name, next) = read_name(packet, offset)?;
+        let rtype = read_u16(packet, next)?;
+        let class = read_u16(packet, next + 2)? & 0x7fff; // without cache-flush bit
+        let rdlen = read_u16(packet, next + 8)? as usize;
+        let rdata = packet.get(next + 10..next + 10 + rdlen)?;
+        if rtype == TYPE_A && class == CLASS_IN && rdlen == 4 {
+            answers.push((name, Ipv4Addr::new(rdata[0], rdata[1], rdata[2], rdata[3])));
+        }
+        offset = next + 10 + rdlen;
+    }
+
+    Some(answers)
+}
+
+fn read_u16(packet: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(
+        packet.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+/// Reads a possibly compressed name, returning it and the offset after it
+fn read_name(packet: &[u8], mut offset: usize) -> Option<(String, usize)> {
+    let mut labels: Vec<String> = Vec::new();
+    let mut end = None;
+    let mut jumps = 0;
+
+    loop {
+        let len = *packet.get(offset)? as usize;
+        match len {
+            0 => {
+                return Some((labels.join("."), end.unwrap_or(offset + 1)));
+            }
+            _ if len & 0xc0 == 0xc0 => {
+                jumps += 1;
+                if jumps > MAX_NAME_JUMPS {
+                    return None;
+                }
+                end.get_or_insert(offset + 2);
+                offset = (read_u16(packet, offset)? & 0x3fff) as usize;
+            }
+            _ => {
+                let label = packet.get(offset + 1..offset + 1 + len)?;
+                labels.push(String::from_utf8_lossy(label).into_owned());
+                offset += 1 + len;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a response the way `edge-mdns` does: the question is echoed for legacy queries and
+    /// the answer refers to it through a compression pointer
+    fn build_response(query: &[u8], address: Ipv4Addr) -> Vec<u8> {
+        let mut packet = query[..2].to_vec();
+        packet.extend_from_slice(&0x8400u16.to_be_bytes());
+        packet.extend_from_slice(&1u16.to_be_bytes());
+        packet.extend_from_slice(&1u16.to_be_bytes());
+        packet.extend_from_slice(&[0; 4]);
+        packet.extend_from_slice(&query[12..]);
+        packet.extend_from_slice(&0xc00cu16.to_be_bytes());
+        packet.extend_from_slice(&TYPE_A.to_be_bytes());
+        packet.extend_from_slice(&(CLASS_IN | 0x8000).to_be_bytes());
+        packet.extend_from_slice(&60u32.to_be_bytes());
+        packet.extend_from_slice(&4u16.to_be_bytes());
+        packet.extend_from_slice(&address.octets());
+        packet
+    }
+
+    #[test]
+    fn test_parse_compressed_response() {
+        let query = build_query(42, HOSTNAME);
+        let response = build_response(&query, Ipv4Addr::new(192, 168, 1, 23));
+
+        assert_eq!(
+            parse_response(&response).unwrap(),
+            vec![(HOSTNAME.to_string(), Ipv4Addr::new(192, 168, 1, 23))]
+        );
+        assert!(parse_response(&query).is_none());
+        assert!(parse_response(&response[..response.len() - 2]).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_discover_against_local_responders() {
+        // Stand-in for the mDNS multicast group: one socket receiving the query, answered by
+        // two "devices" from their own sockets
+        let responder = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let target = responder.local_addr().unwrap();
+
+        let stand_in = tokio::spawn(async move {
+            let mut buf = [0u8; 1500];
+            let (len, querier) = responder.recv_from(&mut buf).await.unwrap();
+            let query = buf[..len].to_vec();
+
+            let (name, _) = read_name(&query, 12).unwrap();
+            assert_eq!(name, HOSTNAME);
+
+            // The second answer from 10.0.0.7 is a repeated announcement and must be merged
+            for address in [[10, 0, 0, 7], [10, 0, 0, 5], [10, 0, 0, 7]] {
+                let device = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+                device
+                    .send_to(&build_response(&query, address.into()), querier)
+                    .await
+                    .unwrap();
+            }
+        });
+
+        let devices = discover(target, Duration::from_millis(500)).await.unwrap();
+        stand_in.await.unwrap();
+
+        assert_eq!(
+            devices,
+            vec![
+                DiscoveredDevice {
+                    address: Ipv4Addr::new(10, 0, 0, 5),
+                    hostname: HOSTNAME.to_string(),
+                },
+                DiscoveredDevice {
+                    address: Ipv4Addr::new(10, 0, 0, 7),
+                    hostname: HOSTNAME.to_string(),
+                },
+            ]
+        );
+    }
+}
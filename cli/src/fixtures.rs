@@ -0,0 +1,41 @@
+//! Fixtures shared by the tests
+
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+use axum::Router;
+
+/// Empty directory in the system's temp dir, removed again when dropped
+pub(crate) struct TempDir(PathBuf);
+
+impl TempDir {
+    /// `name` keeps tests running at the same time apart
+    pub(crate) fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("pecli-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Serves `router` on a free local port, returns its address
+pub(crate) async fn serve(router: Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    tokio::spawn(async move { axum::serve(listener, router).await });
+    address
+}
@@ -0,0 +1,171 @@
+//! Playlists created by desktop players, resolved to the audio files they reference
+use std::path::{Path, PathBuf};
+
+/// Reads an M3U/M3U8 or PLS playlist. Relative entries are resolved against the directory
+/// of the playlist.
+pub fn read_playlist(path: &Path) -> Result<Vec<PathBuf>, String> {
+    let data = std::fs::read(path).map_err(|e| format!("Reading {}: {}", path.display(), e))?;
+    let content = decode_text(data);
+    let base = path.parent().unwrap_or(Path::new("."));
+
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("m3u") | Some("m3u8") => parse_m3u(&content, base),
+        Some("pls") => parse_pls(&content, base),
+        _ => Err(format!(
+            "Unsupported playlist format: {} (expected .m3u, .m3u8 or .pls)",
+            path.display()
+        )),
+    }
+}
+
+/// M3U8 is always UTF-8, plain M3U files written by older players are often Latin-1
+fn decode_text(data: Vec<u8>) -> String {
+    let text = String::from_utf8(data)
+        .unwrap_or_else(|e| e.into_bytes().iter().map(|&byte| byte as char).collect());
+    text.trim_start_matches('\u{feff}').to_string()
+}
+
+pub fn parse_m3u(content: &str, base: &Path) -> Result<Vec<PathBuf>, String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| resolve_location(line, base))
+        .collect()
+}
+
+/// Entries are ordered by their `FileN` number, not by their position in the file
+pub fn parse_pls(content: &str, base: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut entries: Vec<(u32, &str)> = content
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.trim().split_once('=')?;
+            let number = key
+                .get(..4)
+                .filter(|prefix| prefix.eq_ignore_ascii_case("file"))
+                .and_then(|_| key[4..].trim().parse().ok())?;
+            Some((number, value.trim()))
+        })
+        .collect();
+    entries.sort_by_key(|(number, _)| *number);
+
+    entries
+        .into_iter()
+        .map(|(_, location)| resolve_location(location, base))
+        .collect()
+}
+
+/// Resolves a playlist entry (path or `file://` URL) to a path on the host
+fn resolve_location(location: &str, base: &Path) -> Result<PathBuf, String> {
+    let location = match location.strip_prefix("file://") {
+        // `file:///home/...` on Unix, `file:///C:/...` on Windows
+        Some(url) => {
+            let path = percent_decode(url);
+            if cfg!(windows) {
+                path.trim_start_matches('/').to_string()
+            } else {
+                path
+            }
+        }
+        None if location.contains("://") => {
+            return Err(format!("Streams are not supported: {}", location));
+        }
+        None => location.to_string(),
+    };
+
+    // Playlists written on Windows use backslashes
+    let location = if cfg!(windows) {
+        location
+    } else {
+        location.replace('\\', "/")
+    };
+
+    let path = Path::new(&location);
+    Ok(if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        base.join(path)
+    })
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| bytes.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_m3u_resolves_relative_entries() {
+        let m3u = "#EXTM3U\r\n\
+                   #EXTINF:123,Artist - Song\r\n\
+                   ../Music/01 Song.mp3\r\n\
+                   \r\n\
+                   Album\\02 Other.flac\r\n\
+                   /srv/music/03.ogg\r\n\
+                   file:///srv/music/Gr%C3%BC%C3%9Fe%20Song.mp3\r\n";
+
+        let entries = parse_m3u(m3u, Path::new("/home/kid/Playlists")).unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                PathBuf::from("/home/kid/Playlists/../Music/01 Song.mp3"),
+                PathBuf::from("/home/kid/Playlists/Album/02 Other.flac"),
+                PathBuf::from("/srv/music/03.ogg"),
+                PathBuf::from("/srv/music/Grüße Song.mp3"),
+            ]
+        );
+
+        assert!(parse_m3u("http://radio.example/stream", Path::new("/")).is_err());
+    }
+
+    #[test]
+    fn test_parse_pls_orders_by_number() {
+        let pls = "[playlist]\n\
+                   File2=b.mp3\n\
+                   Title2=B\n\
+                   File10=c.mp3\n\
+                   file1 = a.mp3\n\
+                   NumberOfEntries=3\n\
+                   Version=2\n";
+
+        let entries = parse_pls(pls, Path::new("music")).unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                PathBuf::from("music/a.mp3"),
+                PathBuf::from("music/b.mp3"),
+                PathBuf::from("music/c.mp3"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_text() {
+        assert_eq!(decode_text("\u{feff}Grüße".as_bytes().to_vec()), "Grüße");
+        assert_eq!(decode_text(vec![b'G', b'r', 0xfc, b'n']), "Grün");
+    }
+}
@@ -0,0 +1,524 @@
+//! Printable labels for fobs: one sticker or card per fob, laid out on sheets of paper
+//!
+//! All dimensions are in millimeters, measured from the top left corner of the page.
+mod pdf;
+mod svg;
+
+use clap::ValueEnum;
+use std::collections::HashMap;
+
+pub use pdf::render_pdf;
+pub use svg::render_svg;
+
+/// Gap between labels, for cutting
+const GAP: f32 = 3.0;
+const MARGIN: f32 = 10.0;
+/// Average glyph width of Helvetica relative to the font size, used to shorten text that does
+/// not fit
+const REGULAR_WIDTH: f32 = 0.52;
+const BOLD_WIDTH: f32 = 0.58;
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum LabelSize {
+    /// 28 mm square sticker for S50 key fobs
+    Fob,
+    /// ISO 7810 ID-1 card (85.6 × 54 mm) for credit card tags
+    Card,
+}
+
+impl LabelSize {
+    fn dimensions(self) -> (f32, f32) {
+        match self {
+            LabelSize::Fob => (28.0, 28.0),
+            LabelSize::Card => (85.6, 54.0),
+        }
+    }
+
+    fn corner_radius(self) -> f32 {
+        match self {
+            LabelSize::Fob => 2.0,
+            LabelSize::Card => 3.2,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Paper {
+    A4,
+    Letter,
+}
+
+impl Paper {
+    pub fn dimensions(self) -> (f32, f32) {
+        match self {
+            Paper::A4 => (210.0, 297.0),
+            Paper::Letter => (215.9, 279.4),
+        }
+    }
+}
+
+/// JPEG thumbnail of the cover art
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cover {
+    pub jpeg: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Cover {
+    /// Scales down an image in any supported format, `None` if it cannot be decoded
+    pub fn thumbnail(data: &[u8]) -> Option<Self> {
+        let image = image::load_from_memory(data)
+            .ok()?
+            .thumbnail(256, 256)
+            .to_rgb8();
+        let mut jpeg = Vec::new();
+        image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, 85)
+            .encode_image(&image)
+            .ok()?;
+        Some(Self {
+            jpeg,
+            width: image.width(),
+            height: image.height(),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Track {
+    pub artist: String,
+    pub title: String,
+    pub album: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Label {
+    pub fob: String,
+    pub title: String,
+    pub artist: String,
+    pub tracks: Vec<String>,
+    pub cover: Option<Cover>,
+}
+
+impl Label {
+    /// Titles the label with the album most tracks are from, and the artist if all tracks
+    /// share it
+    pub fn from_tracks(
+        fob: &str,
+        title: Option<String>,
+        tracks: &[Track],
+        cover: Option<Cover>,
+    ) -> Self {
+        let known = |value: &str| !value.is_empty() && value != "Unknown";
+
+        let mut albums: HashMap<&str, usize> = HashMap::new();
+        for track in tracks.iter().filter(|track| known(&track.album)) {
+            *albums.entry(&track.album).or_default() += 1;
+        }
+        // `max_by_key` picks the last of equal albums, so ties go to the one that comes first
+        // in the playlist
+        let album = tracks
+            .iter()
+            .rev()
+            .map(|track| track.album.as_str())
+            .filter(|album| known(album))
+            .max_by_key(|album| albums[album]);
+
+        let mut artists = tracks
+            .iter()
+            .map(|track| track.artist.as_str())
+            .filter(|artist| known(artist));
+        let artist = match artists.next() {
+            Some(first) if artists.all(|artist| artist == first) => first.to_string(),
+            Some(_) => "Various artists".to_string(),
+            None => String::new(),
+        };
+
+        Self {
+            fob: fob.to_string(),
+            title: title
+                .or(album.map(str::to_string))
+                .unwrap_or_else(|| format!("Fob {}", fob)),
+            artist,
+            tracks: tracks
+                .iter()
+                .map(|track| {
+                    if known(&track.artist) {
+                        format!("{} - {}", track.artist, track.title)
+                    } else {
+                        track.title.clone()
+                    }
+                })
+                .collect(),
+            cover,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Element<'a> {
+    /// Cut line around a label
+    Outline {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        radius: f32,
+    },
+    /// Single line of text, `y` is the baseline
+    Text {
+        x: f32,
+        y: f32,
+        size: f32,
+        bold: bool,
+        gray: bool,
+        text: String,
+    },
+    Image {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        cover: &'a Cover,
+    },
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Page<'a> {
+    pub elements: Vec<Element<'a>>,
+}
+
+/// Distributes the labels over as many pages as needed
+pub fn layout<'a>(labels: &'a [Label], size: LabelSize, paper: Paper) -> Vec<Page<'a>> {
+    let (page_width, page_height) = paper.dimensions();
+    let (width, height) = size.dimensions();
+    let columns = (((page_width - 2.0 * MARGIN + GAP) / (width + GAP)) as usize).max(1);
+    let rows = (((page_height - 2.0 * MARGIN + GAP) / (height + GAP)) as usize).max(1);
+
+    labels
+        .chunks(columns * rows)
+        .map(|chunk| {
+            let mut page = Page::default();
+            for (i, label) in chunk.iter().enumerate() {
+                let x = MARGIN + (i % columns) as f32 * (width + GAP);
+                let y = MARGIN + (i / columns) as f32 * (height + GAP);
+                page.elements.push(Element::Outline {
+                    x,
+                    y,
+                    width,
+                    height,
+                    radius: size.corner_radius(),
+                });
+                match size {
+                    LabelSize::Fob => layout_fob(&mut page, label, x, y),
+                    LabelSize::Card => layout_card(&mut page, label, x, y),
+                }
+            }
+            page
+        })
+        .collect()
+}
+
+/// Cover art on top, title below, the fob ID in small print
+fn layout_fob<'a>(page: &mut Page<'a>, label: &'a Label, x: f32, y: f32) {
+    let (width, height) = LabelSize::Fob.dimensions();
+    let padding = 2.0;
+    let text_width = width - 2.0 * padding;
+
+    let title_y = match &label.cover {
+        Some(cover) => {
+            page.elements
+                .push(fit_image(cover, x + 7.0, y + padding, 14.0, 14.0));
+            y + 20.5
+        }
+        None => y + 11.0,
+    };
+    page.elements.push(text(
+        x + padding,
+        title_y,
+        2.8,
+        true,
+        false,
+        &label.title,
+        text_width,
+    ));
+    if label.cover.is_none() && !label.artist.is_empty() {
+        page.elements.push(text(
+            x + padding,
+            title_y + 3.6,
+            2.2,
+            false,
+            false,
+            &label.artist,
+            text_width,
+        ));
+    }
+    page.elements.push(text(
+        x + padding,
+        y + height - padding,
+        1.6,
+        false,
+        true,
+        &label.fob,
+        text_width,
+    ));
+}
+
+/// Cover art and title side by side, the track list below
+fn layout_card<'a>(page: &mut Page<'a>, label: &'a Label, x: f32, y: f32) {
+    let (width, height) = LabelSize::Card.dimensions();
+    let padding = 4.0;
+    let cover_size = 20.0;
+
+    let text_x = match &label.cover {
+        Some(cover) => {
+            page.elements.push(fit_image(
+                cover,
+                x + padding,
+                y + padding,
+                cover_size,
+                cover_size,
+            ));
+            x + padding + cover_size + 3.0
+        }
+        None => x + padding,
+    };
+    let text_width = x + width - padding - text_x;
+    page.elements.push(text(
+        text_x,
+        y + 9.0,
+        4.2,
+        true,
+        false,
+        &label.title,
+        text_width,
+    ));
+    if !label.artist.is_empty() {
+        page.elements.push(text(
+            text_x,
+            y + 14.0,
+            3.0,
+            false,
+            false,
+            &label.artist,
+            text_width,
+        ));
+    }
+
+    let line_height = 3.2;
+    let top = y + padding + cover_size + 4.0;
+    let bottom = y + height - padding - 3.0;
+    let lines = ((bottom - top) / line_height) as usize + 1;
+    let shown = if label.tracks.len() > lines {
+        lines - 1
+    } else {
+        label.tracks.len()
+    };
+    let track_width = width - 2.0 * padding;
+    for (i, track) in label.tracks.iter().take(shown).enumerate() {
+        page.elements.push(text(
+            x + padding,
+            top + i as f32 * line_height,
+            2.5,
+            false,
+            false,
+            &format!("{}. {}", i + 1, track),
+            track_width,
+        ));
+    }
+    if shown < label.tracks.len() {
+        page.elements.push(text(
+            x + padding,
+            top + shown as f32 * line_height,
+            2.5,
+            false,
+            true,
+            &format!("+ {} more", label.tracks.len() - shown),
+            track_width,
+        ));
+    }
+
+    page.elements.push(Element::Text {
+        x: x + width - padding - estimate_width(&label.fob, 1.8, false),
+        y: y + height - padding,
+        size: 1.8,
+        bold: false,
+        gray: true,
+        text: label.fob.clone(),
+    });
+}
+
+fn text(
+    x: f32,
+    y: f32,
+    size: f32,
+    bold: bool,
+    gray: bool,
+    value: &str,
+    max_width: f32,
+) -> Element<'static> {
+    Element::Text {
+        x,
+        y,
+        size,
+        bold,
+        gray,
+        text: shorten(value, size, bold, max_width),
+    }
+}
+
+/// Centers the image in the box, keeping its aspect ratio
+fn fit_image(cover: &Cover, x: f32, y: f32, width: f32, height: f32) -> Element<'_> {
+    let scale = (width / cover.width as f32).min(height / cover.height as f32);
+    let (fitted_width, fitted_height) = (cover.width as f32 * scale, cover.height as f32 * scale);
+    Element::Image {
+        x: x + (width - fitted_width) / 2.0,
+        y: y + (height - fitted_height) / 2.0,
+        width: fitted_width,
+        height: fitted_height,
+        cover,
+    }
+}
+
+fn estimate_width(text: &str, size: f32, bold: bool) -> f32 {
+    let factor = if bold { BOLD_WIDTH } else { REGULAR_WIDTH };
+    text.chars().count() as f32 * size * factor
+}
+
+/// Cuts text that is estimated to be wider than `max_width` and appends an ellipsis
+fn shorten(text: &str, size: f32, bold: bool, max_width: f32) -> String {
+    if estimate_width(text, size, bold) <= max_width {
+        return text.to_string();
+    }
+
+    let mut shortened = text.trim_end().to_string();
+    while !shortened.is_empty()
+        && estimate_width(&format!("{}…", shortened), size, bold) > max_width
+    {
+        shortened.pop();
+        shortened.truncate(shortened.trim_end().len());
+    }
+    format!("{}…", shortened)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(artist: &str, title: &str, album: &str) -> Track {
+        Track {
+            artist: artist.to_string(),
+            title: title.to_string(),
+            album: album.to_string(),
+        }
+    }
+
+    fn label(tracks: usize, cover: bool) -> Label {
+        Label {
+            fob: "1a2b3c4d".to_string(),
+            title: "Lullabies".to_string(),
+            artist: "Various artists".to_string(),
+            tracks: (1..=tracks).map(|i| format!("Song {}", i)).collect(),
+            cover: cover.then(|| Cover {
+                jpeg: vec![0xff, 0xd8],
+                width: 200,
+                height: 100,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_label_from_tracks() {
+        let tracks = vec![
+            track("Mozart", "Night Music", "Lullabies"),
+            track("Brahms", "Lullaby", "Lullabies"),
+            track("Unknown", "Humming", "Unknown"),
+            track("Mozart", "Twinkle", "Classics"),
+        ];
+
+        let label = Label::from_tracks("1a2b3c4d", None, &tracks, None);
+        assert_eq!(label.title, "Lullabies");
+        assert_eq!(label.artist, "Various artists");
+        assert_eq!(
+            label.tracks,
+            vec![
+                "Mozart - Night Music",
+                "Brahms - Lullaby",
+                "Humming",
+                "Mozart - Twinkle"
+            ]
+        );
+
+        let label = Label::from_tracks("1a2b3c4d", None, &tracks[2..], None);
+        assert_eq!(label.title, "Classics");
+        assert_eq!(label.artist, "Mozart");
+
+        let label = Label::from_tracks("1a2b3c4d", Some("Bedtime".to_string()), &[], None);
+        assert_eq!(label.title, "Bedtime");
+        assert_eq!(label.artist, "");
+
+        let label = Label::from_tracks("1a2b3c4d", None, &tracks[2..3], None);
+        assert_eq!(label.title, "Fob 1a2b3c4d");
+    }
+
+    #[test]
+    fn test_layout_fills_pages() {
+        let labels: Vec<Label> = (0..9).map(|_| label(1, false)).collect();
+
+        let pages = layout(&labels, LabelSize::Card, Paper::A4);
+        let outlines = |page: &Page| {
+            page.elements
+                .iter()
+                .filter(|element| matches!(element, Element::Outline { .. }))
+                .count()
+        };
+        assert_eq!(pages.len(), 2);
+        assert_eq!(outlines(&pages[0]), 8);
+        assert_eq!(outlines(&pages[1]), 1);
+
+        let pages = layout(&labels, LabelSize::Fob, Paper::Letter);
+        assert_eq!(pages.len(), 1);
+        assert_eq!(outlines(&pages[0]), 9);
+    }
+
+    #[test]
+    fn test_card_layout() {
+        let labels = vec![label(20, true)];
+        let pages = layout(&labels, LabelSize::Card, Paper::A4);
+        let elements = &pages[0].elements;
+
+        // Cover is scaled to fit the box, centered vertically
+        assert!(elements.contains(&Element::Image {
+            x: 14.0,
+            y: 19.0,
+            width: 20.0,
+            height: 10.0,
+            cover: labels[0].cover.as_ref().unwrap(),
+        }));
+
+        let texts: Vec<&str> = elements
+            .iter()
+            .filter_map(|element| match element {
+                Element::Text { text, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(texts[..3], ["Lullabies", "Various artists", "1. Song 1"]);
+        assert!(texts.contains(&"5. Song 5"));
+        assert!(texts.contains(&"+ 15 more"));
+        assert_eq!(texts.last(), Some(&"1a2b3c4d"));
+    }
+
+    #[test]
+    fn test_shorten() {
+        assert_eq!(shorten("Short", 3.0, false, 50.0), "Short");
+        let shortened = shorten(
+            "A title that is much too long for a sticker",
+            3.0,
+            true,
+            30.0,
+        );
+        assert!(shortened.ends_with('…'));
+        assert!(estimate_width(&shortened, 3.0, true) <= 30.0);
+    }
+}
@@ -0,0 +1,249 @@
+use pdf_writer::{Content, Filter, Finish, Name, Pdf, Rect, Ref, Str};
+
+use super::{Cover, Element, Page, Paper};
+
+const POINTS_PER_MM: f32 = 72.0 / 25.4;
+const REGULAR_FONT: Name = Name(b"F1");
+const BOLD_FONT: Name = Name(b"F2");
+/// Control point distance for approximating a quarter circle with a cubic Bézier curve
+const KAPPA: f32 = 0.552_284_8;
+
+/// Renders all pages into one PDF document, using the standard Helvetica fonts
+pub fn render_pdf(pages: &[Page], paper: Paper) -> Vec<u8> {
+    let (width, height) = paper.dimensions();
+    let page_height = height * POINTS_PER_MM;
+
+    let mut next_id = Ref::new(1);
+    let mut alloc = || next_id.bump();
+    let catalog_id = alloc();
+    let page_tree_id = alloc();
+    let regular_font_id = alloc();
+    let bold_font_id = alloc();
+
+    let mut pdf = Pdf::new();
+    pdf.catalog(catalog_id).pages(page_tree_id);
+    pdf.type1_font(regular_font_id)
+        .base_font(Name(b"Helvetica"))
+        .encoding_predefined(Name(b"WinAnsiEncoding"));
+    pdf.type1_font(bold_font_id)
+        .base_font(Name(b"Helvetica-Bold"))
+        .encoding_predefined(Name(b"WinAnsiEncoding"));
+
+    // Each cover is embedded once, even if several labels show it
+    let mut images: Vec<(&Cover, Ref)> = Vec::new();
+    let mut page_ids = Vec::with_capacity(pages.len());
+    for page in pages {
+        let page_id = alloc();
+        let content_id = alloc();
+        page_ids.push(page_id);
+
+        let mut content = Content::new();
+        let mut page_images = Vec::new();
+        for element in &page.elements {
+            match element {
+                Element::Outline {
+                    x,
+                    y,
+                    width,
+                    height,
+                    radius,
+                } => {
+                    content
+                        .save_state()
+                        .set_stroke_gray(0.6)
+                        .set_line_width(0.2 * POINTS_PER_MM)
+                        .set_dash_pattern([POINTS_PER_MM, POINTS_PER_MM], 0.0);
+                    rounded_rect(
+                        &mut content,
+                        x * POINTS_PER_MM,
+                        page_height - (y + height) * POINTS_PER_MM,
+                        width * POINTS_PER_MM,
+                        height * POINTS_PER_MM,
+                        radius * POINTS_PER_MM,
+                    );
+                    content.stroke().restore_state();
+                }
+                Element::Text {
+                    x,
+                    y,
+                    size,
+                    bold,
+                    gray,
+                    text,
+                } => {
+                    content
+                        .set_fill_gray(if *gray { 0.4 } else { 0.0 })
+                        .begin_text()
+                        .set_font(
+                            if *bold { BOLD_FONT } else { REGULAR_FONT },
+                            size * POINTS_PER_MM,
+                        )
+                        .next_line(x * POINTS_PER_MM, page_height - y * POINTS_PER_MM)
+                        .show(Str(&win_ansi(text)))
+                        .end_text();
+                }
+                Element::Image {
+                    x,
+                    y,
+                    width,
+                    height,
+                    cover,
+                } => {
+                    let index = match images
+                        .iter()
+                        .position(|(image, _)| std::ptr::eq(*image, *cover))
+                    {
+                        Some(index) => index,
+                        None => {
+                            images.push((cover, alloc()));
+                            images.len() - 1
+                        }
+                    };
+                    let name = format!("Im{}", index);
+                    content
+                        .save_state()
+                        .transform([
+                            width * POINTS_PER_MM,
+                            0.0,
+                            0.0,
+                            height * POINTS_PER_MM,
+                            x * POINTS_PER_MM,
+                            page_height - (y + height) * POINTS_PER_MM,
+                        ])
+                        .x_object(Name(name.as_bytes()))
+                        .restore_state();
+                    page_images.push((name, images[index].1));
+                }
+            }
+        }
+        pdf.stream(content_id, &content.finish());
+
+        let mut page_writer = pdf.page(page_id);
+        page_writer
+            .parent(page_tree_id)
+            .media_box(Rect::new(0.0, 0.0, width * POINTS_PER_MM, page_height))
+            .contents(content_id);
+        let mut resources = page_writer.resources();
+        resources
+            .fonts()
+            .pair(REGULAR_FONT, regular_font_id)
+            .pair(BOLD_FONT, bold_font_id);
+        let mut x_objects = resources.x_objects();
+        for (name, id) in &page_images {
+            x_objects.pair(Name(name.as_bytes()), *id);
+        }
+    }
+
+    for (cover, id) in images {
+        let mut image = pdf.image_xobject(id, &cover.jpeg);
+        image
+            .width(cover.width as i32)
+            .height(cover.height as i32)
+            .bits_per_component(8);
+        image.filter(Filter::DctDecode);
+        image.color_space().device_rgb();
+        image.finish();
+    }
+
+    pdf.pages(page_tree_id)
+        .kids(page_ids.iter().copied())
+        .count(page_ids.len() as i32);
+    pdf.finish()
+}
+
+/// Adds a rectangle with rounded corners to the current path, `(x, y)` is the bottom left
+fn rounded_rect(content: &mut Content, x: f32, y: f32, width: f32, height: f32, radius: f32) {
+    let k = radius * KAPPA;
+    let (right, top) = (x + width, y + height);
+    content
+        .move_to(x + radius, y)
+        .line_to(right - radius, y)
+        .cubic_to(
+            right - radius + k,
+            y,
+            right,
+            y + radius - k,
+            right,
+            y + radius,
+        )
+        .line_to(right, top - radius)
+        .cubic_to(
+            right,
+            top - radius + k,
+            right - radius + k,
+            top,
+            right - radius,
+            top,
+        )
+        .line_to(x + radius, top)
+        .cubic_to(x + radius - k, top, x, top - radius + k, x, top - radius)
+        .line_to(x, y + radius)
+        .cubic_to(x, y + radius - k, x + radius - k, y, x + radius, y)
+        .close_path();
+}
+
+/// Encodes text for the standard fonts, characters they cannot show become `?`
+fn win_ansi(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c {
+            '…' => 0x85,
+            '–' => 0x96,
+            '—' => 0x97,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            '€' => 0x80,
+            ' '..='~' | '\u{a0}'..='\u{ff}' => c as u8,
+            _ => b'?',
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_pdf() {
+        let cover = Cover {
+            jpeg: vec![0xff, 0xd8, 0xff],
+            width: 1,
+            height: 1,
+        };
+        let page = || Page {
+            elements: vec![
+                Element::Text {
+                    x: 14.0,
+                    y: 19.0,
+                    size: 4.2,
+                    bold: true,
+                    gray: false,
+                    text: "Lullabies".to_string(),
+                },
+                Element::Image {
+                    x: 14.0,
+                    y: 14.0,
+                    width: 20.0,
+                    height: 20.0,
+                    cover: &cover,
+                },
+            ],
+        };
+
+        let pdf = render_pdf(&[page(), page()], Paper::A4);
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.starts_with("%PDF-"));
+        assert!(text.contains("/Count 2"));
+        assert!(text.contains("/BaseFont /Helvetica-Bold"));
+        // The cover is embedded once and used on both pages
+        assert_eq!(text.matches("/Subtype /Image").count(), 1);
+        assert_eq!(text.matches("/Im0").count(), 4);
+    }
+
+    #[test]
+    fn test_win_ansi() {
+        assert_eq!(win_ansi("Grüße…"), b"Gr\xfc\xdfe\x85");
+        assert_eq!(win_ansi("日本"), b"??");
+    }
+}
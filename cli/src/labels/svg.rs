@@ -0,0 +1,118 @@
+use base64::{Engine, engine::general_purpose::STANDARD};
+use std::fmt::Write;
+
+use super::{Element, Page, Paper};
+
+const FONT_FAMILY: &str = "Helvetica, Arial, sans-serif";
+
+/// Renders a page as SVG with millimeter units, so it prints at the right size
+pub fn render_svg(page: &Page, paper: Paper) -> String {
+    let (width, height) = paper.dimensions();
+    let mut svg = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}mm\" height=\"{height}mm\" \
+         viewBox=\"0 0 {width} {height}\" font-family=\"{FONT_FAMILY}\">\n"
+    );
+
+    for element in &page.elements {
+        match element {
+            Element::Outline {
+                x,
+                y,
+                width,
+                height,
+                radius,
+            } => writeln!(
+                svg,
+                "  <rect x=\"{x}\" y=\"{y}\" width=\"{width}\" height=\"{height}\" rx=\"{radius}\" \
+                 fill=\"none\" stroke=\"#999\" stroke-width=\"0.2\" stroke-dasharray=\"1 1\"/>"
+            ),
+            Element::Text {
+                x,
+                y,
+                size,
+                bold,
+                gray,
+                text,
+            } => writeln!(
+                svg,
+                "  <text x=\"{x}\" y=\"{y}\" font-size=\"{size}\"{}{}>{}</text>",
+                if *bold { " font-weight=\"bold\"" } else { "" },
+                if *gray { " fill=\"#666\"" } else { "" },
+                escape(text)
+            ),
+            Element::Image {
+                x,
+                y,
+                width,
+                height,
+                cover,
+            } => writeln!(
+                svg,
+                "  <image x=\"{x}\" y=\"{y}\" width=\"{width}\" height=\"{height}\" \
+                 href=\"data:image/jpeg;base64,{}\"/>",
+                STANDARD.encode(&cover.jpeg)
+            ),
+        }
+        .expect("writing to a string");
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::labels::Cover;
+
+    #[test]
+    fn test_render_svg() {
+        let cover = Cover {
+            jpeg: vec![0xff, 0xd8, 0xff],
+            width: 1,
+            height: 1,
+        };
+        let page = Page {
+            elements: vec![
+                Element::Outline {
+                    x: 10.0,
+                    y: 10.0,
+                    width: 85.6,
+                    height: 54.0,
+                    radius: 3.2,
+                },
+                Element::Text {
+                    x: 14.0,
+                    y: 19.0,
+                    size: 4.2,
+                    bold: true,
+                    gray: false,
+                    text: "Tom & Jerry <3".to_string(),
+                },
+                Element::Image {
+                    x: 14.0,
+                    y: 14.0,
+                    width: 20.0,
+                    height: 20.0,
+                    cover: &cover,
+                },
+            ],
+        };
+
+        let svg = render_svg(&page, Paper::A4);
+        assert!(svg.contains("width=\"210mm\" height=\"297mm\" viewBox=\"0 0 210 297\""));
+        assert!(svg.contains("<rect x=\"10\" y=\"10\" width=\"85.6\" height=\"54\" rx=\"3.2\""));
+        assert!(svg.contains(
+            "<text x=\"14\" y=\"19\" font-size=\"4.2\" font-weight=\"bold\">Tom &amp; Jerry &lt;3</text>"
+        ));
+        assert!(svg.contains("href=\"data:image/jpeg;base64,/9j/\""));
+        assert!(roxmltree::Document::parse(&svg).is_ok());
+    }
+}
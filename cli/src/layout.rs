@@ -0,0 +1,484 @@
+//! On-card layout shared with the firmware (`entities::audio_file`, `entities::playlist`,
+//! `entities::resume` and `drivers::sd`). Keep in sync when the firmware changes its file
+//! format.
+use audio_file_utils::metadata::{INFO_CHUNK_SIZE, Metadata};
+use audio_file_utils::order;
+use serde::{Deserialize, Serialize};
+
+pub const FILE_DIR: &str = "FILES";
+pub const FILE_EXT: &str = ".WAV";
+pub const PLAYLIST_DIR: &str = "FOBS";
+pub const PLAYLIST_EXT: &str = ".M3U";
+pub const RESUME_DIR: &str = "RESUME";
+pub const RESUME_EXT: &str = ".RES";
+pub const CONFIG_FILE: &str = "config.jsn";
+pub const REPEAT_DIRECTIVE: &str = "#EXT-X-REPEAT:";
+pub const SHUFFLE_DIRECTIVE: &str = "#EXT-X-SHUFFLE:";
+pub const TRANSITION_DIRECTIVE: &str = "#EXT-X-TRANSITION:";
+/// Longest gap or crossfade the firmware accepts, longer ones are cut to it
+pub const MAX_TRANSITION_MS: u16 = 10_000;
+
+/// Sample rate of the files on the card
+pub const SAMPLE_RATE: u64 = 44100;
+
+/// Size of the RIFF header written by the transcoder (RIFF + fmt + LIST/INFO + data header)
+pub const WAV_HEADER_SIZE: u64 = 48 + 8 + INFO_CHUNK_SIZE as u64;
+
+/// Highest volume step of the firmware's `entities::volume`, steps are 1.5 dB apart
+pub const MAX_VOLUME: u8 = 32;
+/// Volume without a saved or configured one
+pub const DEFAULT_VOLUME: u8 = 28;
+/// Last volume, `{"volume": <step>}`
+pub const VOLUME_FILE: &str = "VOLUME.JSN";
+/// Clips replacing the built-in tones of system sounds, `SYSTEM/NAME.WAV`
+pub const SOUND_DIR: &str = "SYSTEM";
+/// API names of the firmware's `entities::system_sound::SystemSound` and their clip names
+pub const SYSTEM_SOUNDS: [(&str, &str); 10] = [
+    ("boot", "BOOT"),
+    ("fob_recognized", "FOB"),
+    ("unknown_fob", "UNKNOWN"),
+    ("volume_limit", "VOLMAX"),
+    ("wifi_on", "WIFION"),
+    ("wifi_off", "WIFIOFF"),
+    ("low_battery", "LOWBATT"),
+    ("sleep_timer", "SLEEP"),
+    ("sleep_timer_off", "SLEEPOFF"),
+    ("error", "ERROR"),
+];
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct DeviceConfig {
+    #[serde(alias = "SSID")]
+    pub ssid: String,
+
+    #[serde(alias = "PASSWORD")]
+    pub password: String,
+
+    /// Highest volume step the buttons and the API can set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_volume: Option<u8>,
+
+    /// Volume step after power-on, the last volume is restored if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub startup_volume: Option<u8>,
+
+    /// Equalizer tuned for the enclosure, playback is unfiltered if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eq: Option<EqConfig>,
+
+    /// What scanning a fob without playlist does, `sound` if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unknown_fob: Option<UnknownFobAction>,
+
+    /// Plays fobs only while they are on the reader, fobs start playback only if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag_presence: Option<TagPresenceConfig>,
+
+    /// What scanning the fob playing again does, ignored after a cooldown if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rescan: Option<RescanConfig>,
+}
+
+/// Second swipe settings of `DeviceConfig`
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct RescanConfig {
+    pub action: RescanAction,
+    /// Seconds after a scan of the fob playing in which scanning it again does nothing
+    pub cooldown_seconds: u16,
+}
+
+impl Default for RescanConfig {
+    fn default() -> Self {
+        Self {
+            action: RescanAction::default(),
+            cooldown_seconds: 10,
+        }
+    }
+}
+
+/// Reaction to scanning the fob whose playlist is playing. Scanning the fob of a paused
+/// playlist resumes it with `Pause`, otherwise it plays from its resume point like any other
+/// scan.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RescanAction {
+    #[default]
+    Ignore,
+    /// Pauses, or resumes while paused
+    Pause,
+    Next,
+    /// Starts the playlist over at its first track
+    Restart,
+}
+
+/// Tag presence mode of `DeviceConfig`: removing the fob pauses its playlist, putting it back
+/// resumes it
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct TagPresenceConfig {
+    /// Seconds after the removal at which the paused playlist stops
+    pub stop_after_seconds: u16,
+}
+
+impl Default for TagPresenceConfig {
+    fn default() -> Self {
+        Self {
+            stop_after_seconds: 300,
+        }
+    }
+}
+
+/// Reaction to a fob without playlist
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum UnknownFobAction {
+    Ignore,
+    #[default]
+    Sound,
+    /// Plays the sound and saves the playlist playing to the fob on the next play/pause press
+    Learn,
+}
+
+/// Equalizer settings of `DeviceConfig`, a low shelf and a presence peak
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct EqConfig {
+    pub bass_db: i8,
+    pub bass_hz: u16,
+    pub presence_db: i8,
+    pub presence_hz: u16,
+    pub presence_q: f32,
+    /// Boosts the bass at low volume
+    pub loudness: bool,
+}
+
+impl Default for EqConfig {
+    fn default() -> Self {
+        Self {
+            bass_db: 0,
+            bass_hz: 150,
+            presence_db: 0,
+            presence_hz: 3000,
+            presence_q: 1.0,
+            loudness: true,
+        }
+    }
+}
+
+/// What scanning a fob again does
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ResumeMode {
+    #[default]
+    Resume,
+    Restart,
+}
+
+/// Where playback of a fob's playlist stopped, stored as `RESUME/<FOB>.RES`
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct ResumePoint {
+    #[serde(default)]
+    pub mode: ResumeMode,
+    #[serde(default)]
+    pub index: usize,
+    /// Decoded samples into the file at `index`
+    #[serde(default)]
+    pub sample: u64,
+}
+
+impl ResumePoint {
+    /// Where the firmware starts playing a playlist with `total_files` files
+    pub fn start(&self, total_files: usize) -> Self {
+        if self.mode == ResumeMode::Restart || self.index >= total_files {
+            Self {
+                mode: self.mode,
+                ..Default::default()
+            }
+        } else {
+            *self
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Repeat {
+    #[default]
+    Off,
+    One,
+    All,
+}
+
+impl From<Repeat> for order::Repeat {
+    fn from(repeat: Repeat) -> Self {
+        match repeat {
+            Repeat::Off => order::Repeat::Off,
+            Repeat::One => order::Repeat::One,
+            Repeat::All => order::Repeat::All,
+        }
+    }
+}
+
+/// What plays between two tracks of a playlist, a 1 second gap if the playlist does not say
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Transition {
+    /// Silence of this many milliseconds, 0 plays the tracks gapless
+    Gap(u16),
+    /// The end of a track fades into the start of the next over this many milliseconds
+    Crossfade(u16),
+}
+
+impl Transition {
+    /// Parses a `#EXT-X-TRANSITION` value like the firmware's `Transition::parse`
+    pub fn parse(value: &str) -> Option<Self> {
+        if value == "GAPLESS" {
+            return Some(Transition::Gap(0));
+        }
+        let (kind, ms) = value.split_once('=')?;
+        let ms = ms.trim().parse::<u16>().ok()?.min(MAX_TRANSITION_MS);
+        match kind.trim() {
+            "GAP" => Some(Transition::Gap(ms)),
+            "CROSSFADE" => Some(Transition::Crossfade(ms)),
+            _ => None,
+        }
+    }
+
+    pub fn directive_value(self) -> String {
+        match self {
+            Transition::Gap(0) => "GAPLESS".to_string(),
+            Transition::Gap(ms) => format!("GAP={}", ms),
+            Transition::Crossfade(ms) => format!("CROSSFADE={}", ms),
+        }
+    }
+}
+
+/// Playback mode set by a playlist with `#EXT-X-REPEAT:OFF|ONE|ALL`,
+/// `#EXT-X-SHUFFLE:ON|OFF` and `#EXT-X-TRANSITION:GAPLESS|GAP=<ms>|CROSSFADE=<ms>`, unset
+/// fields use the global mode
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PlaylistMode {
+    pub repeat: Option<Repeat>,
+    pub shuffle: Option<bool>,
+    pub transition: Option<Transition>,
+}
+
+impl PlaylistMode {
+    /// Reads the directives of a playlist like the firmware's `PlayListRef::read`
+    pub fn parse(m3u: &str) -> Self {
+        let mut mode = Self::default();
+        for line in m3u.lines().map(str::trim) {
+            if let Some(value) = line.strip_prefix(REPEAT_DIRECTIVE) {
+                mode.repeat = match value.trim() {
+                    "OFF" => Some(Repeat::Off),
+                    "ONE" => Some(Repeat::One),
+                    "ALL" => Some(Repeat::All),
+                    _ => None,
+                };
+            } else if let Some(value) = line.strip_prefix(SHUFFLE_DIRECTIVE) {
+                mode.shuffle = match value.trim() {
+                    "ON" => Some(true),
+                    "OFF" => Some(false),
+                    _ => None,
+                };
+            } else if let Some(value) = line.strip_prefix(TRANSITION_DIRECTIVE) {
+                mode.transition = Transition::parse(value.trim());
+            }
+        }
+        mode
+    }
+}
+
+/// A file referenced from a playlist, with the information `#EXTINF` needs
+#[derive(Clone, Debug)]
+pub struct PlaylistEntry {
+    pub name: String,
+    pub metadata: Metadata,
+    pub duration: u32,
+}
+
+/// Duration in seconds as computed by the firmware from the file size
+pub fn duration_from_size(file_size: u64) -> u32 {
+    (file_size.saturating_sub(WAV_HEADER_SIZE) / 22050) as u32
+}
+
+/// Checks whether `data` already is in the format produced by the transcoder
+/// (44.1 kHz mono IMA ADPCM with 1024 byte blocks)
+pub fn is_device_wav(data: &[u8]) -> bool {
+    data.len() >= WAV_HEADER_SIZE as usize
+        && &data[0..4] == b"RIFF"
+        && &data[8..12] == b"WAVE"
+        && &data[12..16] == b"fmt "
+        && u16::from_le_bytes([data[20], data[21]]) == 0x0011
+        && u16::from_le_bytes([data[22], data[23]]) == 1
+        && u32::from_le_bytes([data[24], data[25], data[26], data[27]]) == 44100
+        && u16::from_le_bytes([data[32], data[33]]) == 1024
+}
+
+/// Validates a device file or fob name (at most 8 characters, usable as a FAT short name)
+pub fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.len() > 8 {
+        return Err(format!("'{}' must be between 1 and 8 characters", name));
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(format!(
+            "'{}' may only contain ASCII letters and digits",
+            name
+        ));
+    }
+    Ok(())
+}
+
+/// Extracts the file name from a playlist line like the firmware's `AudioFile::from_path`
+pub fn audio_file_from_path(path: &str) -> Option<&str> {
+    let prefix = format!("..\\{}\\", FILE_DIR);
+    let name = path.strip_prefix(&prefix)?.strip_suffix(FILE_EXT)?;
+    (name.len() <= 8).then_some(name)
+}
+
+/// Renders a playlist exactly like the firmware's `Playlist::write`
+pub fn render_playlist(entries: &[PlaylistEntry], mode: &PlaylistMode) -> String {
+    let mut m3u = String::from("#EXTM3U\r\n");
+    if let Some(repeat) = mode.repeat {
+        let value = match repeat {
+            Repeat::Off => "OFF",
+            Repeat::One => "ONE",
+            Repeat::All => "ALL",
+        };
+        m3u.push_str(&format!("{}{}\r\n", REPEAT_DIRECTIVE, value));
+    }
+    if let Some(shuffle) = mode.shuffle {
+        let value = if shuffle { "ON" } else { "OFF" };
+        m3u.push_str(&format!("{}{}\r\n", SHUFFLE_DIRECTIVE, value));
+    }
+    if let Some(transition) = mode.transition {
+        m3u.push_str(&format!(
+            "{}{}\r\n",
+            TRANSITION_DIRECTIVE,
+            transition.directive_value()
+        ));
+    }
+    for entry in entries {
+        m3u.push_str(&format!(
+            "#EXTINF:{},{} - {}\r\n",
+            entry.duration, entry.metadata.artist, entry.metadata.title
+        ));
+        m3u.push_str(&format!("..\\{}\\{}{}\r\n", FILE_DIR, entry.name, FILE_EXT));
+    }
+    m3u
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_playlist_matches_firmware_format() {
+        let entries = vec![
+            PlaylistEntry {
+                name: "ABCDEFGH".to_string(),
+                metadata: Metadata {
+                    artist: "Artist".try_into().unwrap(),
+                    title: "Song".try_into().unwrap(),
+                    album: "Album".try_into().unwrap(),
+                },
+                duration: 123,
+            },
+            PlaylistEntry {
+                name: "IJKLMNOP".to_string(),
+                metadata: Metadata::default(),
+                duration: 7,
+            },
+        ];
+
+        assert_eq!(
+            render_playlist(&entries, &PlaylistMode::default()),
+            "#EXTM3U\r\n\
+             #EXTINF:123,Artist - Song\r\n\
+             ..\\FILES\\ABCDEFGH.WAV\r\n\
+             #EXTINF:7,Unknown - Unknown\r\n\
+             ..\\FILES\\IJKLMNOP.WAV\r\n"
+        );
+    }
+
+    #[test]
+    fn test_playlist_mode_round_trip() {
+        let mode = PlaylistMode {
+            repeat: Some(Repeat::All),
+            shuffle: Some(false),
+            transition: Some(Transition::Crossfade(3000)),
+        };
+        let m3u = render_playlist(&[], &mode);
+        assert_eq!(
+            m3u,
+            "#EXTM3U\r\n#EXT-X-REPEAT:ALL\r\n#EXT-X-SHUFFLE:OFF\r\n\
+             #EXT-X-TRANSITION:CROSSFADE=3000\r\n"
+        );
+        assert_eq!(PlaylistMode::parse(&m3u), mode);
+
+        let mode = PlaylistMode::parse("#EXTM3U\n#EXT-X-SHUFFLE:ON\n#EXT-X-REPEAT:SOMETIMES\n");
+        assert_eq!(mode.shuffle, Some(true));
+        assert_eq!(mode.repeat, None);
+    }
+
+    #[test]
+    fn test_transition_parse() {
+        assert_eq!(Transition::parse("GAPLESS"), Some(Transition::Gap(0)));
+        assert_eq!(Transition::parse("GAP=250"), Some(Transition::Gap(250)));
+        assert_eq!(
+            Transition::parse("CROSSFADE=60000"),
+            Some(Transition::Crossfade(MAX_TRANSITION_MS))
+        );
+        assert_eq!(Transition::parse("FADE=100"), None);
+        assert_eq!(Transition::parse("GAP=-1"), None);
+        for transition in [Transition::Gap(0), Transition::Gap(500)] {
+            assert_eq!(
+                Transition::parse(&transition.directive_value()),
+                Some(transition)
+            );
+        }
+    }
+
+    #[test]
+    fn test_resume_point_start() {
+        let point: ResumePoint = serde_json::from_str(r#"{"index":2,"sample":88200}"#).unwrap();
+        assert_eq!(point.mode, ResumeMode::Resume);
+        assert_eq!(point.start(3), point);
+        assert_eq!(point.start(2), ResumePoint::default());
+
+        let restart = ResumePoint {
+            mode: ResumeMode::Restart,
+            ..point
+        };
+        assert_eq!(restart.start(3).index, 0);
+        assert_eq!(
+            serde_json::to_string(&restart.start(3)).unwrap(),
+            r#"{"mode":"restart","index":0,"sample":0}"#
+        );
+    }
+
+    #[test]
+    fn test_duration_from_size() {
+        assert_eq!(duration_from_size(WAV_HEADER_SIZE + 22050 * 10 + 5), 10);
+        assert_eq!(duration_from_size(10), 0);
+    }
+
+    #[test]
+    fn test_audio_file_from_path() {
+        assert_eq!(
+            audio_file_from_path("..\\FILES\\ABCDEFGH.WAV"),
+            Some("ABCDEFGH")
+        );
+        assert_eq!(audio_file_from_path("../FILES/ABCDEFGH.WAV"), None);
+        assert_eq!(audio_file_from_path("..\\FILES\\ABCDEFGH.wav"), None);
+        assert_eq!(audio_file_from_path("..\\FILES\\ABCDEFGHI.WAV"), None);
+    }
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name("1a2b3c4d").is_ok());
+        assert!(validate_name("").is_err());
+        assert!(validate_name("123456789").is_err());
+        assert!(validate_name("a.b").is_err());
+    }
+}
@@ -1,5 +1,28 @@
 use clap::{Parser, Subcommand};
+mod archive;
+mod client;
 mod commands;
+mod discovery;
+#[cfg(test)]
+mod fixtures;
+mod import;
+mod labels;
+mod layout;
+mod manifest;
+mod mock;
+mod podcast;
+mod wav;
+use commands::backup::BackupCommand;
+use commands::discover::DiscoverCommand;
+use commands::export::ExportCommand;
+use commands::import_playlist::ImportPlaylistCommand;
+use commands::inspect::InspectCommand;
+use commands::labels::LabelsCommand;
+use commands::mock_device::MockDeviceCommand;
+use commands::monitor::MonitorCommand;
+use commands::podcast::PodcastCommand;
+use commands::restore::RestoreCommand;
+use commands::sd_image::SdImageCommand;
 use commands::transcode::TranscodeCommand;
 
 #[derive(Parser)]
@@ -14,6 +37,17 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     Transcode(TranscodeCommand),
+    SdImage(SdImageCommand),
+    Backup(BackupCommand),
+    Restore(RestoreCommand),
+    Discover(DiscoverCommand),
+    Inspect(InspectCommand),
+    Export(ExportCommand),
+    MockDevice(MockDeviceCommand),
+    Monitor(MonitorCommand),
+    Podcast(PodcastCommand),
+    ImportPlaylist(ImportPlaylistCommand),
+    Labels(LabelsCommand),
 }
 
 #[tokio::main]
@@ -22,6 +56,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     match cli.command {
         Commands::Transcode(cmd) => cmd.execute().await?,
+        Commands::SdImage(cmd) => cmd.execute().await?,
+        Commands::Backup(cmd) => cmd.execute().await?,
+        Commands::Restore(cmd) => cmd.execute().await?,
+        Commands::Discover(cmd) => cmd.execute().await?,
+        Commands::Inspect(cmd) => cmd.execute().await?,
+        Commands::Export(cmd) => cmd.execute().await?,
+        Commands::MockDevice(cmd) => cmd.execute().await?,
+        Commands::Monitor(cmd) => cmd.execute().await?,
+        Commands::Podcast(cmd) => cmd.execute().await?,
+        Commands::ImportPlaylist(cmd) => cmd.execute().await?,
+        Commands::Labels(cmd) => cmd.execute().await?,
     }
 
     Ok(())
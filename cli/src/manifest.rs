@@ -0,0 +1,141 @@
+//! Library manifest describing the content of a device
+//!
+//! ```toml
+//! [wifi]
+//! ssid = "home"
+//! password = "secret"
+//!
+//! [[fobs]]
+//! id = "1a2b3c4d"
+//! title = "Lullabies"
+//! files = ["albums/lullabies/01.mp3", "albums/lullabies/02.mp3"]
+//! ```
+//!
+//! Relative file paths are resolved against the directory of the manifest.
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use crate::layout::{DeviceConfig, validate_name};
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    pub wifi: Option<DeviceConfig>,
+    #[serde(default)]
+    pub fobs: Vec<FobEntry>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct FobEntry {
+    pub id: String,
+    /// Title printed on labels, defaults to the album of the files
+    pub title: Option<String>,
+    pub files: Vec<PathBuf>,
+}
+
+impl Manifest {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Reading manifest {}: {}", path.display(), e))?;
+        let base_dir = path.parent().unwrap_or(Path::new("."));
+        Self::parse(&content, base_dir)
+    }
+
+    pub fn parse(content: &str, base_dir: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut manifest: Manifest = toml::from_str(content)?;
+
+        for fob in &mut manifest.fobs {
+            validate_name(&fob.id).map_err(|e| format!("Invalid fob id: {}", e))?;
+            for file in &mut fob.files {
+                if file.is_relative() {
+                    *file = base_dir.join(&*file);
+                }
+            }
+        }
+
+        Ok(manifest)
+    }
+
+    /// All referenced source files, in order of first appearance
+    pub fn sources(&self) -> Vec<&Path> {
+        let mut sources: Vec<&Path> = Vec::new();
+        for file in self.fobs.iter().flat_map(|fob| &fob.files) {
+            if !sources.contains(&file.as_path()) {
+                sources.push(file);
+            }
+        }
+        sources
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_resolves_relative_paths() {
+        let manifest = Manifest::parse(
+            r#"
+            [wifi]
+            ssid = "home"
+            password = "secret"
+
+            [[fobs]]
+            id = "1a2b3c4d"
+            files = ["a.mp3", "/abs/b.mp3"]
+
+            [[fobs]]
+            id = "deadbeef"
+            files = ["a.mp3"]
+            "#,
+            Path::new("/music"),
+        )
+        .unwrap();
+
+        assert_eq!(manifest.wifi.unwrap().ssid, "home");
+        assert_eq!(
+            manifest.fobs[0].files,
+            vec![PathBuf::from("/music/a.mp3"), PathBuf::from("/abs/b.mp3")]
+        );
+    }
+
+    #[test]
+    fn test_sources_are_deduplicated() {
+        let manifest = Manifest::parse(
+            r#"
+            [[fobs]]
+            id = "1"
+            files = ["a.mp3", "b.mp3"]
+
+            [[fobs]]
+            id = "2"
+            files = ["b.mp3", "c.mp3"]
+            "#,
+            Path::new("/music"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            manifest.sources(),
+            vec![
+                Path::new("/music/a.mp3"),
+                Path::new("/music/b.mp3"),
+                Path::new("/music/c.mp3"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_fob_id_is_rejected() {
+        let result = Manifest::parse(
+            r#"
+            [[fobs]]
+            id = "far-too-long"
+            files = []
+            "#,
+            Path::new("."),
+        );
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,294 @@
+//! SD card contents of the mock device, stored in a host directory with the same layout as on
+//! the device (`FILES/NAME.WAV`, `FOBS/NAME.M3U`, `RESUME/NAME.RES`, `config.jsn`,
+//! `VOLUME.JSN`, `SYSTEM/NAME.WAV`)
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+
+use audio_file_utils::metadata::{Metadata, extract_metadata};
+
+use crate::client::{Association, FileEntry, FileMetadata};
+use crate::layout::{
+    CONFIG_FILE, DeviceConfig, FILE_DIR, FILE_EXT, PLAYLIST_DIR, PLAYLIST_EXT, PlaylistEntry,
+    PlaylistMode, RESUME_DIR, RESUME_EXT, ResumePoint, SOUND_DIR, VOLUME_FILE, WAV_HEADER_SIZE,
+    audio_file_from_path, duration_from_size, render_playlist,
+};
+
+pub struct Card {
+    root: PathBuf,
+}
+
+impl Card {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Path of a file on the card. FAT short names are case insensitive, so names are
+    /// stored upper case.
+    fn path(&self, dir: &str, name: &str, ext: &str) -> PathBuf {
+        self.root
+            .join(dir)
+            .join(format!("{}{}", name.to_ascii_uppercase(), ext))
+    }
+
+    fn file_path(&self, name: &str) -> PathBuf {
+        self.path(FILE_DIR, name, FILE_EXT)
+    }
+
+    fn playlist_path(&self, fob: &str) -> PathBuf {
+        self.path(PLAYLIST_DIR, fob, PLAYLIST_EXT)
+    }
+
+    /// Names without extension of all entries in `dir` ending with `ext`, sorted
+    fn list(&self, dir: &str, ext: &str) -> io::Result<Vec<String>> {
+        let entries = match fs::read_dir(self.root.join(dir)) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        let mut names = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            if !entry.file_type()?.is_file() {
+                continue;
+            }
+            let file_name = entry.file_name().to_string_lossy().to_ascii_uppercase();
+            if let Some(name) = file_name.strip_suffix(ext)
+                && !name.is_empty()
+                && name.len() <= 8
+            {
+                names.push(name.to_string());
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    pub async fn list_files(&self) -> io::Result<Vec<FileEntry>> {
+        let mut files = Vec::new();
+        for name in self.list(FILE_DIR, FILE_EXT)? {
+            let metadata = self.metadata(&name).await?;
+            files.push(FileEntry { name, metadata });
+        }
+        Ok(files)
+    }
+
+    /// Metadata from the INFO chunk and duration from the file size, like
+    /// `AudioFile::metadata` in the firmware
+    async fn read_metadata(&self, name: &str) -> io::Result<(Metadata, u32)> {
+        let mut file = fs::File::open(self.file_path(name))?;
+        let size = file.metadata()?.len();
+
+        let mut header = Vec::with_capacity(WAV_HEADER_SIZE as usize);
+        (&mut file).take(WAV_HEADER_SIZE).read_to_end(&mut header)?;
+        let metadata = extract_metadata(&mut &header[..]).await.unwrap_or_default();
+
+        Ok((metadata, duration_from_size(size)))
+    }
+
+    pub async fn metadata(&self, name: &str) -> io::Result<FileMetadata> {
+        let (metadata, duration) = self.read_metadata(name).await?;
+        Ok(FileMetadata {
+            artist: metadata.artist.to_string(),
+            title: metadata.title.to_string(),
+            album: metadata.album.to_string(),
+            duration,
+        })
+    }
+
+    /// Metadata of a file referenced by a playlist, with the firmware's fallback for missing files
+    async fn metadata_or_default(&self, name: &str) -> FileMetadata {
+        match self.metadata(name).await {
+            Ok(metadata) => metadata,
+            Err(_) => {
+                let metadata = Metadata::default();
+                FileMetadata {
+                    artist: metadata.artist.to_string(),
+                    title: metadata.title.to_string(),
+                    album: metadata.album.to_string(),
+                    duration: 60,
+                }
+            }
+        }
+    }
+
+    pub fn size(&self, name: &str) -> Option<u64> {
+        fs::metadata(self.file_path(name)).ok().map(|m| m.len())
+    }
+
+    pub fn read_file(&self, name: &str) -> io::Result<Vec<u8>> {
+        fs::read(self.file_path(name))
+    }
+
+    /// Create an empty file, truncating an existing one
+    pub fn create_file(&self, name: &str) -> io::Result<()> {
+        fs::create_dir_all(self.root.join(FILE_DIR))?;
+        fs::File::create(self.file_path(name))?;
+        Ok(())
+    }
+
+    pub fn write_file(&self, name: &str, data: &[u8]) -> io::Result<()> {
+        fs::create_dir_all(self.root.join(FILE_DIR))?;
+        fs::write(self.file_path(name), data)
+    }
+
+    /// Write `data` at `offset` of an existing file, like `AudioFile::append_at`
+    pub fn write_file_at(&self, name: &str, offset: u64, data: &[u8]) -> io::Result<()> {
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .open(self.file_path(name))?;
+        file.seek(SeekFrom::Start(offset))?;
+        file.write_all(data)
+    }
+
+    pub fn remove_file(&self, name: &str) -> io::Result<()> {
+        fs::remove_file(self.file_path(name))
+    }
+
+    /// Whether the system sound with clip name `name` has a clip
+    pub fn has_sound(&self, name: &str) -> bool {
+        self.path(SOUND_DIR, name, FILE_EXT).is_file()
+    }
+
+    pub fn write_sound(&self, name: &str, data: &[u8]) -> io::Result<()> {
+        fs::create_dir_all(self.root.join(SOUND_DIR))?;
+        fs::write(self.path(SOUND_DIR, name, FILE_EXT), data)
+    }
+
+    pub fn remove_sound(&self, name: &str) -> io::Result<()> {
+        fs::remove_file(self.path(SOUND_DIR, name, FILE_EXT))
+    }
+
+    pub fn list_playlists(&self) -> io::Result<Vec<String>> {
+        self.list(PLAYLIST_DIR, PLAYLIST_EXT)
+    }
+
+    /// File names of a playlist, skipping lines the firmware does not understand
+    pub fn read_playlist(&self, fob: &str) -> io::Result<Vec<String>> {
+        let content = fs::read_to_string(self.playlist_path(fob))?;
+        Ok(content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(audio_file_from_path)
+            .map(str::to_string)
+            .collect())
+    }
+
+    /// Repeat, shuffle and transition directives of a playlist, unset if it cannot be read
+    pub fn read_playlist_mode(&self, fob: &str) -> PlaylistMode {
+        fs::read_to_string(self.playlist_path(fob))
+            .map(|content| PlaylistMode::parse(&content))
+            .unwrap_or_default()
+    }
+
+    pub async fn write_playlist(
+        &self,
+        fob: &str,
+        files: &[String],
+        mode: &PlaylistMode,
+    ) -> io::Result<()> {
+        let mut entries = Vec::new();
+        for name in files {
+            let (metadata, duration) = self.read_metadata(name).await?;
+            entries.push(PlaylistEntry {
+                name: name.to_ascii_uppercase(),
+                metadata,
+                duration,
+            });
+        }
+
+        fs::create_dir_all(self.root.join(PLAYLIST_DIR))?;
+        fs::write(self.playlist_path(fob), render_playlist(&entries, mode))
+    }
+
+    pub async fn association(&self, fob: &str) -> io::Result<Association> {
+        let files = self.read_playlist(fob)?;
+        Ok(Association {
+            fob: fob.to_ascii_uppercase(),
+            files: self.with_metadata(&files).await,
+        })
+    }
+
+    pub async fn with_metadata(&self, files: &[String]) -> Vec<FileEntry> {
+        let mut entries = Vec::new();
+        for name in files {
+            entries.push(FileEntry {
+                name: name.clone(),
+                metadata: self.metadata_or_default(name).await,
+            });
+        }
+        entries
+    }
+
+    /// Forgets the position saved for `fob`, keeping its mode, when the fob gets other files
+    pub fn reset_resume_position(&self, fob: &str) -> io::Result<()> {
+        let resume_point = self.read_resume(fob);
+        if resume_point.index == 0 && resume_point.sample == 0 {
+            return Ok(());
+        }
+        self.write_resume(
+            fob,
+            &ResumePoint {
+                mode: resume_point.mode,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// The resume point of `fob`, or the default if none was saved or it cannot be read, like
+    /// `ResumePoint::read` in the firmware
+    pub fn read_resume(&self, fob: &str) -> ResumePoint {
+        fs::read(self.path(RESUME_DIR, fob, RESUME_EXT))
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn write_resume(&self, fob: &str, point: &ResumePoint) -> io::Result<()> {
+        fs::create_dir_all(self.root.join(RESUME_DIR))?;
+        fs::write(
+            self.path(RESUME_DIR, fob, RESUME_EXT),
+            serde_json::to_vec(point)?,
+        )
+    }
+
+    /// The last volume, `None` if none was saved or it cannot be read, like `SavedVolume::read`
+    /// in the firmware
+    pub fn read_volume(&self) -> Option<u8> {
+        let data = fs::read(self.root.join(VOLUME_FILE)).ok()?;
+        let saved: serde_json::Value = serde_json::from_slice(&data).ok()?;
+        saved["volume"].as_u64()?.try_into().ok()
+    }
+
+    pub fn write_volume(&self, volume: u8) -> io::Result<()> {
+        fs::create_dir_all(&self.root)?;
+        fs::write(
+            self.root.join(VOLUME_FILE),
+            serde_json::to_vec(&serde_json::json!({ "volume": volume }))?,
+        )
+    }
+
+    pub fn read_config(&self) -> io::Result<Option<DeviceConfig>> {
+        match fs::read(self.root.join(CONFIG_FILE)) {
+            Ok(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn write_config(&self, config: &DeviceConfig) -> io::Result<()> {
+        fs::create_dir_all(&self.root)?;
+        fs::write(self.root.join(CONFIG_FILE), serde_json::to_vec(config)?)
+    }
+
+    /// Returns whether a configuration existed
+    pub fn delete_config(&self) -> io::Result<bool> {
+        match fs::remove_file(self.root.join(CONFIG_FILE)) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+}
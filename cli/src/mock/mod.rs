@@ -0,0 +1,1477 @@
+//! Host-side stand-in for the device, serving the REST API from `API.md` on top of a directory
+//! with the SD card layout
+//!
+//! Besides the device API, `POST /mock/fob` simulates scanning a fob, `DELETE /mock/fob`
+//! taking it off the reader, `POST /mock/play_pause` a short press of the play/pause button and
+//! `GET /mock/state` exposes state that the device API does not report.
+use axum::{
+    Json, Router,
+    body::{Body, Bytes},
+    extract::{DefaultBodyLimit, Path, Query, State},
+    http::{HeaderMap, HeaderValue, StatusCode, Uri, header},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::layout::{
+    DeviceConfig, EqConfig, MAX_VOLUME, PlaylistMode, Repeat, RescanAction, RescanConfig,
+    ResumeMode, ResumePoint, SAMPLE_RATE, SYSTEM_SOUNDS, TagPresenceConfig, UnknownFobAction,
+};
+
+mod card;
+mod player;
+
+pub use card::Card;
+use player::{Player, Seek};
+
+/// Playlist name the firmware uses for files played through the API
+const WEB_API_PLAYLIST: &str = "WEB_API";
+/// Playlist name the firmware uses for files added to the queue while stopped
+const QUEUE_PLAYLIST: &str = "QUEUE";
+/// How long a fob without playlist waits for one in learn mode
+const LEARN_TIMEOUT: Duration = Duration::from_secs(120);
+
+pub struct MockDevice {
+    card: Card,
+    player: Mutex<Player>,
+    /// Fob being played whose resume point is kept up to date
+    resume_fob: Mutex<Option<String>>,
+    last_fob: Mutex<Option<String>>,
+    /// Fob without playlist in learn mode and when learn mode ends
+    learning: Mutex<Option<(String, Instant)>>,
+    /// Fob whose removal paused its playlist and when the playlist stops
+    removed_fob: Mutex<Option<(String, Instant)>>,
+    /// When a fob was scanned last, for the cooldown of second swipes
+    last_scan: Mutex<Option<Instant>>,
+    /// Directory with the built web UI
+    web_dir: Option<PathBuf>,
+}
+
+type AppState = Arc<MockDevice>;
+
+impl MockDevice {
+    pub fn new(card: Card, web_dir: Option<PathBuf>) -> Self {
+        // Like the firmware: configured limit, then the configured or last volume
+        let mut player = Player::new(Instant::now());
+        let config = card.read_config().ok().flatten().unwrap_or_default();
+        if let Some(max_volume) = config.max_volume {
+            player.set_max_volume(max_volume);
+        }
+        if let Some(volume) = config.startup_volume.or_else(|| card.read_volume()) {
+            player.set_volume(volume);
+        }
+        Self {
+            card,
+            player: Mutex::new(player),
+            resume_fob: Mutex::new(None),
+            last_fob: Mutex::new(None),
+            learning: Mutex::new(None),
+            removed_fob: Mutex::new(None),
+            last_scan: Mutex::new(None),
+            web_dir,
+        }
+    }
+
+    /// Saves the volume after a change so it survives a restart of the mock device
+    fn save_volume(&self) {
+        let volume = self.player.lock().unwrap().volume();
+        if self.card.read_volume() != Some(volume)
+            && let Err(e) = self.card.write_volume(volume)
+        {
+            eprintln!("Mock device: saving volume failed: {}", e);
+        }
+    }
+
+    pub fn router(self) -> Router {
+        Router::new()
+            .route("/api/files", get(list_files))
+            .route(
+                "/api/files/{name}",
+                get(file_metadata)
+                    .head(file_size)
+                    .post(create_file)
+                    .patch(patch_file)
+                    .put(put_file)
+                    .delete(delete_file),
+            )
+            .route("/api/files/{name}/data", get(download_file))
+            .route("/api/system_sounds", get(list_sounds))
+            .route(
+                "/api/system_sounds/{sound}",
+                post(play_sound).put(put_sound).delete(delete_sound),
+            )
+            .route("/api/last_fob", get(last_fob))
+            .route("/api/associations", get(list_associations).post(associate))
+            .route("/api/resume", get(get_resume).put(put_resume))
+            .route("/api/playback/status", get(status))
+            .route("/api/playback/current_playlist", get(current_playlist))
+            .route("/api/playback/play", post(play))
+            .route("/api/playback/queue", post(queue))
+            .route("/api/playback/stop", post(stop))
+            .route("/api/playback/pause", post(pause))
+            .route("/api/playback/volume_up", post(volume_up))
+            .route("/api/playback/volume_down", post(volume_down))
+            .route("/api/playback/next", post(next))
+            .route("/api/playback/previous", post(previous))
+            .route("/api/playback/seek", post(seek))
+            .route("/api/playback/sleep", post(sleep))
+            .route("/api/playback/mode", get(get_mode).put(put_mode))
+            .route(
+                "/api/config",
+                get(get_config).put(put_config).delete(delete_config),
+            )
+            .route("/mock/fob", post(scan_fob).delete(remove_fob))
+            .route("/mock/play_pause", post(press_play_pause))
+            .route("/mock/state", get(mock_state))
+            .route("/api/{*path}", get(not_found).post(not_found))
+            .fallback(web_ui)
+            .layer(DefaultBodyLimit::disable())
+            .with_state(Arc::new(self))
+    }
+
+    /// Reacts to a fob put on the reader like the firmware. A fob whose removal paused its
+    /// playlist resumes it, scanning the fob playing or paused again applies the second swipe
+    /// action.
+    async fn fob_scanned(&self, fob: &str) {
+        let fob = fob.to_ascii_uppercase();
+        let removed = self.removed_fob.lock().unwrap().take();
+        if removed.is_some_and(|(removed, _)| removed == fob) {
+            let mut player = self.player.lock().unwrap();
+            if player.state(Instant::now()) == player::State::Paused {
+                println!("Mock device: fob returned, resuming");
+                player.pause(Instant::now());
+                return;
+            }
+        }
+
+        let last_scan = self.last_scan.lock().unwrap().replace(Instant::now());
+        let config = self.card.read_config().ok().flatten().unwrap_or_default();
+        let rescan = config.rescan.unwrap_or_default();
+        let state = {
+            let mut player = self.player.lock().unwrap();
+            let state = player.state(Instant::now());
+            let fob_active = state != player::State::Stopped
+                && player
+                    .current_playlist(Instant::now())
+                    .is_some_and(|playlist| playlist.playlist_name == fob);
+            fob_active.then_some(state)
+        };
+        // A paused playlist only counts for pausing, which resumes it
+        if state.is_none_or(|state| {
+            state == player::State::Paused && rescan.action != RescanAction::Pause
+        }) {
+            self.play_playlist_ref(&fob, false).await;
+            return;
+        }
+
+        let cooldown = Duration::from_secs(rescan.cooldown_seconds.into());
+        if last_scan.is_some_and(|last| last.elapsed() < cooldown) {
+            println!("Mock device: fob {} scanned again within the cooldown", fob);
+            return;
+        }
+        println!(
+            "Mock device: fob {} scanned again, {:?}",
+            fob, rescan.action
+        );
+        match rescan.action {
+            RescanAction::Ignore => {}
+            RescanAction::Pause => self.toggle_pause(),
+            RescanAction::Next => {
+                self.player.lock().unwrap().next(Instant::now());
+                self.save_resume_point();
+            }
+            RescanAction::Restart => self.play_playlist_ref(&fob, true).await,
+        }
+    }
+
+    /// Plays a fob's playlist, continuing at the fob's resume point or from its first track
+    /// with `restart`
+    async fn play_playlist_ref(&self, fob: &str, restart: bool) {
+        let fob = fob.to_ascii_uppercase();
+        let Ok(files) = self.card.read_playlist(&fob) else {
+            self.unknown_fob(fob);
+            return;
+        };
+        self.stop();
+        let resume_point = self.card.read_resume(&fob);
+        let start = if restart {
+            ResumePoint {
+                mode: resume_point.mode,
+                ..Default::default()
+            }
+        } else {
+            resume_point.start(files.len())
+        };
+        let mode = self.card.read_playlist_mode(&fob);
+        self.play_files(&fob, &files, &mode).await;
+        self.player.lock().unwrap().start_at(
+            start.index,
+            Duration::from_secs_f64(start.sample as f64 / SAMPLE_RATE as f64),
+            Instant::now(),
+        );
+        self.resume_fob.lock().unwrap().replace(fob);
+    }
+
+    /// Reacts to a fob without playlist as configured, playback goes on
+    fn unknown_fob(&self, fob: String) {
+        let config = self.card.read_config().ok().flatten().unwrap_or_default();
+        let action = config.unknown_fob.unwrap_or_default();
+        println!("Mock device: no playlist for fob {}, {:?}", fob, action);
+        match action {
+            UnknownFobAction::Ignore => return,
+            UnknownFobAction::Sound => {}
+            UnknownFobAction::Learn => {
+                let deadline = Instant::now() + LEARN_TIMEOUT;
+                self.learning.lock().unwrap().replace((fob, deadline));
+            }
+        }
+        println!("Mock device: playing system sound unknown_fob");
+    }
+
+    /// Pauses, or resumes while paused
+    fn toggle_pause(&self) {
+        // Paused or resumed by hand, the returning fob no longer matters
+        self.removed_fob.lock().unwrap().take();
+        let paused = {
+            let mut player = self.player.lock().unwrap();
+            player.pause(Instant::now());
+            player.state(Instant::now()) == player::State::Paused
+        };
+        if paused {
+            self.save_resume_point();
+        }
+    }
+
+    /// Pauses the playlist of the fob taken off the reader in tag presence mode, stopping it
+    /// unless the fob returns in time
+    fn fob_left(self: &Arc<Self>, fob: String) {
+        let config = self.card.read_config().ok().flatten().unwrap_or_default();
+        let Some(tag_presence) = config.tag_presence else {
+            return;
+        };
+        {
+            let mut player = self.player.lock().unwrap();
+            let playing_fob = player
+                .current_playlist(Instant::now())
+                .is_some_and(|playlist| playlist.playlist_name == fob);
+            if !playing_fob || player.state(Instant::now()) != player::State::Playing {
+                return;
+            }
+            player.pause(Instant::now());
+        }
+        self.save_resume_point();
+
+        let deadline = Instant::now() + Duration::from_secs(tag_presence.stop_after_seconds.into());
+        self.removed_fob.lock().unwrap().replace((fob, deadline));
+        let device = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep_until(deadline.into()).await;
+            let mut removed = device.removed_fob.lock().unwrap();
+            if removed.as_ref().is_some_and(|(_, end)| *end == deadline) {
+                removed.take();
+                drop(removed);
+                if device.player.lock().unwrap().state(Instant::now()) == player::State::Paused {
+                    println!("Mock device: fob did not return, stopping");
+                    device.stop();
+                }
+            }
+        });
+    }
+
+    /// Fob waiting for a playlist in learn mode, until `LEARN_TIMEOUT` passed
+    fn learning_fob(&self) -> Option<String> {
+        let mut learning = self.learning.lock().unwrap();
+        if learning
+            .as_ref()
+            .is_some_and(|(_, deadline)| *deadline <= Instant::now())
+        {
+            *learning = None;
+        }
+        learning.as_ref().map(|(fob, _)| fob.clone())
+    }
+
+    /// Saves the playlist playing to the fob in learn mode, with the directives of the fob
+    /// playlist playing
+    async fn confirm_learning(&self, fob: &str) -> std::io::Result<()> {
+        let playlist = {
+            let mut player = self.player.lock().unwrap();
+            match player.state(Instant::now()) {
+                player::State::Stopped => None,
+                _ => player.current_playlist(Instant::now()),
+            }
+        };
+        let Some(playlist) = playlist else {
+            println!("Mock device: nothing playing to save to fob {}", fob);
+            println!("Mock device: playing system sound error");
+            return Ok(());
+        };
+        let files: Vec<String> = playlist.files.into_iter().map(|file| file.file).collect();
+        let mode = self.card.read_playlist_mode(&playlist.playlist_name);
+        self.card.reset_resume_position(fob)?;
+        self.card.write_playlist(fob, &files, &mode).await?;
+        self.learning.lock().unwrap().take();
+        println!("Mock device: saved playlist to fob {}", fob);
+        println!("Mock device: playing system sound fob_recognized");
+        Ok(())
+    }
+
+    /// Saves where the fob being played is, or the start of the playlist if it finished.
+    /// Unlike the firmware, the mock only saves on commands and not periodically.
+    fn save_resume_point(&self) {
+        let Some(fob) = self.resume_fob.lock().unwrap().clone() else {
+            return;
+        };
+        let saved = self.card.read_resume(&fob);
+        if saved.mode == ResumeMode::Restart {
+            return;
+        }
+
+        let (index, position) = self
+            .player
+            .lock()
+            .unwrap()
+            .position(Instant::now())
+            .unwrap_or_default();
+        let point = ResumePoint {
+            mode: saved.mode,
+            index,
+            sample: (position.as_secs_f64() * SAMPLE_RATE as f64) as u64,
+        };
+        if let Err(e) = self.card.write_resume(&fob, &point) {
+            eprintln!("Mock device: saving resume point: {}", e);
+        }
+    }
+
+    /// Saves the resume point and stops keeping it up to date, like the firmware does once the
+    /// queue of a fob's playlist was edited
+    fn stop_resuming(&self) {
+        self.save_resume_point();
+        self.resume_fob.lock().unwrap().take();
+    }
+
+    fn stop(&self) {
+        self.save_resume_point();
+        self.resume_fob.lock().unwrap().take();
+        self.player.lock().unwrap().stop();
+    }
+
+    async fn play_files(&self, name: &str, files: &[String], mode: &PlaylistMode) {
+        self.removed_fob.lock().unwrap().take();
+        let entries = self.card.with_metadata(files).await;
+        let exists: Vec<bool> = files
+            .iter()
+            .map(|file| self.card.size(file).is_some())
+            .collect();
+        self.player
+            .lock()
+            .unwrap()
+            .play(name, entries, &exists, mode, Instant::now());
+    }
+}
+
+type ApiResult<T> = Result<T, StatusCode>;
+
+fn internal_error(e: std::io::Error) -> StatusCode {
+    eprintln!("Mock device: {}", e);
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+/// File and fob names are `heapless::String<8>` in the firmware, longer names do not route
+fn check_name(name: &str) -> ApiResult<()> {
+    if name.is_empty() || name.len() > 8 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    Ok(())
+}
+
+async fn not_found() -> StatusCode {
+    StatusCode::NOT_FOUND
+}
+
+// ---- Files ----
+
+async fn list_files(State(device): State<AppState>) -> ApiResult<impl IntoResponse> {
+    Ok(Json(
+        device.card.list_files().await.map_err(internal_error)?,
+    ))
+}
+
+async fn file_metadata(
+    State(device): State<AppState>,
+    Path(name): Path<String>,
+) -> ApiResult<impl IntoResponse> {
+    check_name(&name)?;
+    let metadata = device
+        .card
+        .metadata(&name)
+        .await
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+    Ok(Json(metadata))
+}
+
+async fn download_file(
+    State(device): State<AppState>,
+    Path(name): Path<String>,
+) -> ApiResult<impl IntoResponse> {
+    check_name(&name)?;
+    let data = device
+        .card
+        .read_file(&name)
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+    Ok(([(header::CONTENT_TYPE, "audio/wav")], data))
+}
+
+async fn file_size(
+    State(device): State<AppState>,
+    Path(name): Path<String>,
+) -> ApiResult<impl IntoResponse> {
+    check_name(&name)?;
+    let size = device.card.size(&name).ok_or(StatusCode::NOT_FOUND)?;
+    Ok([("Upload-Offset", size.to_string())])
+}
+
+async fn create_file(
+    State(device): State<AppState>,
+    Path(name): Path<String>,
+) -> ApiResult<impl IntoResponse> {
+    check_name(&name)?;
+    device.card.create_file(&name).map_err(internal_error)?;
+    Ok((
+        StatusCode::CREATED,
+        [(header::LOCATION, format!("/api/files/{}", name))],
+    ))
+}
+
+async fn patch_file(
+    State(device): State<AppState>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<StatusCode, (StatusCode, &'static str)> {
+    check_name(&name).map_err(|status| (status, ""))?;
+    let offset = headers
+        .get("Upload-Offset")
+        .ok_or((StatusCode::BAD_REQUEST, "Upload-Offset header required"))?
+        .to_str()
+        .ok()
+        .and_then(|offset| offset.parse::<u64>().ok())
+        .ok_or((StatusCode::BAD_REQUEST, "Invalid Upload-Offset header"))?;
+
+    let size = device.card.size(&name).ok_or((StatusCode::NOT_FOUND, ""))?;
+    if offset > size {
+        return Err((StatusCode::BAD_REQUEST, "Upload-Offset exceeds file size"));
+    }
+
+    device
+        .card
+        .write_file_at(&name, offset, &body)
+        .map_err(|e| (internal_error(e), ""))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn put_file(
+    State(device): State<AppState>,
+    Path(name): Path<String>,
+    body: Bytes,
+) -> ApiResult<StatusCode> {
+    check_name(&name)?;
+    device
+        .card
+        .write_file(&name, &body)
+        .map_err(internal_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn delete_file(
+    State(device): State<AppState>,
+    Path(name): Path<String>,
+) -> ApiResult<StatusCode> {
+    check_name(&name)?;
+    match device.card.remove_file(&name) {
+        Ok(()) => Ok(StatusCode::NO_CONTENT),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(StatusCode::NOT_FOUND),
+        Err(e) => Err(internal_error(e)),
+    }
+}
+
+// ---- System sounds ----
+
+#[derive(Serialize)]
+struct SystemSoundEntry {
+    sound: &'static str,
+    custom: bool,
+}
+
+/// Clip name of the system sound called `sound` in the API
+fn sound_file(sound: &str) -> ApiResult<&'static str> {
+    SYSTEM_SOUNDS
+        .iter()
+        .find(|(name, _)| *name == sound)
+        .map(|(_, file)| *file)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn list_sounds(State(device): State<AppState>) -> impl IntoResponse {
+    let sounds: Vec<_> = SYSTEM_SOUNDS
+        .iter()
+        .map(|(sound, file)| SystemSoundEntry {
+            sound,
+            custom: device.card.has_sound(file),
+        })
+        .collect();
+    Json(sounds)
+}
+
+async fn put_sound(
+    State(device): State<AppState>,
+    Path(sound): Path<String>,
+    body: Bytes,
+) -> ApiResult<StatusCode> {
+    let file = sound_file(&sound)?;
+    device
+        .card
+        .write_sound(file, &body)
+        .map_err(internal_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn delete_sound(
+    State(device): State<AppState>,
+    Path(sound): Path<String>,
+) -> ApiResult<StatusCode> {
+    let file = sound_file(&sound)?;
+    match device.card.remove_sound(file) {
+        Ok(()) => Ok(StatusCode::NO_CONTENT),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(StatusCode::NOT_FOUND),
+        Err(e) => Err(internal_error(e)),
+    }
+}
+
+/// The mock has no speaker, it only logs the sound
+async fn play_sound(Path(sound): Path<String>) -> ApiResult<StatusCode> {
+    sound_file(&sound)?;
+    println!("Mock device: playing system sound {}", sound);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// ---- Associations ----
+
+#[derive(Serialize)]
+struct LastFob {
+    last_fob: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AssociationRequest {
+    fob: String,
+    files: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct ListQuery {
+    fob: Option<String>,
+}
+
+async fn last_fob(State(device): State<AppState>) -> impl IntoResponse {
+    Json(LastFob {
+        last_fob: device.last_fob.lock().unwrap().clone(),
+    })
+}
+
+async fn list_associations(
+    State(device): State<AppState>,
+    Query(query): Query<ListQuery>,
+) -> ApiResult<Response> {
+    if let Some(fob) = query.fob {
+        check_name(&fob)?;
+        let association = device
+            .card
+            .association(&fob)
+            .await
+            .map_err(|_| StatusCode::NOT_FOUND)?;
+        return Ok(Json(association).into_response());
+    }
+
+    let mut associations = Vec::new();
+    for fob in device.card.list_playlists().map_err(internal_error)? {
+        associations.push(
+            device
+                .card
+                .association(&fob)
+                .await
+                .map_err(internal_error)?,
+        );
+    }
+    Ok(Json(associations).into_response())
+}
+
+async fn associate(
+    State(device): State<AppState>,
+    Json(request): Json<AssociationRequest>,
+) -> ApiResult<StatusCode> {
+    check_name(&request.fob)?;
+    for file in &request.files {
+        check_name(file).map_err(|_| StatusCode::BAD_REQUEST)?;
+    }
+
+    // The saved position belongs to the old playlist
+    device
+        .card
+        .reset_resume_position(&request.fob)
+        .map_err(internal_error)?;
+
+    // Keep the playback directives of the playlist being replaced
+    let mode = device.card.read_playlist_mode(&request.fob);
+    device
+        .card
+        .write_playlist(&request.fob, &request.files, &mode)
+        .await
+        .map_err(internal_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+struct ResumeQuery {
+    fob: String,
+}
+
+#[derive(Serialize)]
+struct ResumeResponse {
+    fob: String,
+    mode: ResumeMode,
+    index_in_playlist: usize,
+    position_seconds: u32,
+}
+
+#[derive(Deserialize)]
+struct ResumeRequest {
+    fob: String,
+    mode: ResumeMode,
+}
+
+async fn get_resume(
+    State(device): State<AppState>,
+    Query(query): Query<ResumeQuery>,
+) -> ApiResult<impl IntoResponse> {
+    check_name(&query.fob).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let resume_point = device.card.read_resume(&query.fob);
+    Ok(Json(ResumeResponse {
+        fob: query.fob,
+        mode: resume_point.mode,
+        index_in_playlist: resume_point.index,
+        position_seconds: (resume_point.sample / SAMPLE_RATE) as u32,
+    }))
+}
+
+async fn put_resume(
+    State(device): State<AppState>,
+    Json(request): Json<ResumeRequest>,
+) -> ApiResult<StatusCode> {
+    check_name(&request.fob).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let resume_point = device.card.read_resume(&request.fob);
+    device
+        .card
+        .write_resume(
+            &request.fob,
+            &ResumePoint {
+                mode: request.mode,
+                ..resume_point
+            },
+        )
+        .map_err(internal_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// ---- Playback ----
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum PlayRequest {
+    File(String),
+    Playlist(Vec<String>),
+    PlaylistRef(String),
+}
+
+async fn status(State(device): State<AppState>) -> impl IntoResponse {
+    let status = device.player.lock().unwrap().status(Instant::now());
+    Json(player::StatusResponse {
+        learning_fob: device.learning_fob(),
+        ..status
+    })
+}
+
+async fn current_playlist(State(device): State<AppState>) -> impl IntoResponse {
+    Json(
+        device
+            .player
+            .lock()
+            .unwrap()
+            .current_playlist(Instant::now()),
+    )
+}
+
+async fn play(State(device): State<AppState>, Json(request): Json<PlayRequest>) -> StatusCode {
+    match request {
+        PlayRequest::File(file) => {
+            device.stop();
+            device
+                .play_files(WEB_API_PLAYLIST, &[file], &PlaylistMode::default())
+                .await
+        }
+        PlayRequest::Playlist(files) => {
+            device.stop();
+            device
+                .play_files(WEB_API_PLAYLIST, &files, &PlaylistMode::default())
+                .await
+        }
+        PlayRequest::PlaylistRef(fob) => device.play_playlist_ref(&fob, false).await,
+    }
+    StatusCode::NO_CONTENT
+}
+
+/// Edits the playlist playing, indices are positions in the playlist
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum QueueRequest {
+    Append(PlayRequest),
+    InsertNext(PlayRequest),
+    Remove(usize),
+    Move { from: usize, to: usize },
+    Clear,
+}
+
+async fn queue(State(device): State<AppState>, Json(request): Json<QueueRequest>) -> StatusCode {
+    let now = Instant::now();
+    let playing = device.player.lock().unwrap().state(now) != player::State::Stopped;
+    let (source, next) = match request {
+        QueueRequest::Append(source) => (source, false),
+        QueueRequest::InsertNext(source) => (source, true),
+        QueueRequest::Remove(_) | QueueRequest::Move { .. } if !playing => {
+            return StatusCode::CONFLICT;
+        }
+        QueueRequest::Remove(index) => {
+            device.stop_resuming();
+            return match device.player.lock().unwrap().remove(index, now) {
+                true => StatusCode::NO_CONTENT,
+                false => StatusCode::NOT_FOUND,
+            };
+        }
+        QueueRequest::Move { from, to } => {
+            device.stop_resuming();
+            return match device.player.lock().unwrap().move_file(from, to, now) {
+                true => StatusCode::NO_CONTENT,
+                false => StatusCode::NOT_FOUND,
+            };
+        }
+        QueueRequest::Clear => {
+            if playing {
+                device.stop_resuming();
+                device.player.lock().unwrap().clear_queue(now);
+            }
+            return StatusCode::NO_CONTENT;
+        }
+    };
+
+    let files = match source {
+        PlayRequest::File(file) => vec![file],
+        PlayRequest::Playlist(files) => files,
+        PlayRequest::PlaylistRef(fob) => device.card.read_playlist(&fob).unwrap_or_default(),
+    };
+    if !playing {
+        if !files.is_empty() {
+            device
+                .play_files(QUEUE_PLAYLIST, &files, &PlaylistMode::default())
+                .await;
+        }
+        return StatusCode::NO_CONTENT;
+    }
+    device.stop_resuming();
+    let entries = device.card.with_metadata(&files).await;
+    let exists: Vec<bool> = files
+        .iter()
+        .map(|file| device.card.size(file).is_some())
+        .collect();
+    device
+        .player
+        .lock()
+        .unwrap()
+        .enqueue(entries, &exists, next, Instant::now());
+    StatusCode::NO_CONTENT
+}
+
+async fn stop(State(device): State<AppState>) -> StatusCode {
+    device
+        .player
+        .lock()
+        .unwrap()
+        .set_sleep_timer(0, Instant::now());
+    device.stop();
+    StatusCode::NO_CONTENT
+}
+
+async fn pause(State(device): State<AppState>) -> StatusCode {
+    device.toggle_pause();
+    StatusCode::NO_CONTENT
+}
+
+async fn volume_up(State(device): State<AppState>) -> StatusCode {
+    device.player.lock().unwrap().volume_up();
+    device.save_volume();
+    StatusCode::NO_CONTENT
+}
+
+async fn volume_down(State(device): State<AppState>) -> StatusCode {
+    device.player.lock().unwrap().volume_down();
+    device.save_volume();
+    StatusCode::NO_CONTENT
+}
+
+async fn next(State(device): State<AppState>) -> StatusCode {
+    device.player.lock().unwrap().next(Instant::now());
+    device.save_resume_point();
+    StatusCode::NO_CONTENT
+}
+
+async fn previous(State(device): State<AppState>) -> StatusCode {
+    device.player.lock().unwrap().previous(Instant::now());
+    device.save_resume_point();
+    StatusCode::NO_CONTENT
+}
+
+async fn seek(State(device): State<AppState>, Json(seek): Json<Seek>) -> StatusCode {
+    device.player.lock().unwrap().seek(seek, Instant::now());
+    StatusCode::NO_CONTENT
+}
+
+#[derive(Deserialize)]
+struct SleepRequest {
+    minutes: u16,
+}
+
+async fn sleep(State(device): State<AppState>, Json(request): Json<SleepRequest>) -> StatusCode {
+    device
+        .player
+        .lock()
+        .unwrap()
+        .set_sleep_timer(request.minutes, Instant::now());
+    StatusCode::NO_CONTENT
+}
+
+/// Changes the global playback mode, omitted fields stay as they are
+#[derive(Deserialize)]
+struct ModeRequest {
+    #[serde(default)]
+    repeat: Option<Repeat>,
+    #[serde(default)]
+    shuffle: Option<bool>,
+}
+
+async fn get_mode(State(device): State<AppState>) -> impl IntoResponse {
+    Json(device.player.lock().unwrap().mode(Instant::now()))
+}
+
+async fn put_mode(State(device): State<AppState>, Json(request): Json<ModeRequest>) -> StatusCode {
+    let mut player = device.player.lock().unwrap();
+    if let Some(repeat) = request.repeat {
+        player.set_repeat(repeat, Instant::now());
+    }
+    if let Some(shuffle) = request.shuffle {
+        player.set_shuffle(shuffle, Instant::now());
+    }
+    StatusCode::NO_CONTENT
+}
+
+// ---- Configuration ----
+
+#[derive(Serialize)]
+struct PublicConfig {
+    ssid: String,
+    max_volume: Option<u8>,
+    startup_volume: Option<u8>,
+    eq: Option<EqConfig>,
+    unknown_fob: Option<UnknownFobAction>,
+    tag_presence: Option<TagPresenceConfig>,
+    rescan: Option<RescanConfig>,
+}
+
+async fn get_config(State(device): State<AppState>) -> ApiResult<impl IntoResponse> {
+    match device.card.read_config() {
+        Ok(Some(config)) => Ok(Json(PublicConfig {
+            ssid: config.ssid,
+            max_volume: config.max_volume,
+            startup_volume: config.startup_volume,
+            eq: config.eq,
+            unknown_fob: config.unknown_fob,
+            tag_presence: config.tag_presence,
+            rescan: config.rescan,
+        })),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => Err(internal_error(e)),
+    }
+}
+
+async fn put_config(
+    State(device): State<AppState>,
+    Json(mut config): Json<DeviceConfig>,
+) -> ApiResult<StatusCode> {
+    config.max_volume = config
+        .max_volume
+        .map(|max_volume| max_volume.clamp(1, MAX_VOLUME));
+    // Wi-Fi settings apply after a restart, the volume limit right away
+    device
+        .player
+        .lock()
+        .unwrap()
+        .set_max_volume(config.max_volume.unwrap_or(MAX_VOLUME));
+    device.save_volume();
+    if config.unknown_fob != Some(UnknownFobAction::Learn) {
+        device.learning.lock().unwrap().take();
+    }
+    device.card.write_config(&config).map_err(internal_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn delete_config(State(device): State<AppState>) -> ApiResult<StatusCode> {
+    device.player.lock().unwrap().set_max_volume(MAX_VOLUME);
+    device.learning.lock().unwrap().take();
+    match device.card.delete_config().map_err(internal_error)? {
+        true => Ok(StatusCode::NO_CONTENT),
+        false => Err(StatusCode::NOT_FOUND),
+    }
+}
+
+// ---- Mock control ----
+
+#[derive(Deserialize)]
+struct ScanRequest {
+    fob: String,
+}
+
+#[derive(Serialize)]
+struct MockState {
+    volume: u8,
+}
+
+async fn scan_fob(
+    State(device): State<AppState>,
+    Json(request): Json<ScanRequest>,
+) -> ApiResult<StatusCode> {
+    check_name(&request.fob).map_err(|_| StatusCode::BAD_REQUEST)?;
+    println!("Mock device: fob scanned: {}", request.fob);
+    device.last_fob.lock().unwrap().replace(request.fob.clone());
+    device.fob_scanned(&request.fob).await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// A short press saves the playlist playing to a fob in learn mode, else it pauses
+async fn press_play_pause(State(device): State<AppState>) -> ApiResult<StatusCode> {
+    match device.learning_fob() {
+        Some(fob) => device
+            .confirm_learning(&fob)
+            .await
+            .map_err(internal_error)?,
+        None => device.toggle_pause(),
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn remove_fob(State(device): State<AppState>) -> StatusCode {
+    let Some(fob) = device.last_fob.lock().unwrap().clone() else {
+        return StatusCode::NOT_FOUND;
+    };
+    println!("Mock device: fob removed: {}", fob);
+    device.fob_left(fob.to_ascii_uppercase());
+    StatusCode::NO_CONTENT
+}
+
+async fn mock_state(State(device): State<AppState>) -> impl IntoResponse {
+    Json(MockState {
+        volume: device.player.lock().unwrap().volume(),
+    })
+}
+
+// ---- Web UI ----
+
+/// Serves files of the built web UI, falling back to `index.html` like the firmware. Files
+/// may be pre-compressed (`*.gz`), like in `firmware/public`.
+async fn web_ui(State(device): State<AppState>, uri: Uri) -> Response {
+    let Some(web_dir) = &device.web_dir else {
+        return (
+            StatusCode::NOT_FOUND,
+            "Web UI not available, pass --web-dir with the built web UI",
+        )
+            .into_response();
+    };
+
+    let path = uri.path().trim_start_matches('/');
+    if path.split('/').any(|segment| segment == "..") {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    let candidates = [path, "index.html"];
+    for candidate in candidates.into_iter().filter(|c| !c.is_empty()) {
+        let file = web_dir.join(candidate);
+        let content_type = HeaderValue::from_static(content_type(candidate));
+        if file.is_file()
+            && let Ok(data) = std::fs::read(&file)
+        {
+            return ([(header::CONTENT_TYPE, content_type)], data).into_response();
+        }
+
+        let gzipped = web_dir.join(format!("{}.gz", candidate));
+        if let Ok(data) = std::fs::read(&gzipped) {
+            return (
+                [
+                    (header::CONTENT_TYPE, content_type),
+                    (header::CONTENT_ENCODING, HeaderValue::from_static("gzip")),
+                ],
+                Body::from(data),
+            )
+                .into_response();
+        }
+    }
+
+    StatusCode::NOT_FOUND.into_response()
+}
+
+fn content_type(path: &str) -> &'static str {
+    match path.rsplit_once('.').map(|(_, extension)| extension) {
+        Some("html") => "text/html",
+        Some("js") => "text/javascript",
+        Some("css") => "text/css",
+        Some("wasm") => "application/wasm",
+        Some("json") => "application/json",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("ico") => "image/x-icon",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{DeviceClient, PlaybackCommand};
+    use crate::fixtures::{TempDir, serve};
+    use crate::wav::tests::device_wav;
+
+    #[tokio::test]
+    async fn test_upload_associate_and_scan() {
+        let root = TempDir::new("mock");
+        let address = serve(MockDevice::new(Card::new(&*root), None).router()).await;
+        let client = DeviceClient::new(&address);
+        let http = reqwest::Client::new();
+
+        // 5 seconds of audio, uploaded in two chunks
+        let data = device_wav(108, "Artist");
+        client.create_file("abcd1234").await.unwrap();
+        client
+            .upload_chunk("abcd1234", 0, data[..1000].to_vec())
+            .await
+            .unwrap();
+        assert_eq!(client.file_size("abcd1234").await.unwrap(), Some(1000));
+        assert!(
+            client
+                .upload_chunk("abcd1234", 2000, data[1000..].to_vec())
+                .await
+                .is_err()
+        );
+        client
+            .upload_chunk("abcd1234", 1000, data[1000..].to_vec())
+            .await
+            .unwrap();
+        assert_eq!(client.download_file("ABCD1234").await.unwrap(), data);
+        assert_eq!(client.file_size("missing").await.unwrap(), None);
+
+        let files = client.list_files().await.unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].name, "ABCD1234");
+        assert_eq!(files[0].metadata.artist, "Artist");
+        assert_eq!(files[0].metadata.duration, 5);
+
+        // Playlists are written in the firmware's format
+        client
+            .associate("1a2b3c4d", &["ABCD1234".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(root.join("FOBS/1A2B3C4D.M3U")).unwrap(),
+            "#EXTM3U\r\n#EXTINF:5,Artist - Song\r\n..\\FILES\\ABCD1234.WAV\r\n"
+        );
+        let associations = client.list_associations().await.unwrap();
+        assert_eq!(associations[0].fob, "1A2B3C4D");
+        assert_eq!(associations[0].files[0].name, "ABCD1234");
+
+        http.post(format!("http://{}/mock/fob", address))
+            .json(&serde_json::json!({ "fob": "1a2b3c4d" }))
+            .send()
+            .await
+            .unwrap()
+            .error_for_status()
+            .unwrap();
+
+        let last_fob: serde_json::Value = http
+            .get(format!("http://{}/api/last_fob", address))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(last_fob["last_fob"], "1a2b3c4d");
+
+        let status: serde_json::Value = http
+            .get(format!("http://{}/api/playback/status", address))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(status["state"], "Playing");
+        assert_eq!(status["playlist_name"], "1A2B3C4D");
+
+        let playlist: serde_json::Value = http
+            .get(format!("http://{}/api/playback/current_playlist", address))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(playlist["files"][0]["file"], "ABCD1234");
+
+        client
+            .put_config(&DeviceConfig {
+                ssid: "home".to_string(),
+                password: "secret12".to_string(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            client.get_config().await.unwrap().map(|config| config.ssid),
+            Some("home".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resume() {
+        let root = TempDir::new("mock-resume");
+        let address = serve(MockDevice::new(Card::new(&*root), None).router()).await;
+        let client = DeviceClient::new(&address);
+        let http = reqwest::Client::new();
+        let card = Card::new(&*root);
+
+        let data = device_wav(108, "Artist");
+        let files = ["FILE1".to_string(), "FILE2".to_string()];
+        for file in &files {
+            client.create_file(file).await.unwrap();
+            client.upload_chunk(file, 0, data.clone()).await.unwrap();
+        }
+        client.associate("1a2b3c4d", &files).await.unwrap();
+        card.write_resume(
+            "1a2b3c4d",
+            &ResumePoint {
+                mode: ResumeMode::Resume,
+                index: 1,
+                sample: 2 * SAMPLE_RATE,
+            },
+        )
+        .unwrap();
+
+        let post = |path: &'static str, body: serde_json::Value| {
+            let request = http.post(format!("http://{}{}", address, path)).json(&body);
+            async move { request.send().await.unwrap().error_for_status().unwrap() }
+        };
+        let scan = || post("/mock/fob", serde_json::json!({ "fob": "1a2b3c4d" }));
+
+        scan().await;
+        let status = client.playback_status().await.unwrap();
+        assert_eq!(status.index_in_playlist, 1);
+        assert_eq!(status.position_seconds, 2);
+
+        // Pausing saves the position
+        post("/api/playback/pause", serde_json::json!(null)).await;
+        let point = card.read_resume("1A2B3C4D");
+        assert_eq!(point.index, 1);
+        assert!(point.sample >= 2 * SAMPLE_RATE);
+
+        let resume: serde_json::Value = http
+            .get(format!("http://{}/api/resume?fob=1A2B3C4D", address))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(resume["mode"], "resume");
+        assert_eq!(resume["index_in_playlist"], 1);
+        assert_eq!(resume["position_seconds"], 2);
+
+        // Fobs set to restart always start at the first track
+        http.put(format!("http://{}/api/resume", address))
+            .json(&serde_json::json!({ "fob": "1a2b3c4d", "mode": "restart" }))
+            .send()
+            .await
+            .unwrap()
+            .error_for_status()
+            .unwrap();
+        scan().await;
+        assert_eq!(client.playback_status().await.unwrap().index_in_playlist, 0);
+        assert_eq!(card.read_resume("1A2B3C4D").mode, ResumeMode::Restart);
+
+        // New files reset the position but keep the mode
+        card.write_resume(
+            "1a2b3c4d",
+            &ResumePoint {
+                mode: ResumeMode::Resume,
+                index: 1,
+                sample: 0,
+            },
+        )
+        .unwrap();
+        client.associate("1a2b3c4d", &files[..1]).await.unwrap();
+        assert_eq!(card.read_resume("1A2B3C4D"), ResumePoint::default());
+    }
+
+    #[tokio::test]
+    async fn test_learn_unknown_fob() {
+        let root = TempDir::new("mock-learn");
+        let address = serve(MockDevice::new(Card::new(&*root), None).router()).await;
+        let client = DeviceClient::new(&address);
+        let http = reqwest::Client::new();
+        let card = Card::new(&*root);
+
+        let data = device_wav(108, "Artist");
+        let files = ["FILE1".to_string(), "FILE2".to_string()];
+        for file in &files {
+            client.create_file(file).await.unwrap();
+            client.upload_chunk(file, 0, data.clone()).await.unwrap();
+        }
+        let post = |path: &'static str, body: serde_json::Value| {
+            let request = http.post(format!("http://{}{}", address, path)).json(&body);
+            async move { request.send().await.unwrap().error_for_status().unwrap() }
+        };
+        let scan = || post("/mock/fob", serde_json::json!({ "fob": "1a2b3c4d" }));
+
+        // Without learn mode, unknown fobs only play a sound
+        scan().await;
+        assert_eq!(client.playback_status().await.unwrap().learning_fob, None);
+
+        client
+            .put_config(&DeviceConfig {
+                ssid: "home".to_string(),
+                password: "secret12".to_string(),
+                unknown_fob: Some(UnknownFobAction::Learn),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        scan().await;
+        let status = client.playback_status().await.unwrap();
+        assert_eq!(status.learning_fob.as_deref(), Some("1A2B3C4D"));
+
+        // Nothing playing, the press only complains
+        post("/mock/play_pause", serde_json::json!(null)).await;
+        assert!(card.read_playlist("1A2B3C4D").is_err());
+
+        post(
+            "/api/playback/play",
+            serde_json::json!({ "playlist": files }),
+        )
+        .await;
+        post("/mock/play_pause", serde_json::json!(null)).await;
+        assert_eq!(card.read_playlist("1A2B3C4D").unwrap(), files);
+        let status = client.playback_status().await.unwrap();
+        assert_eq!(status.learning_fob, None);
+        assert_eq!(status.state, "Playing");
+    }
+
+    #[tokio::test]
+    async fn test_tag_presence() {
+        let root = TempDir::new("mock-presence");
+        let address = serve(MockDevice::new(Card::new(&*root), None).router()).await;
+        let client = DeviceClient::new(&address);
+        let http = reqwest::Client::new();
+
+        let data = device_wav(108, "Artist");
+        let files = ["FILE1".to_string(), "FILE2".to_string()];
+        for file in &files {
+            client.create_file(file).await.unwrap();
+            client.upload_chunk(file, 0, data.clone()).await.unwrap();
+        }
+        client.associate("1a2b3c4d", &files).await.unwrap();
+        client.associate("5e6f7a8b", &files[..1]).await.unwrap();
+        let url = format!("http://{}/mock/fob", address);
+        let scan = |fob: &'static str| {
+            let request = http.post(&url).json(&serde_json::json!({ "fob": fob }));
+            async move { request.send().await.unwrap().error_for_status().unwrap() }
+        };
+        let remove = || {
+            let request = http.delete(&url);
+            async move { request.send().await.unwrap().error_for_status().unwrap() }
+        };
+        let state = || async { client.playback_status().await.unwrap().state };
+
+        // Without tag presence mode, removing the fob changes nothing
+        scan("1a2b3c4d").await;
+        remove().await;
+        assert_eq!(state().await, "Playing");
+
+        client
+            .put_config(&DeviceConfig {
+                ssid: "home".to_string(),
+                password: "secret12".to_string(),
+                tag_presence: Some(TagPresenceConfig {
+                    stop_after_seconds: 1,
+                }),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        remove().await;
+        assert_eq!(state().await, "Paused");
+        scan("1a2b3c4d").await;
+        assert_eq!(state().await, "Playing");
+
+        // Another fob switches playlists
+        remove().await;
+        scan("5e6f7a8b").await;
+        let status = client.playback_status().await.unwrap();
+        assert_eq!(status.state, "Playing");
+        assert_eq!(status.playlist_name.as_deref(), Some("5E6F7A8B"));
+
+        // Playback stops when the fob stays away
+        remove().await;
+        assert_eq!(state().await, "Paused");
+        tokio::time::sleep(Duration::from_millis(1200)).await;
+        assert_eq!(state().await, "Stopped");
+    }
+
+    #[tokio::test]
+    async fn test_rescan() {
+        let root = TempDir::new("mock-rescan");
+        let address = serve(MockDevice::new(Card::new(&*root), None).router()).await;
+        let client = DeviceClient::new(&address);
+        let http = reqwest::Client::new();
+
+        let data = device_wav(108, "Artist");
+        let files = ["FILE1".to_string(), "FILE2".to_string()];
+        for file in &files {
+            client.create_file(file).await.unwrap();
+            client.upload_chunk(file, 0, data.clone()).await.unwrap();
+        }
+        client.associate("1a2b3c4d", &files).await.unwrap();
+        let url = format!("http://{}/mock/fob", address);
+        let scan = || {
+            let request = http
+                .post(&url)
+                .json(&serde_json::json!({ "fob": "1a2b3c4d" }));
+            async move { request.send().await.unwrap().error_for_status().unwrap() }
+        };
+        let put_rescan = |action, cooldown_seconds| {
+            let config = DeviceConfig {
+                ssid: "home".to_string(),
+                password: "secret12".to_string(),
+                rescan: Some(RescanConfig {
+                    action,
+                    cooldown_seconds,
+                }),
+                ..Default::default()
+            };
+            let client = &client;
+            async move { client.put_config(&config).await.unwrap() }
+        };
+
+        // Scanning the fob playing again is ignored by default
+        scan().await;
+        scan().await;
+        let status = client.playback_status().await.unwrap();
+        assert_eq!(status.state, "Playing");
+        assert_eq!(status.index_in_playlist, 0);
+
+        put_rescan(RescanAction::Next, 0).await;
+        scan().await;
+        assert_eq!(client.playback_status().await.unwrap().index_in_playlist, 1);
+
+        put_rescan(RescanAction::Restart, 0).await;
+        scan().await;
+        let status = client.playback_status().await.unwrap();
+        assert_eq!(status.state, "Playing");
+        assert_eq!(status.index_in_playlist, 0);
+
+        // Pausing toggles, resuming keeps the track and position
+        client
+            .playback_command(PlaybackCommand::Next)
+            .await
+            .unwrap();
+        put_rescan(RescanAction::Pause, 0).await;
+        scan().await;
+        assert_eq!(client.playback_status().await.unwrap().state, "Paused");
+        scan().await;
+        let status = client.playback_status().await.unwrap();
+        assert_eq!(status.state, "Playing");
+        assert_eq!(status.index_in_playlist, 1);
+
+        // Within the cooldown the second scan does nothing, also while paused
+        put_rescan(RescanAction::Pause, 60).await;
+        scan().await;
+        assert_eq!(client.playback_status().await.unwrap().state, "Playing");
+        client
+            .playback_command(PlaybackCommand::Pause)
+            .await
+            .unwrap();
+        scan().await;
+        assert_eq!(client.playback_status().await.unwrap().state, "Paused");
+    }
+
+    #[tokio::test]
+    async fn test_system_sounds() {
+        let root = TempDir::new("mock-sounds");
+        let address = serve(MockDevice::new(Card::new(&*root), None).router()).await;
+        let http = reqwest::Client::new();
+        let url = |path: &str| format!("http://{}/api/system_sounds{}", address, path);
+        let list = || async {
+            http.get(url(""))
+                .send()
+                .await
+                .unwrap()
+                .json::<serde_json::Value>()
+                .await
+                .unwrap()
+        };
+
+        let sounds = list().await;
+        assert_eq!(sounds.as_array().unwrap().len(), SYSTEM_SOUNDS.len());
+        assert_eq!(
+            sounds[0],
+            serde_json::json!({ "sound": "boot", "custom": false })
+        );
+
+        let response = http
+            .put(url("/unknown_fob"))
+            .body(device_wav(108, "Artist"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert!(root.join("SYSTEM").join("UNKNOWN.WAV").is_file());
+        assert_eq!(list().await[2]["custom"], true);
+
+        let response = http.post(url("/unknown_fob")).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        let response = http.put(url("/beep")).body("").send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = http.delete(url("/unknown_fob")).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(list().await[2]["custom"], false);
+        let response = http.delete(url("/unknown_fob")).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
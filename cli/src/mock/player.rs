@@ -0,0 +1,746 @@
+//! Simulated playback of the mock device
+//!
+//! Nothing is decoded, the position advances with the clock and files end after the duration
+//! the firmware reports for them. Commands behave like `controllers::playback` in the firmware.
+use audio_file_utils::order::{PlayOrder, Skip};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+use crate::client::{FileEntry, FileMetadata};
+use crate::layout::{DEFAULT_VOLUME, MAX_VOLUME, PlaylistMode, Repeat};
+
+/// Fade-out after the sleep timer elapsed, playback stops at its end
+const SLEEP_FADE: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Copy, Debug, Serialize, PartialEq)]
+pub enum State {
+    Playing,
+    Paused,
+    Stopped,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Seek {
+    To(u32),
+    By(i32),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct PlaybackMode {
+    pub repeat: Repeat,
+    pub shuffle: bool,
+}
+
+impl PlaybackMode {
+    fn with_playlist_mode(self, playlist_mode: &PlaylistMode) -> Self {
+        Self {
+            repeat: playlist_mode.repeat.unwrap_or(self.repeat),
+            shuffle: playlist_mode.shuffle.unwrap_or(self.shuffle),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct StatusResponse {
+    pub position_seconds: u32,
+    pub state: State,
+    pub index_in_playlist: usize,
+    pub playlist_name: Option<String>,
+    pub repeat: Repeat,
+    pub shuffle: bool,
+    pub sleep_timer_seconds: Option<u32>,
+    pub volume: u8,
+    pub max_volume: u8,
+    /// Set by the device, the player does not know about fobs
+    pub learning_fob: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct AudioFileWithMetadata {
+    pub file: String,
+    pub metadata: FileMetadata,
+}
+
+#[derive(Clone, Serialize)]
+pub struct PlaylistWithMetadata {
+    pub playlist_name: String,
+    pub files: Vec<AudioFileWithMetadata>,
+}
+
+pub struct Player {
+    state: State,
+    playlist: Option<PlaylistWithMetadata>,
+    /// Playback length of each file, zero for files that cannot be read
+    durations: Vec<Duration>,
+    order: PlayOrder,
+    /// Position in the current file at `updated_at`
+    position: Duration,
+    updated_at: Instant,
+    volume: u8,
+    /// Highest volume the commands can set, from the device config
+    max_volume: u8,
+    /// Mode for playlists without their own directives
+    default_mode: PlaybackMode,
+    /// Mode of the playlist playing
+    mode: PlaybackMode,
+    /// When the sleep timer elapses and the fade-out starts
+    sleep_deadline: Option<Instant>,
+}
+
+impl Player {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            state: State::Stopped,
+            playlist: None,
+            durations: Vec::new(),
+            order: PlayOrder::new(0, 0, false, 0),
+            position: Duration::ZERO,
+            updated_at: now,
+            volume: DEFAULT_VOLUME,
+            max_volume: MAX_VOLUME,
+            default_mode: PlaybackMode::default(),
+            mode: PlaybackMode::default(),
+            sleep_deadline: None,
+        }
+    }
+
+    /// Start playing `files` with the directives of their playlist. `exists` tells which files
+    /// can be read, the firmware skips the others.
+    pub fn play(
+        &mut self,
+        name: &str,
+        files: Vec<FileEntry>,
+        exists: &[bool],
+        playlist_mode: &PlaylistMode,
+        now: Instant,
+    ) {
+        self.durations = durations(&files, exists);
+        self.playlist = Some(PlaylistWithMetadata {
+            playlist_name: name.to_string(),
+            files: with_metadata(files),
+        });
+        self.state = State::Playing;
+        self.mode = self.default_mode.with_playlist_mode(playlist_mode);
+        self.reorder(0);
+        self.position = Duration::ZERO;
+        self.updated_at = now;
+        self.advance(now);
+    }
+
+    /// Continue at `position` into the file at `index`, like the firmware does when a fob
+    /// resumes
+    pub fn start_at(&mut self, index: usize, position: Duration, now: Instant) {
+        self.advance(now);
+        if self.state != State::Stopped {
+            self.reorder(index.min(self.durations.len().saturating_sub(1)));
+            self.position = position;
+            self.advance(now);
+        }
+    }
+
+    /// Adds files to the playlist playing, right after the current file with `next` and after
+    /// all others otherwise, like the firmware's queue
+    pub fn enqueue(&mut self, files: Vec<FileEntry>, exists: &[bool], next: bool, now: Instant) {
+        self.advance(now);
+        let Some(playlist) = self.playlist.as_mut() else {
+            return;
+        };
+        let len = playlist.files.len();
+        let at = match self.order.current() {
+            Some(current) if next => current + 1,
+            _ => len,
+        };
+        self.order.insert(at, files.len(), next);
+        self.durations.splice(at..at, durations(&files, exists));
+        playlist.files.splice(at..at, with_metadata(files));
+    }
+
+    /// Removes the file at `index` of the playlist playing, playback continues with the next
+    /// file if it was playing. Returns whether there was such a file.
+    pub fn remove(&mut self, index: usize, now: Instant) -> bool {
+        self.advance(now);
+        let Some(playlist) = self.playlist.as_mut() else {
+            return false;
+        };
+        if index >= playlist.files.len() {
+            return false;
+        }
+        playlist.files.remove(index);
+        self.durations.remove(index);
+        if self.order.remove(index) {
+            self.position = Duration::ZERO;
+        }
+        self.advance(now);
+        true
+    }
+
+    /// Moves the file at index `from` of the playlist playing to index `to`. Returns whether
+    /// both indices are in the playlist.
+    pub fn move_file(&mut self, from: usize, to: usize, now: Instant) -> bool {
+        self.advance(now);
+        let Some(playlist) = self.playlist.as_mut() else {
+            return false;
+        };
+        let len = playlist.files.len();
+        if from >= len || to >= len {
+            return false;
+        }
+        let file = playlist.files.remove(from);
+        playlist.files.insert(to, file);
+        let duration = self.durations.remove(from);
+        self.durations.insert(to, duration);
+        self.order.move_file(from, to);
+        true
+    }
+
+    /// Removes all files but the current one, playback ends after it
+    pub fn clear_queue(&mut self, now: Instant) {
+        self.advance(now);
+        let index = self.index();
+        let Some(playlist) = self.playlist.as_mut() else {
+            return;
+        };
+        playlist.files = vec![playlist.files.swap_remove(index)];
+        self.durations = vec![self.durations[index]];
+        self.order.keep_current();
+    }
+
+    pub fn stop(&mut self) {
+        self.state = State::Stopped;
+        self.playlist = None;
+        self.durations.clear();
+        self.order = PlayOrder::new(0, 0, false, 0);
+        self.position = Duration::ZERO;
+        self.mode = self.default_mode;
+    }
+
+    pub fn pause(&mut self, now: Instant) {
+        self.advance(now);
+        self.state = match self.state {
+            State::Playing => State::Paused,
+            State::Paused => State::Playing,
+            State::Stopped => State::Stopped,
+        };
+    }
+
+    /// Skips to the next file, wrapping around only when repeating all
+    pub fn next(&mut self, now: Instant) {
+        self.advance(now);
+        if self.state != State::Stopped {
+            self.order.skip(Skip::Next, self.mode.repeat.into());
+            self.position = Duration::ZERO;
+        }
+    }
+
+    /// Skips to the previous file, wrapping around only when repeating all
+    pub fn previous(&mut self, now: Instant) {
+        self.advance(now);
+        if self.state != State::Stopped {
+            self.order.skip(Skip::Previous, self.mode.repeat.into());
+            self.position = Duration::ZERO;
+        }
+    }
+
+    /// Sets the global repeat mode, which also applies to the playlist playing
+    pub fn set_repeat(&mut self, repeat: Repeat, now: Instant) {
+        self.advance(now);
+        self.default_mode.repeat = repeat;
+        self.mode.repeat = repeat;
+    }
+
+    /// Sets the global shuffle mode, which also applies to the playlist playing
+    pub fn set_shuffle(&mut self, shuffle: bool, now: Instant) {
+        self.advance(now);
+        self.default_mode.shuffle = shuffle;
+        self.mode.shuffle = shuffle;
+        self.order.set_shuffle(shuffle);
+    }
+
+    /// Stops playback `minutes` from `now` after fading out, 0 cancels the timer
+    pub fn set_sleep_timer(&mut self, minutes: u16, now: Instant) {
+        self.advance(now);
+        self.sleep_deadline = (minutes > 0).then(|| now + Duration::from_secs(minutes as u64 * 60));
+    }
+
+    pub fn mode(&mut self, now: Instant) -> PlaybackMode {
+        self.advance(now);
+        self.mode
+    }
+
+    /// Moves to `target` seconds into the current file, or by `offset` seconds. Like the
+    /// firmware, seeking past the end continues with the next file.
+    pub fn seek(&mut self, seek: Seek, now: Instant) {
+        self.advance(now);
+        if self.state == State::Stopped {
+            return;
+        }
+        self.position = match seek {
+            Seek::To(seconds) => Duration::from_secs(seconds as u64),
+            Seek::By(seconds) if seconds < 0 => self
+                .position
+                .saturating_sub(Duration::from_secs(seconds.unsigned_abs() as u64)),
+            Seek::By(seconds) => self.position + Duration::from_secs(seconds as u64),
+        };
+        self.advance(now);
+    }
+
+    pub fn volume_up(&mut self) {
+        self.volume = self.max_volume.min(self.volume + 1);
+    }
+
+    pub fn volume_down(&mut self) {
+        self.volume = self.volume.saturating_sub(1).clamp(1, self.max_volume);
+    }
+
+    /// Step 0 is muted, like the firmware the volume never goes that low
+    pub fn set_volume(&mut self, volume: u8) {
+        self.volume = volume.clamp(1, self.max_volume);
+    }
+
+    /// Lowers the volume right away if it is above the new limit
+    pub fn set_max_volume(&mut self, max_volume: u8) {
+        self.max_volume = max_volume.clamp(1, MAX_VOLUME);
+        self.volume = self.volume.min(self.max_volume);
+    }
+
+    pub fn volume(&self) -> u8 {
+        self.volume
+    }
+
+    pub fn state(&mut self, now: Instant) -> State {
+        self.advance(now);
+        self.state
+    }
+
+    /// Index and position in the current file, `None` when stopped
+    pub fn position(&mut self, now: Instant) -> Option<(usize, Duration)> {
+        self.advance(now);
+        (self.state != State::Stopped).then_some((self.index(), self.position))
+    }
+
+    pub fn status(&mut self, now: Instant) -> StatusResponse {
+        self.advance(now);
+        StatusResponse {
+            position_seconds: self.position.as_secs() as u32,
+            state: self.state,
+            index_in_playlist: self.index(),
+            playlist_name: self
+                .playlist
+                .as_ref()
+                .map(|playlist| playlist.playlist_name.clone()),
+            repeat: self.mode.repeat,
+            shuffle: self.mode.shuffle,
+            sleep_timer_seconds: self
+                .sleep_deadline
+                .map(|deadline| deadline.saturating_duration_since(now).as_secs() as u32),
+            volume: self.volume,
+            max_volume: self.max_volume,
+            learning_fob: None,
+        }
+    }
+
+    pub fn current_playlist(&mut self, now: Instant) -> Option<PlaylistWithMetadata> {
+        self.advance(now);
+        self.playlist.clone()
+    }
+
+    /// Moves the position forward to `now`, stopping when the sleep timer has faded out
+    fn advance(&mut self, now: Instant) {
+        if let Some(stop_at) = self.sleep_deadline.map(|deadline| deadline + SLEEP_FADE)
+            && now >= stop_at
+        {
+            self.play_until(stop_at);
+            self.sleep_deadline = None;
+            self.stop();
+        }
+        self.play_until(now);
+    }
+
+    /// Moves the position forward to `now`, continuing with the next files and stopping at the
+    /// end of the playlist
+    fn play_until(&mut self, now: Instant) {
+        if self.state == State::Playing {
+            self.position += now.saturating_duration_since(self.updated_at);
+        }
+        self.updated_at = now;
+
+        if self.state == State::Stopped {
+            return;
+        }
+        // Unreadable files are skipped even when repeated, a playlist without a readable file
+        // ends
+        let mut unreadable = 0;
+        while let Some(duration) = self.order.current().map(|i| self.durations[i]) {
+            if self.position < duration {
+                return;
+            }
+            self.position -= duration;
+            if duration.is_zero() {
+                unreadable += 1;
+                if unreadable >= self.durations.len() {
+                    break;
+                }
+                self.order.next_after_error(self.mode.repeat.into());
+            } else {
+                unreadable = 0;
+                self.order.next_after_end(self.mode.repeat.into());
+            }
+        }
+        self.stop();
+    }
+
+    /// Index of the current file in the playlist
+    fn index(&self) -> usize {
+        self.order.current().unwrap_or_default()
+    }
+
+    /// Rebuilds the playing order for the current mode, starting at file `first`. Like the
+    /// firmware, shuffling plays `first` and then the others in random order.
+    fn reorder(&mut self, first: usize) {
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.subsec_nanos())
+            .unwrap_or_default();
+        self.order = PlayOrder::new(self.durations.len(), first, self.mode.shuffle, seed);
+    }
+}
+
+/// Playback length of each file, zero for files that cannot be read
+fn durations(files: &[FileEntry], exists: &[bool]) -> Vec<Duration> {
+    files
+        .iter()
+        .zip(exists)
+        .map(|(file, &exists)| {
+            Duration::from_secs(if exists {
+                file.metadata.duration as u64
+            } else {
+                0
+            })
+        })
+        .collect()
+}
+
+fn with_metadata(files: Vec<FileEntry>) -> Vec<AudioFileWithMetadata> {
+    files
+        .into_iter()
+        .map(|file| AudioFileWithMetadata {
+            file: file.name,
+            metadata: file.metadata,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files(durations: &[u32]) -> Vec<FileEntry> {
+        durations
+            .iter()
+            .enumerate()
+            .map(|(i, &duration)| FileEntry {
+                name: format!("FILE{}", i),
+                metadata: FileMetadata {
+                    artist: "Artist".to_string(),
+                    title: format!("Song {}", i),
+                    album: "Album".to_string(),
+                    duration,
+                },
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_position_advances_through_playlist() {
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        let mut player = Player::new(start);
+        player.play(
+            "FOB1",
+            files(&[10, 60, 5]),
+            &[true, false, true],
+            &PlaylistMode::default(),
+            start,
+        );
+
+        let status = player.status(at(4));
+        assert_eq!(status.state, State::Playing);
+        assert_eq!((status.index_in_playlist, status.position_seconds), (0, 4));
+        assert_eq!(status.playlist_name.as_deref(), Some("FOB1"));
+
+        // The missing second file is skipped
+        let status = player.status(at(12));
+        assert_eq!((status.index_in_playlist, status.position_seconds), (2, 2));
+
+        let status = player.status(at(15));
+        assert_eq!(status.state, State::Stopped);
+        assert!(player.current_playlist(at(15)).is_none());
+    }
+
+    #[test]
+    fn test_pause_and_skip() {
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        let mut player = Player::new(start);
+        player.play(
+            "WEB_API",
+            files(&[10, 10]),
+            &[true, true],
+            &PlaylistMode::default(),
+            start,
+        );
+
+        player.pause(at(3));
+        assert_eq!(player.status(at(100)).state, State::Paused);
+        assert_eq!(player.status(at(100)).position_seconds, 3);
+        player.pause(at(100));
+        assert_eq!(player.status(at(102)).position_seconds, 5);
+
+        // Skipping past the last file restarts it, like the firmware does
+        player.next(at(102));
+        player.next(at(103));
+        let status = player.status(at(105));
+        assert_eq!((status.index_in_playlist, status.position_seconds), (1, 2));
+
+        player.previous(at(105));
+        player.previous(at(105));
+        let status = player.status(at(105));
+        assert_eq!((status.index_in_playlist, status.position_seconds), (0, 0));
+    }
+
+    #[test]
+    fn test_seek() {
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        let mut player = Player::new(start);
+        player.play(
+            "FOB1",
+            files(&[10, 10]),
+            &[true, true],
+            &PlaylistMode::default(),
+            start,
+        );
+        player.seek(Seek::To(7), at(1));
+        assert_eq!(player.position(at(1)), Some((0, Duration::from_secs(7))));
+        player.seek(Seek::By(-20), at(1));
+        assert_eq!(player.position(at(1)), Some((0, Duration::ZERO)));
+        player.seek(Seek::By(14), at(1));
+        assert_eq!(player.position(at(1)), Some((1, Duration::from_secs(4))));
+    }
+
+    #[test]
+    fn test_start_at() {
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        let mut player = Player::new(start);
+        player.play(
+            "FOB1",
+            files(&[10, 10]),
+            &[true, true],
+            &PlaylistMode::default(),
+            start,
+        );
+        player.start_at(1, Duration::from_secs(8), start);
+        assert_eq!(player.position(at(1)), Some((1, Duration::from_secs(9))));
+        assert_eq!(player.position(at(2)), None);
+        assert_eq!(player.state(at(2)), State::Stopped);
+    }
+
+    #[test]
+    fn test_repeat() {
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        let mut player = Player::new(start);
+        let repeat_one = PlaylistMode {
+            repeat: Some(Repeat::One),
+            ..Default::default()
+        };
+        player.play("FOB1", files(&[10, 10]), &[true, true], &repeat_one, start);
+        assert_eq!(player.position(at(25)), Some((0, Duration::from_secs(5))));
+        // Skipping still moves on, the global mode comes back after the playlist
+        player.next(at(25));
+        assert_eq!(player.position(at(25)), Some((1, Duration::ZERO)));
+        player.stop();
+        assert_eq!(player.mode(at(25)), PlaybackMode::default());
+
+        player.set_repeat(Repeat::All, at(25));
+        player.play(
+            "FOB1",
+            files(&[10, 10]),
+            &[true, true],
+            &PlaylistMode::default(),
+            at(25),
+        );
+        assert_eq!(player.position(at(50)), Some((0, Duration::from_secs(5))));
+        player.previous(at(50));
+        assert_eq!(player.status(at(50)).index_in_playlist, 1);
+        assert_eq!(player.status(at(50)).repeat, Repeat::All);
+
+        // Repeating a playlist without readable files ends it
+        player.play(
+            "FOB1",
+            files(&[10]),
+            &[false],
+            &PlaylistMode::default(),
+            at(50),
+        );
+        assert_eq!(player.state(at(51)), State::Stopped);
+    }
+
+    #[test]
+    fn test_shuffle() {
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        let mut player = Player::new(start);
+        player.set_shuffle(true, start);
+        player.play(
+            "FOB1",
+            files(&[10; 5]),
+            &[true; 5],
+            &PlaylistMode::default(),
+            start,
+        );
+        player.start_at(3, Duration::ZERO, start);
+
+        // The resumed file comes first, then every other file once
+        let mut played: Vec<usize> = (0..5)
+            .map(|i| player.position(at(i * 10)).unwrap().0)
+            .collect();
+        assert_eq!(played[0], 3);
+        played.sort();
+        assert_eq!(played, [0, 1, 2, 3, 4]);
+        assert_eq!(player.state(at(50)), State::Stopped);
+
+        // Switching shuffle off continues in playlist order after the current file
+        player.play(
+            "FOB1",
+            files(&[10; 5]),
+            &[true; 5],
+            &PlaylistMode::default(),
+            at(50),
+        );
+        let current = player.position(at(50)).unwrap().0;
+        player.set_shuffle(false, at(50));
+        assert_eq!(player.position(at(50)), Some((current, Duration::ZERO)));
+        if current < 4 {
+            assert_eq!(player.position(at(60)).unwrap().0, current + 1);
+        }
+    }
+
+    #[test]
+    fn test_queue() {
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let names = |player: &mut Player, now| -> Vec<String> {
+            player
+                .current_playlist(now)
+                .map(|playlist| playlist.files.into_iter().map(|f| f.file).collect())
+                .unwrap_or_default()
+        };
+
+        let mut player = Player::new(start);
+        player.enqueue(files(&[10]), &[true], false, start);
+        assert_eq!(player.state(start), State::Stopped);
+
+        player.play(
+            "WEB_API",
+            files(&[10, 10]),
+            &[true, true],
+            &PlaylistMode::default(),
+            start,
+        );
+        let mut added = files(&[10]);
+        added[0].name = "NEXT".to_string();
+        player.enqueue(added, &[true], true, at(5));
+        let mut added = files(&[10]);
+        added[0].name = "LAST".to_string();
+        player.enqueue(added, &[true], false, at(5));
+        assert_eq!(
+            names(&mut player, at(5)),
+            ["FILE0", "NEXT", "FILE1", "LAST"]
+        );
+        assert_eq!(player.position(at(15)), Some((1, Duration::from_secs(5))));
+
+        // Moving keeps the current file playing
+        assert!(player.move_file(3, 0, at(15)));
+        assert_eq!(
+            names(&mut player, at(15)),
+            ["LAST", "FILE0", "NEXT", "FILE1"]
+        );
+        assert_eq!(player.position(at(15)), Some((2, Duration::from_secs(5))));
+        assert!(!player.move_file(4, 0, at(15)));
+
+        // Removing the current file moves on to the next one
+        assert!(player.remove(2, at(15)));
+        assert_eq!(player.position(at(15)), Some((2, Duration::ZERO)));
+        assert!(player.remove(0, at(15)));
+        assert_eq!(names(&mut player, at(15)), ["FILE0", "FILE1"]);
+        assert_eq!(player.position(at(16)), Some((1, Duration::from_secs(1))));
+
+        player.enqueue(files(&[10, 10]), &[true, true], false, at(16));
+        player.clear_queue(at(16));
+        assert_eq!(names(&mut player, at(16)), ["FILE1"]);
+        assert_eq!(player.position(at(17)), Some((0, Duration::from_secs(2))));
+        assert_eq!(player.state(at(30)), State::Stopped);
+    }
+
+    #[test]
+    fn test_sleep_timer() {
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        let mut player = Player::new(start);
+        let repeat_all = PlaylistMode {
+            repeat: Some(Repeat::All),
+            ..Default::default()
+        };
+        player.play("FOB1", files(&[100]), &[true], &repeat_all, start);
+        player.set_sleep_timer(1, at(10));
+        assert_eq!(player.status(at(20)).sleep_timer_seconds, Some(50));
+        // Still fading out
+        assert_eq!(player.status(at(80)).sleep_timer_seconds, Some(0));
+        assert_eq!(player.state(at(80)), State::Playing);
+
+        let status = player.status(at(100));
+        assert_eq!(status.state, State::Stopped);
+        assert_eq!(status.sleep_timer_seconds, None);
+
+        player.play("FOB1", files(&[100]), &[true], &repeat_all, at(100));
+        player.set_sleep_timer(1, at(100));
+        player.set_sleep_timer(0, at(110));
+        assert_eq!(player.state(at(300)), State::Playing);
+    }
+
+    #[test]
+    fn test_volume_limits() {
+        let mut player = Player::new(Instant::now());
+        for _ in 0..MAX_VOLUME {
+            player.volume_up();
+        }
+        assert_eq!(player.volume(), MAX_VOLUME);
+        for _ in 0..MAX_VOLUME {
+            player.volume_down();
+        }
+        assert_eq!(player.volume(), 1);
+
+        player.set_volume(MAX_VOLUME);
+        player.set_max_volume(20);
+        assert_eq!(player.volume(), 20);
+        player.volume_up();
+        assert_eq!(player.volume(), 20);
+        player.set_max_volume(0);
+        assert_eq!(player.volume(), 1);
+
+        player.set_volume(0);
+        assert_eq!(player.volume(), 1);
+        player.volume_down();
+        assert_eq!(player.volume(), 1);
+    }
+}
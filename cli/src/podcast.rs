@@ -0,0 +1,229 @@
+//! Podcast subscriptions: RSS feed parsing and the local subscription store
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+
+const ITUNES_NS: &str = "http://www.itunes.com/dtds/podcast-1.0.dtd";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Episode {
+    /// `guid` of the item, or the enclosure URL if the feed has none
+    pub guid: String,
+    pub title: String,
+    pub url: String,
+    pub published: Option<DateTime<FixedOffset>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Feed {
+    pub title: String,
+    pub author: Option<String>,
+    /// Episodes with an audio enclosure, newest first
+    pub episodes: Vec<Episode>,
+}
+
+/// Parses an RSS 2.0 feed
+pub fn parse_feed(xml: &str) -> Result<Feed, String> {
+    let document = roxmltree::Document::parse(xml).map_err(|e| format!("Invalid feed: {}", e))?;
+    let channel = document
+        .root_element()
+        .children()
+        .find(|node| node.has_tag_name("channel"))
+        .ok_or("Invalid feed: missing channel")?;
+
+    let child_text = |node: roxmltree::Node, name: &str| {
+        node.children()
+            .find(|child| child.has_tag_name(name))
+            .and_then(|child| child.text())
+            .map(|text| text.trim().to_string())
+            .filter(|text| !text.is_empty())
+    };
+
+    let author = channel
+        .children()
+        .find(|child| child.has_tag_name((ITUNES_NS, "author")))
+        .and_then(|child| child.text())
+        .map(|text| text.trim().to_string());
+
+    let mut episodes: Vec<Episode> = channel
+        .children()
+        .filter(|node| node.has_tag_name("item"))
+        .filter_map(|item| {
+            let enclosure = item
+                .children()
+                .find(|child| child.has_tag_name("enclosure"))?;
+            let is_audio = enclosure
+                .attribute("type")
+                .is_none_or(|kind| kind.starts_with("audio/"));
+            let url = enclosure.attribute("url").filter(|_| is_audio)?.to_string();
+
+            Some(Episode {
+                guid: child_text(item, "guid").unwrap_or_else(|| url.clone()),
+                title: child_text(item, "title").unwrap_or_else(|| "Unknown".to_string()),
+                published: child_text(item, "pubDate")
+                    .and_then(|date| DateTime::parse_from_rfc2822(&date).ok()),
+                url,
+            })
+        })
+        .collect();
+
+    // Feeds are usually ordered newest first already, but that is not guaranteed
+    episodes.sort_by_key(|episode| std::cmp::Reverse(episode.published));
+
+    Ok(Feed {
+        title: child_text(channel, "title").unwrap_or_else(|| "Podcast".to_string()),
+        author,
+        episodes,
+    })
+}
+
+/// Truncates to at most `max` bytes at a character boundary, for INFO chunk values
+pub fn truncate(value: &str, max: usize) -> &str {
+    let mut end = value.len().min(max);
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+    &value[..end]
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SyncedEpisode {
+    pub guid: String,
+    pub title: String,
+    /// File name on the device
+    pub file: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Subscription {
+    pub feed_url: String,
+    pub fob: String,
+    /// Number of episodes to keep on the device
+    pub keep: usize,
+    /// Episodes on the device after the last sync, newest first
+    #[serde(default)]
+    pub episodes: Vec<SyncedEpisode>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Subscriptions {
+    pub subscriptions: Vec<Subscription>,
+}
+
+impl Subscriptions {
+    /// `$XDG_CONFIG_HOME/pecli/podcasts.json`, falling back to `~/.config`
+    pub fn default_path() -> PathBuf {
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+            .unwrap_or_default();
+        config_dir.join("pecli").join("podcasts.json")
+    }
+
+    /// Loads subscriptions, an empty list if the file does not exist yet
+    pub fn load(path: &Path) -> io::Result<Self> {
+        match std::fs::read(path) {
+            Ok(data) => Ok(serde_json::from_slice(&data)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let partial = path.with_extension("json.partial");
+        std::fs::write(&partial, serde_json::to_vec_pretty(self)?)?;
+        std::fs::rename(partial, path)
+    }
+
+    pub fn find(&self, fob: &str) -> Option<&Subscription> {
+        self.subscriptions
+            .iter()
+            .find(|subscription| subscription.fob.eq_ignore_ascii_case(fob))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Feed with one episode per `(guid, title, date, url)`
+    pub(crate) fn feed_xml(episodes: &[(&str, &str, &str, &str)]) -> String {
+        let items: String = episodes
+            .iter()
+            .map(|(guid, title, date, url)| {
+                format!(
+                    "<item><title>{}</title><guid isPermaLink=\"false\">{}</guid>\
+                     <pubDate>{}</pubDate>\
+                     <enclosure url=\"{}\" length=\"1000\" type=\"audio/mpeg\"/></item>",
+                    title, guid, date, url
+                )
+            })
+            .collect();
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+             <rss version=\"2.0\" xmlns:itunes=\"{}\"><channel>\
+             <title>Bedtime Stories</title><itunes:author>Story Teller</itunes:author>\
+             {}</channel></rss>",
+            ITUNES_NS, items
+        )
+    }
+
+    #[test]
+    fn test_parse_feed_sorts_newest_first() {
+        let xml = feed_xml(&[
+            (
+                "ep1",
+                "Old",
+                "Mon, 06 Jan 2025 08:00:00 +0000",
+                "http://x/1.mp3",
+            ),
+            (
+                "ep3",
+                "New",
+                "Mon, 20 Jan 2025 08:00:00 +0100",
+                "http://x/3.mp3",
+            ),
+            (
+                "ep2",
+                "Middle",
+                "Mon, 13 Jan 2025 08:00:00 GMT",
+                "http://x/2.mp3",
+            ),
+        ]);
+
+        let feed = parse_feed(&xml).unwrap();
+        assert_eq!(feed.title, "Bedtime Stories");
+        assert_eq!(feed.author.as_deref(), Some("Story Teller"));
+        let guids: Vec<_> = feed.episodes.iter().map(|e| e.guid.as_str()).collect();
+        assert_eq!(guids, vec!["ep3", "ep2", "ep1"]);
+        assert_eq!(feed.episodes[0].url, "http://x/3.mp3");
+    }
+
+    #[test]
+    fn test_parse_feed_skips_items_without_audio() {
+        let xml = "<rss><channel><title>T</title>\
+                   <item><title>Text only</title></item>\
+                   <item><title>Video</title><enclosure url=\"http://x/v.mp4\" type=\"video/mp4\"/></item>\
+                   <item><title>No guid</title><enclosure url=\"http://x/a.mp3\"/></item>\
+                   </channel></rss>";
+
+        let feed = parse_feed(xml).unwrap();
+        assert_eq!(feed.author, None);
+        assert_eq!(feed.episodes.len(), 1);
+        assert_eq!(feed.episodes[0].guid, "http://x/a.mp3");
+        assert_eq!(feed.episodes[0].published, None);
+
+        assert!(parse_feed("<html></html>").is_err());
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("short", 31), "short");
+        assert_eq!(truncate("Grüße", 3), "Gr");
+        assert_eq!(truncate("Grüße", 4), "Grü");
+    }
+}
@@ -0,0 +1,348 @@
+//! RIFF/WAVE parsing for inspecting device files
+use std::ops::Range;
+
+/// IMA ADPCM header of a block: initial predictor, step index and a reserved byte
+pub const BLOCK_HEADER_SIZE: usize = 4;
+pub const MAX_STEP_INDEX: u8 = 88;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+    pub id: String,
+    pub offset: usize,
+    pub size: u32,
+    /// Form type of `LIST` chunks
+    pub list_type: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Format {
+    pub format_tag: u16,
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub byte_rate: u32,
+    pub block_align: u16,
+    pub bits_per_sample: u16,
+    /// `wSamplesPerBlock` from the extension of ADPCM formats
+    pub samples_per_block: Option<u16>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Wav {
+    pub riff_size: u32,
+    pub chunks: Vec<Chunk>,
+    pub format: Format,
+    pub info: Vec<(String, String)>,
+    pub data: Range<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockStats {
+    pub blocks: usize,
+    /// Size of a trailing incomplete block, if any
+    pub partial_block: usize,
+    /// Smallest and largest initial predictor
+    pub predictor: (i16, i16),
+    /// Smallest and largest initial step index
+    pub step_index: (u8, u8),
+    pub mean_step_index: f64,
+    pub invalid_step_index: usize,
+    pub nonzero_reserved: usize,
+}
+
+fn u16_at(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn u32_at(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn fourcc(data: &[u8]) -> String {
+    String::from_utf8_lossy(data).into_owned()
+}
+
+impl Wav {
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        if data.len() < 12 || &data[0..4] != b"RIFF" || &data[8..12] != b"WAVE" {
+            return Err("Not a RIFF/WAVE file".to_string());
+        }
+        let riff_size = u32_at(data, 4);
+
+        let mut chunks = Vec::new();
+        let mut format = None;
+        let mut info = Vec::new();
+        let mut data_range = None;
+
+        let mut offset = 12;
+        while offset + 8 <= data.len() {
+            let id = &data[offset..offset + 4];
+            let size = u32_at(data, offset + 4);
+            let body_start = offset + 8;
+            let body_end = body_start + size as usize;
+
+            let list_type = (id == b"LIST" && size >= 4 && body_start + 4 <= data.len())
+                .then(|| fourcc(&data[body_start..body_start + 4]));
+
+            if id == b"data" {
+                // Tolerate truncated files, the data chunk is streamed until EOF anyway
+                data_range = Some(body_start..body_end.min(data.len()));
+            } else if body_end > data.len() {
+                return Err(format!(
+                    "Chunk '{}' at offset {} exceeds file size",
+                    fourcc(id),
+                    offset
+                ));
+            } else if id == b"fmt " {
+                format = Some(Format::parse(&data[body_start..body_end])?);
+            } else if list_type.as_deref() == Some("INFO") {
+                info = parse_info(&data[body_start + 4..body_end]);
+            }
+
+            chunks.push(Chunk {
+                id: fourcc(id),
+                offset,
+                size,
+                list_type,
+            });
+
+            // Chunks are padded to an even size
+            offset = body_end + (size as usize & 1);
+        }
+
+        Ok(Self {
+            riff_size,
+            chunks,
+            format: format.ok_or("Missing 'fmt ' chunk")?,
+            info,
+            data: data_range.ok_or("Missing 'data' chunk")?,
+        })
+    }
+
+    /// Duration in seconds, from the number of samples in the data chunk
+    pub fn duration(&self) -> f64 {
+        let data_size = self.data.len();
+        let samples = match self.format.samples_per_block {
+            Some(samples_per_block) if self.format.block_align > 0 => {
+                let block_align = self.format.block_align as usize;
+                let full_blocks = data_size / block_align;
+                let partial = data_size % block_align;
+                let partial_samples = if partial > BLOCK_HEADER_SIZE {
+                    (partial - BLOCK_HEADER_SIZE) * 2 + 1
+                } else {
+                    0
+                };
+                full_blocks * samples_per_block as usize + partial_samples
+            }
+            _ if self.format.block_align > 0 => data_size / self.format.block_align as usize,
+            _ => 0,
+        };
+
+        samples as f64 / self.format.sample_rate.max(1) as f64
+    }
+
+    /// Statistics over the IMA ADPCM block headers
+    pub fn block_stats(&self, data: &[u8]) -> Option<BlockStats> {
+        if self.format.format_tag != 0x0011 || self.format.block_align as usize <= BLOCK_HEADER_SIZE
+        {
+            return None;
+        }
+
+        let blocks = data[self.data.clone()].chunks(self.format.block_align as usize);
+        let mut stats = BlockStats {
+            blocks: 0,
+            partial_block: 0,
+            predictor: (i16::MAX, i16::MIN),
+            step_index: (u8::MAX, u8::MIN),
+            mean_step_index: 0.0,
+            invalid_step_index: 0,
+            nonzero_reserved: 0,
+        };
+
+        let mut step_index_sum = 0u64;
+        for block in blocks {
+            if block.len() < self.format.block_align as usize {
+                stats.partial_block = block.len();
+                if block.len() < BLOCK_HEADER_SIZE {
+                    continue;
+                }
+            }
+
+            let predictor = i16::from_le_bytes([block[0], block[1]]);
+            let step_index = block[2];
+            stats.blocks += 1;
+            stats.predictor = (
+                stats.predictor.0.min(predictor),
+                stats.predictor.1.max(predictor),
+            );
+            stats.step_index = (
+                stats.step_index.0.min(step_index),
+                stats.step_index.1.max(step_index),
+            );
+            step_index_sum += step_index as u64;
+            if step_index > MAX_STEP_INDEX {
+                stats.invalid_step_index += 1;
+            }
+            if block[3] != 0 {
+                stats.nonzero_reserved += 1;
+            }
+        }
+
+        if stats.blocks > 0 {
+            stats.mean_step_index = step_index_sum as f64 / stats.blocks as f64;
+        }
+        Some(stats)
+    }
+}
+
+impl Format {
+    fn parse(fmt: &[u8]) -> Result<Self, String> {
+        if fmt.len() < 16 {
+            return Err(format!("'fmt ' chunk too short ({} bytes)", fmt.len()));
+        }
+
+        let cb_size = if fmt.len() >= 18 { u16_at(fmt, 16) } else { 0 };
+        let format_tag = u16_at(fmt, 0);
+        let samples_per_block =
+            (format_tag == 0x0011 && cb_size >= 2 && fmt.len() >= 20).then(|| u16_at(fmt, 18));
+
+        Ok(Self {
+            format_tag,
+            channels: u16_at(fmt, 2),
+            sample_rate: u32_at(fmt, 4),
+            byte_rate: u32_at(fmt, 8),
+            block_align: u16_at(fmt, 12),
+            bits_per_sample: u16_at(fmt, 14),
+            samples_per_block,
+        })
+    }
+
+    pub fn format_name(&self) -> &'static str {
+        match self.format_tag {
+            0x0001 => "PCM",
+            0x0003 => "IEEE float",
+            0x0011 => "IMA ADPCM",
+            0xfffe => "Extensible",
+            _ => "Unknown",
+        }
+    }
+}
+
+fn parse_info(mut list: &[u8]) -> Vec<(String, String)> {
+    let mut tags = Vec::new();
+    // The transcoder zero-pads the INFO list to a fixed size
+    while list.len() >= 8 && list[0..4] != [0; 4] {
+        let id = fourcc(&list[0..4]);
+        let size = u32_at(list, 4) as usize;
+        let Some(value) = list.get(8..8 + size) else {
+            break;
+        };
+        let value = String::from_utf8_lossy(value)
+            .trim_end_matches('\0')
+            .to_string();
+        tags.push((id, value));
+
+        // Odd sized sub-chunks are padded by the spec, but not by `write_info_chunk`
+        let mut next = 8 + size;
+        if size & 1 == 1 && list.get(next) == Some(&0) {
+            next += 1;
+        }
+        list = list.get(next..).unwrap_or_default();
+    }
+    tags
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Device file with the transcoder's header layout and `blocks` ADPCM blocks of silence
+    pub(crate) fn device_wav(blocks: usize, artist: &str) -> Vec<u8> {
+        let info_chunk_size = 4 + 40 * 3;
+        let data_size = blocks * 1024;
+
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(
+            &((4 + 28 + 8 + info_chunk_size + 8 + data_size) as u32).to_le_bytes(),
+        );
+        wav.extend_from_slice(b"WAVE");
+        wav.extend_from_slice(b"fmt ");
+        wav.extend_from_slice(&20u32.to_le_bytes());
+        wav.extend_from_slice(&0x0011u16.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&44100u32.to_le_bytes());
+        wav.extend_from_slice(&22125u32.to_le_bytes());
+        wav.extend_from_slice(&1024u16.to_le_bytes());
+        wav.extend_from_slice(&4u16.to_le_bytes());
+        wav.extend_from_slice(&2u16.to_le_bytes());
+        wav.extend_from_slice(&2041u16.to_le_bytes());
+        wav.extend_from_slice(b"LIST");
+        wav.extend_from_slice(&(info_chunk_size as u32).to_le_bytes());
+        wav.extend_from_slice(b"INFO");
+        // Same layout as `write_info_chunk`: NUL terminated, not padded, zeros at the end
+        let mut written = 4;
+        for (id, value) in [(b"IART", artist), (b"INAM", "Song"), (b"IPRD", "Album")] {
+            wav.extend_from_slice(id);
+            wav.extend_from_slice(&(value.len() as u32 + 1).to_le_bytes());
+            wav.extend_from_slice(value.as_bytes());
+            wav.push(0);
+            written += 8 + value.len() + 1;
+        }
+        wav.resize(wav.len() + info_chunk_size - written, 0);
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&(data_size as u32).to_le_bytes());
+        for block in 0..blocks {
+            let mut data = [0u8; 1024];
+            data[0..2].copy_from_slice(&(block as i16 * 100 - 100).to_le_bytes());
+            data[2] = block as u8;
+            wav.extend_from_slice(&data);
+        }
+        wav
+    }
+
+    #[test]
+    fn test_parse_device_wav() {
+        let data = device_wav(3, "Ab");
+        let wav = Wav::parse(&data).unwrap();
+
+        let ids: Vec<_> = wav.chunks.iter().map(|chunk| chunk.id.as_str()).collect();
+        assert_eq!(ids, vec!["fmt ", "LIST", "data"]);
+        assert_eq!(wav.chunks[1].list_type.as_deref(), Some("INFO"));
+        assert_eq!(wav.format.samples_per_block, Some(2041));
+        assert_eq!(wav.format.block_align, 1024);
+        assert_eq!(wav.data.start, crate::layout::WAV_HEADER_SIZE as usize);
+        assert_eq!(
+            wav.info,
+            vec![
+                ("IART".to_string(), "Ab".to_string()),
+                ("INAM".to_string(), "Song".to_string()),
+                ("IPRD".to_string(), "Album".to_string()),
+            ]
+        );
+        assert!((wav.duration() - 3.0 * 2041.0 / 44100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_block_stats() {
+        let mut data = device_wav(3, "Artist");
+        data.extend_from_slice(&[0, 0, 99, 1, 0, 0]);
+        let wav = Wav::parse(&data).unwrap();
+        // Data chunk size does not include the trailing bytes
+        assert_eq!(wav.block_stats(&data).unwrap().blocks, 3);
+
+        let mut wav = wav;
+        wav.data.end = data.len();
+        let stats = wav.block_stats(&data).unwrap();
+        assert_eq!(stats.blocks, 4);
+        assert_eq!(stats.partial_block, 6);
+        assert_eq!(stats.predictor, (-100, 100));
+        assert_eq!(stats.step_index, (0, 99));
+        assert_eq!(stats.invalid_step_index, 1);
+        assert_eq!(stats.nonzero_reserved, 1);
+    }
+
+    #[test]
+    fn test_rejects_non_wav() {
+        assert!(Wav::parse(b"ID3\x04\0\0\0\0\0\0\0\0").is_err());
+    }
+}
@@ -24,9 +24,10 @@ use firmware::drivers::audio::Player;
 use firmware::drivers::charger::Charger;
 use firmware::drivers::control_button::Button;
 use firmware::drivers::indicator::IndicatorLed;
-use firmware::drivers::rfid::Rfid;
+use firmware::drivers::rfid::{Rfid, RfidScanResult, TagEvent};
 use firmware::drivers::sd::{Sd, SdFsWrapper};
 use firmware::drivers::spi_bus;
+use firmware::entities::system_sound::SystemSound;
 use firmware::mk_static;
 use firmware::peripherals::create_peripherals;
 use {esp_backtrace as _, esp_println as _};
@@ -102,8 +103,12 @@ async fn main(spawner: Spawner) {
     let radio = firmware::drivers::radio::Radio::new(peripherals.radio_wifi);
 
     info!("Main: Initializing controls");
-    let btn_play_pause = Button::new(peripherals.controls.btn_a).with_long_press(1500);
-    let btn_next_prev = Button::new(peripherals.controls.btn_b).with_long_press(1500);
+    let btn_play_pause = Button::new(peripherals.controls.btn_a)
+        .with_long_press(1500)
+        .with_repeat(1500);
+    let btn_next_prev = Button::new(peripherals.controls.btn_b)
+        .with_long_press(1500)
+        .with_repeat(500);
     let btn_vol_down = Button::new(peripherals.controls.btn_c).with_repeat(500);
     let btn_vol_up = Button::new(peripherals.controls.btn_d).with_repeat(500);
 
@@ -117,7 +122,7 @@ async fn main(spawner: Spawner) {
     .await;
 
     // Spawn driver tasks
-    let player_handle = PlaybackController::new(player, fs).spawn(&spawner);
+    let player_handle = PlaybackController::new(player, fs, device_config.as_ref()).spawn(&spawner);
     let mut charger_monitor = charger.spawn(&spawner);
     let indicator_handle = indicator.spawn(&spawner);
     let radio_handle = radio.spawn(&spawner).await;
@@ -134,6 +139,7 @@ async fn main(spawner: Spawner) {
         device_config,
         charger_monitor.clone(),
         rtc,
+        player_handle.clone(),
     );
 
     info!("Main: Starting network controller");
@@ -152,6 +158,8 @@ async fn main(spawner: Spawner) {
         player_handle.clone(),
     );
 
+    player_handle.play_sound(SystemSound::Boot, 1).await;
+
     debug!("Main: all controllers spawned, entering main loop");
     loop {
         rfid_handle.trigger_scan();
@@ -164,20 +172,30 @@ async fn main(spawner: Spawner) {
             is_playing, is_charging, is_wifi_active
         );
 
-        let scan_interval_ms = match rfid_handle.wait_for_scan_result().await {
-            firmware::drivers::rfid::RfidScanResult::Found(fob) => {
-                player_handle
-                    .play_playlist_ref(firmware::entities::playlist::PlayListRef::new(fob))
-                    .await;
-                is_playing = true;
-
-                5000
+        let scan = rfid_handle.wait_for_scan_result().await;
+        // A fob left on the reader starts its playlist once, not with every scan
+        for event in scan.events {
+            match event {
+                TagEvent::Arrived(fob) => {
+                    player_handle
+                        .fob_scanned(firmware::entities::playlist::PlayListRef::new(fob))
+                        .await;
+                    is_playing = true;
+                }
+                TagEvent::Left(fob) => player_handle.tag_left(fob).await,
             }
-            firmware::drivers::rfid::RfidScanResult::NotFound => 500,
-            firmware::drivers::rfid::RfidScanResult::Error => 1000,
+        }
+        let scan_interval_ms = match scan.result {
+            // Removing the fob has to be noticed quickly in tag presence mode
+            RfidScanResult::Found(_) if player_handle.tag_presence() => 1000,
+            RfidScanResult::Found(_) => 5000,
+            RfidScanResult::NotFound => 500,
+            RfidScanResult::Error => 1000,
         };
 
-        if is_charging || is_playing || is_wifi_active {
+        // After the sleep timer, the device sleeps at night even while charging
+        let is_bedtime = player_handle.sleep_timer_elapsed();
+        if is_playing || is_wifi_active || (is_charging && !is_bedtime) {
             Timer::after(Duration::from_millis(scan_interval_ms)).await;
         } else {
             let timer_wakeup =
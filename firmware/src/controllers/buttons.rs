@@ -8,6 +8,14 @@ use esp_hal::rtc_cntl::Rtc;
 use crate::controllers::playback::PlaybackHandle;
 use crate::controllers::wifi::WifiManagerHandle;
 use crate::drivers::control_button::{Button, PressType};
+use crate::entities::system_sound::SystemSound;
+
+/// Seconds scrubbed per repeat interval while holding next/prev
+const SCRUB_STEP_SECS: i32 = 10;
+/// Time after tapping next/prev in which holding it fast-forwards instead of skipping
+const TAP_HOLD_WINDOW: Duration = Duration::from_millis(400);
+/// Sleep timer settings cycled by holding play/pause, 0 turns the timer off
+const SLEEP_TIMER_STEPS: [u16; 4] = [15, 30, 45, 0];
 
 pub struct Buttons {
     rtc: &'static RefCell<Rtc<'static>>,
@@ -89,6 +97,18 @@ enum ControlEvent {
     WifiOn,
 }
 
+/// Seeks by `step` seconds per repeat interval while next/prev stays held after a long press.
+/// Returns whether it was held long enough to seek at all.
+async fn scrub(buttons: &mut Buttons, player: &PlaybackHandle, step: i32) -> bool {
+    let mut scrubbed = false;
+    while buttons.next_prev.check_repeat().await {
+        info!("Buttons: NextPrev (scrub {})", step);
+        player.seek_by(step).await;
+        scrubbed = true;
+    }
+    scrubbed
+}
+
 #[embassy_executor::task]
 async fn buttons_task(
     mut buttons: Buttons,
@@ -100,15 +120,61 @@ async fn buttons_task(
             ControlEvent::PlayPause(press_type) => {
                 info!("Buttons: PlayPause({:?})", press_type);
                 match press_type {
+                    // In learn mode the press saves the playlist playing to the new fob
+                    PressType::Short if player.learning_fob().is_some() => {
+                        player.confirm_learning().await
+                    }
                     PressType::Short => player.pause().await,
-                    PressType::Long => player.stop().await,
+                    // Releasing right after the long press stops, holding on cycles through
+                    // the sleep timer settings with one sound per 15 minutes
+                    PressType::Long => {
+                        let mut step = 0;
+                        while buttons.play_pause.check_repeat().await {
+                            let minutes = SLEEP_TIMER_STEPS[step % SLEEP_TIMER_STEPS.len()];
+                            info!("Buttons: PlayPause (sleep timer {} min)", minutes);
+                            player.set_sleep_timer(minutes).await;
+                            match minutes {
+                                0 => player.play_sound(SystemSound::SleepTimerOff, 1).await,
+                                _ => {
+                                    player
+                                        .play_sound(SystemSound::SleepTimer, (minutes / 15) as u8)
+                                        .await
+                                }
+                            }
+                            step += 1;
+                        }
+                        if step == 0 {
+                            player.stop().await;
+                        }
+                    }
                 }
             }
             ControlEvent::NextPrev(press_type) => {
                 info!("Buttons: NextPrev({:?})", press_type);
                 match press_type {
-                    PressType::Short => player.skip_next().await,
-                    PressType::Long => player.skip_previous().await,
+                    // A tap followed by a hold fast-forwards the current track, a tap alone
+                    // goes to the next one
+                    PressType::Short => match buttons
+                        .next_prev
+                        .wait_for_press_within(TAP_HOLD_WINDOW)
+                        .await
+                    {
+                        Some(PressType::Long) => {
+                            scrub(&mut buttons, &player, SCRUB_STEP_SECS).await;
+                        }
+                        Some(PressType::Short) => {
+                            player.skip_next().await;
+                            player.skip_next().await;
+                        }
+                        None => player.skip_next().await,
+                    },
+                    // Releasing right after the long press goes to the previous track,
+                    // holding on rewinds the current one
+                    PressType::Long => {
+                        if !scrub(&mut buttons, &player, -SCRUB_STEP_SECS).await {
+                            player.skip_previous().await;
+                        }
+                    }
                 }
             }
             ControlEvent::VolumeUp => {
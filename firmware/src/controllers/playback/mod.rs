@@ -1,29 +1,53 @@
 pub mod status;
 
-use core::cell::RefCell;
-use core::sync::atomic::{AtomicU8, Ordering};
+use core::cell::{Cell, RefCell};
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
 
 use alloc::boxed::Box;
+use audio_file_utils::eq::Equalizer;
+use audio_file_utils::order::{self, PlayOrder};
+use audio_file_utils::ramp::GainRamp;
 use defmt::{debug, info, warn};
 use embassy_executor::Spawner;
-use embassy_futures::select::{Either, Either3, select, select3};
+use embassy_futures::select::{Either, Either4, select, select4};
+use embassy_sync::blocking_mutex::Mutex;
 use embassy_sync::blocking_mutex::raw::{CriticalSectionRawMutex, NoopRawMutex};
 use embassy_sync::channel::{Channel, Sender};
 use embassy_sync::signal::Signal;
 use embassy_sync::watch::Watch;
+use embassy_time::{Duration, Instant, Timer};
+use embedded_io_async::Read;
 use futures::future::LocalBoxFuture;
+use heapless::String;
 
-use self::status::{AudioFileWithMetadata, PlaylistWithMetadata, State, Status};
-use crate::PrintErr;
+use self::status::{AudioFileWithMetadata, PlaybackMode, PlaylistWithMetadata, State, Status};
 use crate::drivers::audio::{AudioBuffer, AudioPacket, AudioSender, BUF_SAMPLES, Player};
-use crate::drivers::sd::{PlaybackGuard, SdFsWrapper};
+use crate::drivers::sd::{PlaybackGuard, SdFileSystem, SdFsWrapper};
 use crate::entities::audio_file::{AudioDecoder, AudioFile};
-use crate::entities::playlist::{PlayListRef, Playlist};
+use crate::entities::playlist::{PlayListRef, Playlist, Repeat, Transition};
+use crate::entities::resume::{ResumeMode, ResumePoint};
+use crate::entities::system_sound::{SystemSound, Tone};
+use crate::entities::volume::{self, DEFAULT_VOLUME, MAX_VOLUME, SavedVolume};
+use crate::{
+    DeviceConfig, EqConfig, PrintErr, RescanAction, RescanConfig, TagPresenceConfig,
+    UnknownFobAction,
+};
 
 extern crate alloc;
 use alloc::rc::Rc;
 use alloc::vec::Vec;
 
+/// Samples of playback between two saves of the resume point
+const RESUME_SAVE_INTERVAL: u64 = 30 * 44100;
+/// Fade-out after the sleep timer elapsed, playback stops at its end
+const SLEEP_FADE: Duration = Duration::from_secs(30);
+/// Samples before the end of a track at which the next one is opened
+const LOOKAHEAD_SAMPLES: u64 = 44100;
+/// Length of the gain ramps fading playback in and out, 30 ms
+const RAMP_SAMPLES: u32 = 1323;
+/// How long a fob without playlist waits for one in learn mode
+const LEARN_TIMEOUT: Duration = Duration::from_secs(120);
+
 // ---- Command protocol ----
 
 #[derive(Clone, Copy, defmt::Format)]
@@ -32,17 +56,144 @@ pub enum Skip {
     Previous,
 }
 
+impl From<Skip> for order::Skip {
+    fn from(skip: Skip) -> Self {
+        match skip {
+            Skip::Next => order::Skip::Next,
+            Skip::Previous => order::Skip::Previous,
+        }
+    }
+}
+
+#[derive(Clone, Copy, defmt::Format)]
+pub enum Seek {
+    /// Absolute position in seconds from the start of the current file
+    To(u32),
+    /// Seconds relative to the current position, negative to rewind
+    By(i32),
+}
+
+impl Seek {
+    fn target_sample(self, current_sample: u64) -> u64 {
+        match self {
+            Seek::To(seconds) => seconds as u64 * 44100,
+            Seek::By(seconds) => current_sample.saturating_add_signed(seconds as i64 * 44100),
+        }
+    }
+}
+
+/// Files added to the queue
+#[derive(defmt::Format)]
+pub enum QueueSource {
+    Files(Vec<AudioFile>),
+    PlaylistRef(PlayListRef),
+}
+
+impl QueueSource {
+    async fn into_files(self, fs: &SdFileSystem) -> Vec<AudioFile> {
+        match self {
+            QueueSource::Files(files) => files,
+            QueueSource::PlaylistRef(playlist_ref) => playlist_ref
+                .read(fs)
+                .await
+                .print_err("Playback: Failed to read playlist")
+                .map(|playlist| playlist.files)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Change to the files of the playlist playing, indices are positions in the playlist
+#[derive(defmt::Format)]
+pub enum QueueEdit {
+    /// Adds files after the last one
+    Append(QueueSource),
+    /// Adds files right after the one playing
+    InsertNext(QueueSource),
+    /// Removes a file, skipping it if it is playing
+    Remove(usize),
+    Move {
+        from: usize,
+        to: usize,
+    },
+    /// Removes all files but the one playing, playback ends after it
+    Clear,
+}
+
 #[derive(defmt::Format)]
 pub enum PlayerCommand {
     Stop,
     PlayFile(AudioFile),
     Playlist(Playlist),
     PlaylistRef(PlayListRef),
+    /// A fob was put on the reader. Unlike `PlaylistRef`, scanning the fob playing again applies
+    /// the second swipe action.
+    FobScanned(PlayListRef),
+    /// Edits the playlist playing. Adding files while stopped plays them.
+    Queue(QueueEdit),
     Pause,
     SetVolume(u8),
+    /// Limits the volume, lowering it if it is above the new maximum
+    SetMaxVolume(u8),
+    /// Equalizer settings, `None` turns the equalizer off
+    SetEq(Option<EqConfig>),
     VolumeUp,
     VolumeDown,
     Skip(Skip),
+    Seek(Seek),
+    SetRepeat(Repeat),
+    SetShuffle(bool),
+    /// Fades out and stops playback after this many minutes, 0 cancels the timer
+    SleepTimer(u16),
+    /// Plays a system sound `count` times, between two buffers while playing. Dropped while
+    /// paused.
+    Sound {
+        sound: SystemSound,
+        count: u8,
+    },
+    SetUnknownFob(UnknownFobAction),
+    /// Saves the playlist playing to the fob waiting for one in learn mode
+    ConfirmLearning,
+    /// Tag presence mode, `None` turns it off
+    SetTagPresence(Option<TagPresenceConfig>),
+    /// The fob left the reader, which pauses its playlist in tag presence mode
+    TagLeft(String<8>),
+    SetRescan(RescanConfig),
+}
+
+/// File system work of the controller. While playing, the playlist task owns the file system
+/// and does the work between two buffers.
+enum FsRequest {
+    /// Whether the fob has a playlist
+    FobKnown(PlayListRef),
+    /// Saves `files` as playlist of `fob`, with the directives of the fob playlist `source`
+    Learn {
+        fob: String<8>,
+        files: Vec<AudioFile>,
+        source: String<8>,
+    },
+}
+
+impl FsRequest {
+    async fn run(self, fs: &SdFileSystem) -> bool {
+        match self {
+            FsRequest::FobKnown(playlist_ref) => playlist_ref.exists(fs).await,
+            FsRequest::Learn { fob, files, source } => {
+                let source = PlayListRef::new(source);
+                let mode = if source.exists(fs).await {
+                    source
+                        .read(fs)
+                        .await
+                        .map(|playlist| playlist.mode)
+                        .unwrap_or_default()
+                } else {
+                    Default::default()
+                };
+                ResumePoint::reset_position(fs, &fob).await;
+                Playlist::write(fs, fob, &files, &mode).await.is_ok()
+            }
+        }
+    }
 }
 
 // ---- Shared playback state ----
@@ -50,8 +201,38 @@ pub enum PlayerCommand {
 struct PlaybackContext {
     desired_state: &'static Watch<CriticalSectionRawMutex, State, 2>,
     skip_signal: &'static Signal<CriticalSectionRawMutex, Skip>,
+    seek_signal: &'static Signal<CriticalSectionRawMutex, Seek>,
+    /// System sound and its count to play between two buffers
+    sound_signal: &'static Signal<CriticalSectionRawMutex, (SystemSound, u8)>,
+    /// Queue edits for the playlist task, applied between two buffers
+    queue_edits: &'static Channel<CriticalSectionRawMutex, QueueEdit, 4>,
+    /// File system work for the playlist task and its result
+    fs_request: &'static Signal<CriticalSectionRawMutex, FsRequest>,
+    fs_reply: &'static Signal<CriticalSectionRawMutex, bool>,
+    /// Volume step, see `entities::volume`
     volume: &'static AtomicU8,
+    max_volume: &'static AtomicU8,
+    /// Set when the volume changed since it was last saved
+    volume_changed: &'static AtomicBool,
+    /// Mode for playlists without their own repeat or shuffle directives
+    default_mode: &'static Mutex<CriticalSectionRawMutex, Cell<PlaybackMode>>,
+    /// Equalizer settings from the config, playback picks up changes with the next buffer
+    eq: &'static Mutex<CriticalSectionRawMutex, Cell<Option<EqConfig>>>,
+    sleep_deadline: &'static Mutex<CriticalSectionRawMutex, Cell<Option<Instant>>>,
+    /// Set when the sleep timer stopped playback, until the next playlist starts
+    sleep_timer_elapsed: &'static AtomicBool,
+    unknown_fob: &'static Mutex<CriticalSectionRawMutex, Cell<UnknownFobAction>>,
+    /// Fob without playlist in learn mode and when learn mode ends
+    learning: &'static Mutex<CriticalSectionRawMutex, RefCell<Option<(String<8>, Instant)>>>,
+    tag_presence: &'static Mutex<CriticalSectionRawMutex, Cell<Option<TagPresenceConfig>>>,
+    /// Fob whose removal paused its playlist and when the playlist stops
+    removed_fob: &'static Mutex<CriticalSectionRawMutex, RefCell<Option<(String<8>, Instant)>>>,
+    rescan: &'static Mutex<CriticalSectionRawMutex, Cell<RescanConfig>>,
+    /// When a fob was scanned last, for the cooldown of second swipes
+    last_scan: &'static Mutex<CriticalSectionRawMutex, Cell<Option<Instant>>>,
     status: &'static Status,
+    /// Output state of system sounds played while stopped, which do not count as playback
+    sound_status: &'static Status,
 }
 
 impl PlaybackContext {
@@ -59,19 +240,189 @@ impl PlaybackContext {
         let desired_state =
             crate::mk_static!(Watch<CriticalSectionRawMutex, State, 2>, Watch::new());
         let skip_signal = crate::mk_static!(Signal<CriticalSectionRawMutex, Skip>, Signal::new());
-        let volume = crate::mk_static!(AtomicU8, AtomicU8::new(8));
+        let seek_signal = crate::mk_static!(Signal<CriticalSectionRawMutex, Seek>, Signal::new());
+        let sound_signal = crate::mk_static!(
+            Signal<CriticalSectionRawMutex, (SystemSound, u8)>,
+            Signal::new()
+        );
+        let queue_edits = crate::mk_static!(
+            Channel<CriticalSectionRawMutex, QueueEdit, 4>,
+            Channel::new()
+        );
+        let fs_request =
+            crate::mk_static!(Signal<CriticalSectionRawMutex, FsRequest>, Signal::new());
+        let fs_reply = crate::mk_static!(Signal<CriticalSectionRawMutex, bool>, Signal::new());
+        let volume = crate::mk_static!(AtomicU8, AtomicU8::new(DEFAULT_VOLUME));
+        let max_volume = crate::mk_static!(AtomicU8, AtomicU8::new(MAX_VOLUME));
+        let volume_changed = crate::mk_static!(AtomicBool, AtomicBool::new(false));
+        let default_mode = crate::mk_static!(
+            Mutex<CriticalSectionRawMutex, Cell<PlaybackMode>>,
+            Mutex::new(Cell::new(PlaybackMode::default()))
+        );
+        let eq = crate::mk_static!(
+            Mutex<CriticalSectionRawMutex, Cell<Option<EqConfig>>>,
+            Mutex::new(Cell::new(None))
+        );
+        let sleep_deadline = crate::mk_static!(
+            Mutex<CriticalSectionRawMutex, Cell<Option<Instant>>>,
+            Mutex::new(Cell::new(None))
+        );
+        let sleep_timer_elapsed = crate::mk_static!(AtomicBool, AtomicBool::new(false));
+        let unknown_fob = crate::mk_static!(
+            Mutex<CriticalSectionRawMutex, Cell<UnknownFobAction>>,
+            Mutex::new(Cell::new(UnknownFobAction::default()))
+        );
+        let learning = crate::mk_static!(
+            Mutex<CriticalSectionRawMutex, RefCell<Option<(String<8>, Instant)>>>,
+            Mutex::new(RefCell::new(None))
+        );
+        let tag_presence = crate::mk_static!(
+            Mutex<CriticalSectionRawMutex, Cell<Option<TagPresenceConfig>>>,
+            Mutex::new(Cell::new(None))
+        );
+        let removed_fob = crate::mk_static!(
+            Mutex<CriticalSectionRawMutex, RefCell<Option<(String<8>, Instant)>>>,
+            Mutex::new(RefCell::new(None))
+        );
+        let rescan = crate::mk_static!(
+            Mutex<CriticalSectionRawMutex, Cell<RescanConfig>>,
+            Mutex::new(Cell::new(RescanConfig::default()))
+        );
+        let last_scan = crate::mk_static!(
+            Mutex<CriticalSectionRawMutex, Cell<Option<Instant>>>,
+            Mutex::new(Cell::new(None))
+        );
         let status = crate::mk_static!(Status, Status::new());
+        let sound_status = crate::mk_static!(Status, Status::new());
 
         desired_state.sender().send(State::Stopped);
 
         Self {
             desired_state,
             skip_signal,
+            seek_signal,
+            sound_signal,
+            queue_edits,
+            fs_request,
+            fs_reply,
             volume,
+            max_volume,
+            volume_changed,
+            default_mode,
+            eq,
+            sleep_deadline,
+            sleep_timer_elapsed,
+            unknown_fob,
+            learning,
+            tag_presence,
+            removed_fob,
+            rescan,
+            last_scan,
             status,
+            sound_status,
+        }
+    }
+
+    /// Sets the volume to `f(current)`, limited to the maximum volume. Step 0 is muted, the
+    /// volume never goes that low.
+    fn update_volume(&self, f: impl Fn(u8) -> u8) {
+        let max_volume = self.max_volume.load(Ordering::SeqCst);
+        let old = self
+            .volume
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |vol| {
+                Some(f(vol).clamp(1, max_volume))
+            })
+            .unwrap();
+        if self.volume.load(Ordering::SeqCst) != old {
+            self.volume_changed.store(true, Ordering::SeqCst);
+        }
+    }
+
+    fn sleep_deadline(&self) -> Option<Instant> {
+        self.sleep_deadline.lock(|deadline| deadline.get())
+    }
+
+    fn set_sleep_deadline(&self, deadline: Option<Instant>) {
+        self.sleep_deadline.lock(|cell| cell.set(deadline));
+        self.status.update_sleep_deadline(deadline);
+    }
+
+    /// Gain out of 256, falling to zero while the sleep timer fades playback out
+    fn sleep_fade_gain(&self) -> i32 {
+        let Some(deadline) = self.sleep_deadline() else {
+            return 256;
+        };
+        match Instant::now().checked_duration_since(deadline) {
+            Some(fading) => {
+                let faded = fading.as_millis().min(SLEEP_FADE.as_millis());
+                256 - (faded * 256 / SLEEP_FADE.as_millis()) as i32
+            }
+            None => 256,
         }
     }
 
+    /// Fob waiting for a playlist in learn mode, until `LEARN_TIMEOUT` passed
+    fn learning_fob(&self) -> Option<String<8>> {
+        self.learning.lock(|cell| {
+            let mut learning = cell.borrow_mut();
+            if learning
+                .as_ref()
+                .is_some_and(|(_, deadline)| *deadline <= Instant::now())
+            {
+                *learning = None;
+            }
+            learning.as_ref().map(|(fob, _)| fob.clone())
+        })
+    }
+
+    fn set_learning_fob(&self, fob: Option<String<8>>) {
+        let learning = fob.map(|fob| (fob, Instant::now() + LEARN_TIMEOUT));
+        self.learning.lock(|cell| cell.replace(learning));
+    }
+
+    /// When the playlist paused by removing its fob stops
+    fn removed_fob_deadline(&self) -> Option<Instant> {
+        self.removed_fob
+            .lock(|cell| cell.borrow().as_ref().map(|(_, deadline)| *deadline))
+    }
+
+    /// Forgets the fob whose removal paused playback, returning it
+    fn take_removed_fob(&self) -> Option<String<8>> {
+        self.removed_fob
+            .lock(|cell| cell.take())
+            .map(|(fob, _)| fob)
+    }
+
+    /// Whether the playlist playing, or paused, is the one of `fob`
+    fn is_fob_active(&self, fob: &str) -> bool {
+        self.desired_state.try_get() != Some(State::Stopped)
+            && self
+                .status
+                .get_current_playlist()
+                .is_some_and(|playlist| playlist.playlist_name.eq_ignore_ascii_case(fob))
+    }
+
+    fn default_mode(&self) -> PlaybackMode {
+        self.default_mode.lock(|mode| mode.get())
+    }
+
+    /// Applies `f` to the default mode and to the mode of the playlist playing
+    fn update_mode(&self, f: impl Fn(&mut PlaybackMode)) {
+        self.default_mode.lock(|mode| {
+            let mut default_mode = mode.get();
+            f(&mut default_mode);
+            mode.set(default_mode);
+        });
+        let mut current = self.mode();
+        f(&mut current);
+        self.status.update_mode(current);
+    }
+
+    /// Mode of the playlist playing
+    fn mode(&self) -> PlaybackMode {
+        self.status.get_playback_status().mode
+    }
+
     fn set_desired_state(&self, state: State) {
         self.desired_state.sender().send(state);
     }
@@ -90,15 +441,39 @@ pub struct PlaybackController {
     player: Rc<RefCell<Player>>,
     fs: &'static SdFsWrapper,
     context: &'static PlaybackContext,
+    /// Volume after power-on instead of the saved one
+    startup_volume: Option<u8>,
 }
 
 impl PlaybackController {
-    pub fn new(player: Player, fs: &'static SdFsWrapper) -> Self {
+    pub fn new(player: Player, fs: &'static SdFsWrapper, config: Option<&DeviceConfig>) -> Self {
         let context = crate::mk_static!(PlaybackContext, PlaybackContext::new());
+        context
+            .eq
+            .lock(|cell| cell.set(config.and_then(|config| config.eq)));
+        context
+            .tag_presence
+            .lock(|cell| cell.set(config.and_then(|config| config.tag_presence)));
+        context
+            .rescan
+            .lock(|cell| cell.set(config.and_then(|config| config.rescan).unwrap_or_default()));
+        context.unknown_fob.lock(|cell| {
+            cell.set(
+                config
+                    .and_then(|config| config.unknown_fob)
+                    .unwrap_or_default(),
+            )
+        });
+        if let Some(max_volume) = config.and_then(|config| config.max_volume) {
+            context
+                .max_volume
+                .store(max_volume.clamp(1, MAX_VOLUME), Ordering::SeqCst);
+        }
         Self {
             player: Rc::new(RefCell::new(player)),
             fs,
             context,
+            startup_volume: config.and_then(|config| config.startup_volume),
         }
     }
 
@@ -114,6 +489,7 @@ impl PlaybackController {
             self.fs,
             command_channel,
             self.context,
+            self.startup_volume,
             *spawner,
         ));
 
@@ -127,12 +503,52 @@ async fn controller_task(
     fs: &'static SdFsWrapper,
     command_channel: &'static Channel<NoopRawMutex, PlayerCommand, 2>,
     context: &'static PlaybackContext,
+    startup_volume: Option<u8>,
     spawner: Spawner,
 ) {
     let receiver = command_channel.receiver();
 
+    let startup_volume = match startup_volume {
+        Some(volume) => volume,
+        None => {
+            let fs_guard = fs.borrow_mut().await;
+            SavedVolume::read(&fs_guard)
+                .await
+                .map_or(DEFAULT_VOLUME, |saved| saved.volume)
+        }
+    };
+    info!("Playback: startup volume {}", startup_volume);
+    context.update_volume(|_| startup_volume.max(1));
+    context.volume_changed.store(false, Ordering::SeqCst);
+
     loop {
-        let command = receiver.receive().await;
+        let sleep_end = context
+            .sleep_deadline()
+            .map(|deadline| deadline + SLEEP_FADE);
+        let removed_end = context.removed_fob_deadline();
+        let command = match sleep_end.into_iter().chain(removed_end).min() {
+            Some(deadline) => match select(receiver.receive(), Timer::at(deadline)).await {
+                Either::First(command) => command,
+                Either::Second(_) => {
+                    if sleep_end.is_some_and(|end| end <= Instant::now()) {
+                        info!("Playback: sleep timer elapsed, stopping");
+                        context.set_sleep_deadline(None);
+                        stop_playback(context).await;
+                        context.sleep_timer_elapsed.store(true, Ordering::SeqCst);
+                    }
+                    if removed_end.is_some_and(|end| end <= Instant::now()) {
+                        context.take_removed_fob();
+                        // Playback may have been resumed from the buttons or the API since
+                        if context.desired_state.try_get() == Some(State::Paused) {
+                            info!("Playback: fob did not return, stopping");
+                            stop_playback(context).await;
+                        }
+                    }
+                    continue;
+                }
+            },
+            None => receiver.receive().await,
+        };
         handle_command(command, player.clone(), fs, &spawner, context).await;
     }
 }
@@ -156,49 +572,135 @@ async fn handle_command(
     match command {
         PlayerCommand::Stop => {
             info!("Playback: command: STOP");
+            context.set_sleep_deadline(None);
             stop_playback(context).await;
         }
         PlayerCommand::Pause => {
             info!("Playback: command: PAUSE");
-            let current = context.desired_state.try_get().unwrap();
-            let new = match current {
-                State::Playing => State::Paused,
-                State::Paused => State::Playing,
-                State::Stopped => State::Stopped,
-            };
-            context.set_desired_state(new);
+            toggle_pause(context);
         }
         PlayerCommand::VolumeUp => {
             info!("Playback: command: VOLUME UP");
-            context
-                .volume
-                .try_update(Ordering::SeqCst, Ordering::SeqCst, |vol| {
-                    Some(16.min(vol + 1))
-                })
-                .ok();
+            let volume = context.volume.load(Ordering::SeqCst);
+            context.update_volume(|vol| vol + 1);
+            if context.volume.load(Ordering::SeqCst) == volume {
+                play_sound(SystemSound::VolumeLimit, 1, player, fs, spawner, context).await;
+            }
+            save_volume_when_stopped(fs, context).await;
         }
         PlayerCommand::VolumeDown => {
             info!("Playback: command: VOLUME DOWN");
-            context
-                .volume
-                .try_update(Ordering::SeqCst, Ordering::SeqCst, |vol| {
-                    Some(1.max(vol - 1))
-                })
-                .ok();
+            context.update_volume(|vol| vol.saturating_sub(1).max(1));
+            save_volume_when_stopped(fs, context).await;
         }
         PlayerCommand::SetVolume(vol) => {
             info!("Playback: command: SET VOLUME {}", vol);
-            context.volume.store(16.min(vol), Ordering::SeqCst);
+            context.update_volume(|_| vol);
+            save_volume_when_stopped(fs, context).await;
+        }
+        PlayerCommand::SetMaxVolume(max_volume) => {
+            info!("Playback: command: SET MAX VOLUME {}", max_volume);
+            context
+                .max_volume
+                .store(max_volume.clamp(1, MAX_VOLUME), Ordering::SeqCst);
+            context.update_volume(|vol| vol);
+            save_volume_when_stopped(fs, context).await;
+        }
+        PlayerCommand::SetEq(eq) => {
+            info!("Playback: command: SET EQ {}", eq);
+            context.eq.lock(|cell| cell.set(eq));
         }
         PlayerCommand::Skip(skip) => {
             info!("Playback: command: SKIP {:?}", skip);
             context.skip_signal.signal(skip);
         }
+        PlayerCommand::Seek(seek) => {
+            info!("Playback: command: SEEK {:?}", seek);
+            context.seek_signal.signal(seek);
+        }
+        PlayerCommand::SetRepeat(repeat) => {
+            info!("Playback: command: SET REPEAT {:?}", repeat);
+            context.update_mode(|mode| mode.repeat = repeat);
+        }
+        PlayerCommand::SetShuffle(shuffle) => {
+            info!("Playback: command: SET SHUFFLE {}", shuffle);
+            context.update_mode(|mode| mode.shuffle = shuffle);
+        }
+        PlayerCommand::SleepTimer(minutes) => {
+            info!("Playback: command: SLEEP TIMER {} min", minutes);
+            let deadline =
+                (minutes > 0).then(|| Instant::now() + Duration::from_secs(minutes as u64 * 60));
+            context.set_sleep_deadline(deadline);
+        }
+        PlayerCommand::Sound { sound, count } => {
+            info!("Playback: command: SOUND {} x{}", sound, count);
+            play_sound(sound, count, player, fs, spawner, context).await;
+        }
+        PlayerCommand::SetUnknownFob(action) => {
+            info!("Playback: command: SET UNKNOWN FOB {}", action);
+            context.unknown_fob.lock(|cell| cell.set(action));
+            if action != UnknownFobAction::Learn {
+                context.set_learning_fob(None);
+            }
+        }
+        PlayerCommand::SetTagPresence(tag_presence) => {
+            info!("Playback: command: SET TAG PRESENCE {}", tag_presence);
+            context.tag_presence.lock(|cell| cell.set(tag_presence));
+        }
+        PlayerCommand::TagLeft(fob) => {
+            info!("Playback: command: TAG LEFT {}", fob);
+            let Some(tag_presence) = context.tag_presence.lock(|cell| cell.get()) else {
+                return;
+            };
+            // Only the fob of the playlist playing pauses it
+            if !context.is_fob_active(&fob)
+                || context.desired_state.try_get() != Some(State::Playing)
+            {
+                return;
+            }
+            context.set_desired_state(State::Paused);
+            let stop_after = Duration::from_secs(tag_presence.stop_after_seconds as u64);
+            context
+                .removed_fob
+                .lock(|cell| cell.replace(Some((fob, Instant::now() + stop_after))));
+        }
+        PlayerCommand::SetRescan(rescan) => {
+            info!("Playback: command: SET RESCAN {}", rescan);
+            context.rescan.lock(|cell| cell.set(rescan));
+        }
+        PlayerCommand::ConfirmLearning => {
+            info!("Playback: command: CONFIRM LEARNING");
+            let Some(fob) = context.learning_fob() else {
+                return;
+            };
+            let playlist = match context.desired_state.try_get() {
+                Some(State::Stopped) => None,
+                _ => context.status.get_current_playlist(),
+            };
+            let Some(playlist) = playlist else {
+                info!("Playback: nothing playing to save to fob {}", fob);
+                play_sound(SystemSound::Error, 1, player, fs, spawner, context).await;
+                return;
+            };
+            let request = FsRequest::Learn {
+                fob: fob.clone(),
+                files: playlist.files.into_iter().map(|file| file.file).collect(),
+                source: playlist.playlist_name,
+            };
+            let sound = if run_fs_request(request, fs, context).await {
+                info!("Playback: saved playlist to fob {}", fob);
+                context.set_learning_fob(None);
+                SystemSound::FobRecognized
+            } else {
+                SystemSound::Error
+            };
+            play_sound(sound, 1, player, fs, spawner, context).await;
+        }
         PlayerCommand::PlayFile(file) => {
             info!("Playback: command: PLAY FILE {}", file.name());
             stop_playback(context).await;
             let playlist = Playlist::new("SINGLE".try_into().unwrap(), alloc::vec![file]);
-            play_playlist(playlist, player, fs, spawner, context).await;
+            play_playlist(playlist, None, player, fs, spawner, context).await;
         }
         PlayerCommand::Playlist(playlist) => {
             info!(
@@ -206,34 +708,330 @@ async fn handle_command(
                 playlist.files.len()
             );
             stop_playback(context).await;
-            play_playlist(playlist, player, fs, spawner, context).await;
+            play_playlist(playlist, None, player, fs, spawner, context).await;
+        }
+        PlayerCommand::Queue(edit) => {
+            info!("Playback: command: QUEUE {}", edit);
+            if context.status.get_playback_status().state != State::Stopped {
+                if context.queue_edits.try_send(edit).is_err() {
+                    warn!("Playback: queue edits pending, dropping edit");
+                }
+                return;
+            }
+            // Nothing to edit, added files start playing
+            let (QueueEdit::Append(source) | QueueEdit::InsertNext(source)) = edit else {
+                return;
+            };
+            let fs_guard = fs.borrow_mut().await;
+            let files = source.into_files(&fs_guard).await;
+            drop(fs_guard);
+            if !files.is_empty() {
+                let playlist = Playlist::new("QUEUE".try_into().unwrap(), files);
+                play_playlist(playlist, None, player, fs, spawner, context).await;
+            }
         }
         PlayerCommand::PlaylistRef(playlist_ref) => {
             info!("Playback: command: PLAY LIST REF {}", playlist_ref);
-            stop_playback(context).await;
-            let fs_guard = fs.borrow_mut().await;
-            if let Some(playlist) = playlist_ref
-                .read(&fs_guard)
-                .await
-                .print_err("Playback: Failed to read playlist")
+            play_playlist_ref(playlist_ref, false, player, fs, spawner, context).await;
+        }
+        PlayerCommand::FobScanned(playlist_ref) => {
+            info!("Playback: command: FOB SCANNED {}", playlist_ref);
+            // Putting back the fob whose removal paused playback resumes it, other fobs switch
+            // playlists
+            if context
+                .take_removed_fob()
+                .is_some_and(|fob| fob.eq_ignore_ascii_case(playlist_ref.name()))
+                && context.desired_state.try_get() == Some(State::Paused)
             {
-                drop(fs_guard);
-                play_playlist(playlist, player, fs, spawner, context).await;
+                info!("Playback: fob returned, resuming");
+                context.set_desired_state(State::Playing);
+                return;
+            }
+            let last_scan = context
+                .last_scan
+                .lock(|cell| cell.replace(Some(Instant::now())));
+            if context.is_fob_active(playlist_ref.name()) {
+                handle_rescan(playlist_ref, last_scan, player, fs, spawner, context).await;
+                return;
+            }
+            play_playlist_ref(playlist_ref, false, player, fs, spawner, context).await;
+        }
+    }
+}
+
+/// Pauses, or resumes while paused
+fn toggle_pause(context: &PlaybackContext) {
+    // Paused or resumed by hand, the returning fob no longer matters
+    context.take_removed_fob();
+    let current = context.desired_state.try_get().unwrap();
+    let new = match current {
+        State::Playing => State::Paused,
+        State::Paused => State::Playing,
+        State::Stopped => State::Stopped,
+    };
+    context.set_desired_state(new);
+}
+
+/// Plays the playlist of a fob from its resume point, or from its first track with `restart`
+async fn play_playlist_ref(
+    playlist_ref: PlayListRef,
+    restart: bool,
+    player: Rc<RefCell<Player>>,
+    fs: &'static SdFsWrapper,
+    spawner: &Spawner,
+    context: &'static PlaybackContext,
+) {
+    // Known before stopping, so an unknown fob does not interrupt playback
+    let request = FsRequest::FobKnown(playlist_ref.clone());
+    if !run_fs_request(request, fs, context).await {
+        handle_unknown_fob(playlist_ref, player, fs, spawner, context).await;
+        return;
+    }
+    stop_playback(context).await;
+    let fs_guard = fs.borrow_mut().await;
+    let resume_point = ResumePoint::read(&fs_guard, playlist_ref.name()).await;
+    if let Some(playlist) = playlist_ref
+        .read(&fs_guard)
+        .await
+        .print_err("Playback: Failed to read playlist")
+    {
+        drop(fs_guard);
+        let start = if restart {
+            ResumePoint {
+                mode: resume_point.mode,
+                ..Default::default()
             }
+        } else {
+            resume_point.start(playlist.files.len())
+        };
+        info!(
+            "Playback: starting {} at file {}, sample {}",
+            playlist.name(),
+            start.index,
+            start.sample
+        );
+        play_playlist(playlist, Some(start), player, fs, spawner, context).await;
+    } else {
+        drop(fs_guard);
+        play_sound(SystemSound::Error, 1, player, fs, spawner, context).await;
+    }
+}
+
+/// Applies the second swipe action to a scan of the fob playing or paused, unless it was
+/// scanned within the cooldown. A paused playlist only counts for `RescanAction::Pause`, which
+/// resumes it, other scans play it from its resume point like scans of any other fob.
+async fn handle_rescan(
+    playlist_ref: PlayListRef,
+    last_scan: Option<Instant>,
+    player: Rc<RefCell<Player>>,
+    fs: &'static SdFsWrapper,
+    spawner: &Spawner,
+    context: &'static PlaybackContext,
+) {
+    let rescan = context.rescan.lock(|cell| cell.get());
+    if context.desired_state.try_get() == Some(State::Paused)
+        && rescan.action != RescanAction::Pause
+    {
+        play_playlist_ref(playlist_ref, false, player, fs, spawner, context).await;
+        return;
+    }
+    let cooldown = Duration::from_secs(rescan.cooldown_seconds as u64);
+    if last_scan.is_some_and(|last| last + cooldown > Instant::now()) {
+        info!(
+            "Playback: fob {} scanned again within the cooldown",
+            playlist_ref.name()
+        );
+        return;
+    }
+    info!(
+        "Playback: fob {} scanned again, {}",
+        playlist_ref.name(),
+        rescan.action
+    );
+    match rescan.action {
+        RescanAction::Ignore => {}
+        RescanAction::Pause => toggle_pause(context),
+        RescanAction::Next => context.skip_signal.signal(Skip::Next),
+        RescanAction::Restart => {
+            play_playlist_ref(playlist_ref, true, player, fs, spawner, context).await
         }
     }
 }
 
+/// Reacts to a fob without playlist as configured, see `UnknownFobAction`
+async fn handle_unknown_fob(
+    playlist_ref: PlayListRef,
+    player: Rc<RefCell<Player>>,
+    fs: &'static SdFsWrapper,
+    spawner: &Spawner,
+    context: &'static PlaybackContext,
+) {
+    let action = context.unknown_fob.lock(|cell| cell.get());
+    info!(
+        "Playback: no playlist for fob {}, {}",
+        playlist_ref.name(),
+        action
+    );
+    match action {
+        UnknownFobAction::Ignore => return,
+        UnknownFobAction::Sound => {}
+        UnknownFobAction::Learn => {
+            context.set_learning_fob(Some(playlist_ref.name().try_into().unwrap()))
+        }
+    }
+    play_sound(SystemSound::UnknownFob, 1, player, fs, spawner, context).await;
+}
+
+/// Runs `request` in the playlist task while it owns the file system, right away otherwise
+async fn run_fs_request(
+    request: FsRequest,
+    fs: &SdFsWrapper,
+    context: &'static PlaybackContext,
+) -> bool {
+    let request = if context.desired_state.try_get() == Some(State::Stopped) {
+        request
+    } else {
+        context.fs_reply.reset();
+        context.fs_request.signal(request);
+        if let Either::First(reply) = select(
+            context.fs_reply.wait(),
+            context.wait_for_desired_state(State::Stopped),
+        )
+        .await
+        {
+            return reply;
+        }
+        // Playback ended, maybe before the playlist task got to the request
+        if let Some(reply) = context.fs_reply.try_take() {
+            return reply;
+        }
+        match context.fs_request.try_take() {
+            Some(request) => request,
+            None => return false,
+        }
+    };
+    let fs_guard = fs.borrow_mut().await;
+    request.run(&fs_guard).await
+}
+
+/// Plays a system sound between two buffers while playing, or on its own while stopped
+async fn play_sound(
+    sound: SystemSound,
+    count: u8,
+    player: Rc<RefCell<Player>>,
+    fs: &'static SdFsWrapper,
+    spawner: &Spawner,
+    context: &'static PlaybackContext,
+) {
+    let state = context.status.get_playback_status().state;
+    match (state, context.desired_state.try_get().unwrap()) {
+        (State::Paused, _) | (_, State::Paused) => {
+            debug!("Playback: paused, dropping sound {}", sound)
+        }
+        // Nothing plays or is about to, the output is free
+        (State::Stopped, State::Stopped) => {
+            let sender = Player::start(player, spawner, context.sound_status);
+            let fs_guard = fs.borrow_mut().await;
+            let gain = volume::gain(context.volume.load(Ordering::SeqCst));
+            send_sound(&sender, &fs_guard, sound, count, gain).await;
+            drop(fs_guard);
+            sender.send(AudioPacket::Eof).await;
+            context.sound_status.wait_for_state(State::Stopped).await;
+        }
+        _ => context.sound_signal.signal((sound, count)),
+    }
+}
+
+/// Sends `sound` `count` times with short pauses in between, its clip or else its tones
+async fn send_sound(
+    sender: &AudioSender,
+    fs: &SdFileSystem,
+    sound: SystemSound,
+    count: u8,
+    gain: i32,
+) {
+    let mut ramp = GainRamp::new(gain, 0);
+    for _ in 0..count {
+        match sound.data_reader(fs).await {
+            Ok(reader) => {
+                let mut decoder = AudioDecoder::new(reader);
+                loop {
+                    let mut buf = AudioBuffer::alloc();
+                    let n = decoder.next_samples(&mut buf.samples).await.unwrap_or(0);
+                    if n == 0 {
+                        break;
+                    }
+                    buf.len = n;
+                    ramp.apply(&mut buf.samples[..n]);
+                    sender.send(AudioPacket::Buffer(buf)).await;
+                }
+            }
+            Err(_) => send_tones(sender, sound.tones(), &mut ramp).await,
+        }
+        sender.send(AudioPacket::Silence(4410)).await;
+    }
+}
+
+/// Sends sine tones at half of full scale, scaled by `ramp`
+async fn send_tones(sender: &AudioSender, tones: &[Tone], ramp: &mut GainRamp) {
+    for tone in tones {
+        let mut remaining = tone.duration_ms as usize * 441 / 10;
+        if tone.frequency == 0 {
+            sender.send(AudioPacket::Silence(remaining as u32)).await;
+            continue;
+        }
+        let mut phase: f32 = 0.0;
+        let phase_step = 2.0f32 * core::f32::consts::PI * tone.frequency as f32 / 44100.0f32;
+
+        while remaining > 0 {
+            let mut buf = AudioBuffer::alloc();
+            let n = remaining.min(BUF_SAMPLES);
+            for sample in &mut buf.samples[..n] {
+                *sample = (libm::sinf(phase) * 16384.0f32) as i16;
+                phase += phase_step;
+                if phase >= 2.0f32 * core::f32::consts::PI {
+                    phase -= 2.0f32 * core::f32::consts::PI;
+                }
+            }
+            buf.len = n;
+            ramp.apply(&mut buf.samples[..n]);
+            sender.send(AudioPacket::Buffer(buf)).await;
+            remaining -= n;
+        }
+    }
+}
+
+/// Saves a changed volume right away unless playing, playback saves it with the resume point
+async fn save_volume_when_stopped(fs: &SdFsWrapper, context: &PlaybackContext) {
+    if context.status.get_playback_status().state == State::Stopped
+        && context.volume_changed.swap(false, Ordering::SeqCst)
+    {
+        let saved = SavedVolume {
+            volume: context.volume.load(Ordering::SeqCst),
+        };
+        let fs_guard = fs.borrow_mut().await;
+        let _ = saved.write(&fs_guard).await;
+    }
+}
+
+/// Plays `playlist`. With a resume point, playback starts there and the position is saved
+/// for the fob while playing.
 async fn play_playlist(
     playlist: Playlist,
+    resume_point: Option<ResumePoint>,
     player: Rc<RefCell<Player>>,
     fs: &'static SdFsWrapper,
     spawner: &Spawner,
     context: &'static PlaybackContext,
 ) {
+    context.sleep_timer_elapsed.store(false, Ordering::SeqCst);
+    context.take_removed_fob();
     debug!("Playback: building playlist metadata");
     let playlist_with_metadata = playlist_with_metadata_from_playlist(&playlist, fs).await;
     context.status.update_playlist(Some(playlist_with_metadata));
+    context
+        .status
+        .update_mode(context.default_mode().with_playlist_mode(&playlist.mode));
 
     debug!("Playback: borrowing fs for playback");
     let stop_fn: Box<dyn Fn() -> LocalBoxFuture<'static, ()>> =
@@ -241,9 +1039,15 @@ async fn play_playlist(
     let fs_guard = fs.borrow_for_playback(stop_fn).await;
     debug!("Playback: fs borrowed, spawning playlist task");
 
+    let fob = resume_point.map(|_| playlist.name.clone());
+    // Set before the task runs, so commands in between see playback starting
+    context.set_desired_state(State::Playing);
     spawner.must_spawn(playlist_task(
         fs_guard,
         playlist.files,
+        fob,
+        playlist.mode.transition.unwrap_or_default(),
+        resume_point.unwrap_or_default(),
         player,
         context,
         *spawner,
@@ -254,9 +1058,32 @@ async fn play_playlist(
 
 struct SendInterrupted;
 
+/// Request that stops the current buffer from being followed by the next one
+enum Interruption {
+    Pause,
+    Stop,
+    Skip(Skip),
+    Seek(Seek),
+    Sound(SystemSound, u8),
+    /// The file playing was removed from the queue
+    RemovedCurrent,
+}
+
+/// What the queue edits since the last buffer did to the playlist playing
+#[derive(PartialEq)]
+enum QueueChange {
+    Unchanged,
+    Edited,
+    RemovedCurrent,
+}
+
 struct PlaybackStream<'a> {
     sender: &'a AudioSender,
     context: &'a PlaybackContext,
+    /// Fob whose resume point is kept up to date, `None` for playlists not started by a fob
+    /// and once the queue was edited
+    fob: RefCell<Option<String<8>>>,
+    transition: Transition,
 }
 
 impl<'a> PlaybackStream<'a> {
@@ -272,39 +1099,48 @@ impl<'a> PlaybackStream<'a> {
         }
     }
 
-    async fn play_beep(&self, duration_100ms: u32) -> Result<(), SendInterrupted> {
-        let total_samples = duration_100ms as usize * 4410;
-        let mut remaining = total_samples;
-        let mut phase: f32 = 0.0;
-        let phase_step = 2.0f32 * core::f32::consts::PI * 1000.0f32 / 44100.0f32;
-
-        while remaining > 0 {
-            let mut buf = AudioBuffer::alloc();
-            let n = remaining.min(BUF_SAMPLES);
-            for i in 0..n {
-                let sample = (libm::sinf(phase) * 16384.0f32) as i16;
-                buf.samples[i] = sample;
-                phase += phase_step;
-                if phase >= 2.0f32 * core::f32::consts::PI {
-                    phase -= 2.0f32 * core::f32::consts::PI;
-                }
-            }
-            buf.len = n;
-
-            self.send_packet(AudioPacket::Buffer(buf)).await?;
-            remaining -= n;
+    /// Plays a system sound, the volume applies to it as to the music
+    async fn play_sound(
+        &self,
+        fs: &SdFileSystem,
+        sound: SystemSound,
+        count: u8,
+    ) -> Result<(), SendInterrupted> {
+        let gain = volume::gain(self.context.volume.load(Ordering::SeqCst));
+        match select(
+            send_sound(self.sender, fs, sound, count, gain),
+            self.context.wait_for_desired_state(State::Stopped),
+        )
+        .await
+        {
+            Either::First(_) => Ok(()),
+            Either::Second(_) => Err(SendInterrupted),
         }
-
-        Ok(())
     }
 
-    async fn play_silence(&self, duration_secs: u32) -> Result<(), SendInterrupted> {
-        let total = duration_secs * 44100;
-        self.send_packet(AudioPacket::Silence(total)).await?;
-        Ok(())
+    /// The request the next buffer has to fade out for, if any
+    fn pending_interruption(&self) -> Option<Interruption> {
+        match self.context.desired_state.try_get() {
+            Some(State::Stopped) => return Some(Interruption::Stop),
+            Some(State::Paused) => return Some(Interruption::Pause),
+            _ => {}
+        }
+        if let Some(skip) = self.context.skip_signal.try_take() {
+            return Some(Interruption::Skip(skip));
+        }
+        if let Some(seek) = self.context.seek_signal.try_take() {
+            return Some(Interruption::Seek(seek));
+        }
+        self.context
+            .sound_signal
+            .try_take()
+            .map(|(sound, count)| Interruption::Sound(sound, count))
     }
 
-    async fn handle_pause(&self) -> Result<(), SendInterrupted> {
+    /// Waits while paused. Returns a seek requested while paused, playback stays paused then.
+    /// Also returns early for queue edits and file system requests, the caller checks whether
+    /// it is still paused.
+    async fn handle_pause(&self) -> Result<Option<Seek>, SendInterrupted> {
         let mut rx = self.context.desired_state.receiver().unwrap();
         let mut desired_state = rx.try_get().unwrap();
         loop {
@@ -312,27 +1148,45 @@ impl<'a> PlaybackStream<'a> {
                 State::Paused => {
                     debug!("Playback: paused");
                     self.context.status.update_state(State::Paused);
-                    match select(
+                    match select4(
                         self.sender.send(AudioPacket::Silence(BUF_SAMPLES as u32)),
                         rx.changed(),
+                        self.context.seek_signal.wait(),
+                        select(
+                            self.context.queue_edits.ready_to_receive(),
+                            self.context.fs_request.wait(),
+                        ),
                     )
                     .await
                     {
-                        Either::First(_) => {}
-                        Either::Second(value) => {
+                        Either4::First(_) => {}
+                        Either4::Second(value) => {
                             debug!("Playback: state changed while paused");
                             desired_state = value;
 
                             if desired_state == State::Playing {
                                 debug!("Playback: resuming from pause");
                                 self.context.status.update_state(State::Playing);
-                                return Ok(());
+                                return Ok(None);
                             }
                         }
+                        Either4::Third(seek) => {
+                            debug!("Playback: seek {:?} while paused", seek);
+                            return Ok(Some(seek));
+                        }
+                        Either4::Fourth(Either::First(_)) => {
+                            debug!("Playback: queue edited while paused");
+                            return Ok(None);
+                        }
+                        // Handled by the caller before pausing again
+                        Either4::Fourth(Either::Second(request)) => {
+                            self.context.fs_request.signal(request);
+                            return Ok(None);
+                        }
                     }
                 }
                 State::Playing => {
-                    return Ok(());
+                    return Ok(None);
                 }
                 State::Stopped => {
                     debug!("Playback: stopped while paused");
@@ -343,24 +1197,19 @@ impl<'a> PlaybackStream<'a> {
     }
 }
 
-fn handle_skip(skip: Skip, current_index: &mut usize, total_files: usize) {
-    match skip {
-        Skip::Next => *current_index = (*current_index + 1).min(total_files.saturating_sub(1)),
-        Skip::Previous => *current_index = current_index.saturating_sub(1),
-    }
-}
-
 // ---- Playlist task (decoder + channel producer) ----
 
 #[embassy_executor::task]
 async fn playlist_task(
     fs_guard: PlaybackGuard<'static>,
     files: Vec<AudioFile>,
+    fob: Option<String<8>>,
+    transition: Transition,
+    start: ResumePoint,
     player: Rc<RefCell<Player>>,
     context: &'static PlaybackContext,
     spawner: Spawner,
 ) {
-    context.set_desired_state(State::Playing);
     debug!(
         "Playback: playlist task starting with {} files",
         files.len()
@@ -374,9 +1223,35 @@ async fn playlist_task(
     let stream = PlaybackStream {
         sender: &sender,
         context,
+        fob: RefCell::new(fob),
+        transition,
     };
-    let _ = stream.playlist_task_inner(fs_guard, files).await;
+    let mut position = start;
+    match stream
+        .playlist_task_inner(&fs_guard, files, &mut position)
+        .await
+    {
+        // Finished playlists start over on the next scan
+        Ok(()) => {
+            stream
+                .save_resume_point(
+                    &fs_guard,
+                    &ResumePoint {
+                        mode: position.mode,
+                        ..Default::default()
+                    },
+                )
+                .await
+        }
+        Err(SendInterrupted) => stream.save_resume_point(&fs_guard, &position).await,
+    }
+    stream.save_volume(&fs_guard).await;
+    drop(fs_guard);
     stream.close().await;
+    // Directives of the playlist only last while it plays
+    context.status.update_mode(context.default_mode());
+    // Also after the playlist ended by itself, so the output counts as free again
+    context.set_desired_state(State::Stopped);
 }
 
 impl PlaybackStream<'_> {
@@ -388,108 +1263,419 @@ impl PlaybackStream<'_> {
         debug!("Playback: stream closed");
     }
 
+    /// Saves the volume if it changed. The file system belongs to playback while playing, so
+    /// volume changes are saved from here.
+    async fn save_volume(&self, fs_guard: &PlaybackGuard<'static>) {
+        if self.context.volume_changed.swap(false, Ordering::SeqCst) {
+            let saved = SavedVolume {
+                volume: self.context.volume.load(Ordering::SeqCst),
+            };
+            debug!("Playback: saving volume {}", saved.volume);
+            let _ = saved.write(fs_guard).await;
+        }
+    }
+
+    /// Saves the resume point of the fob being played, unless it always restarts
+    async fn save_resume_point(&self, fs_guard: &PlaybackGuard<'static>, position: &ResumePoint) {
+        if let Some(fob) = self.fob.borrow().as_ref()
+            && position.mode == ResumeMode::Resume
+        {
+            debug!(
+                "Playback: saving resume point {}: file {}, sample {}",
+                fob.as_str(),
+                position.index,
+                position.sample
+            );
+            let _ = position.write(fs_guard, fob).await;
+        }
+    }
+
+    /// Moves `order` to the file skipped to and saves it as resume point
+    async fn skip(
+        &self,
+        skip: Skip,
+        order: &mut PlayOrder,
+        fs_guard: &PlaybackGuard<'static>,
+        position: &ResumePoint,
+    ) {
+        order.skip(skip.into(), self.context.mode().repeat.into());
+        self.save_resume_point(
+            fs_guard,
+            &ResumePoint {
+                index: order.current().unwrap_or(0),
+                sample: 0,
+                ..*position
+            },
+        )
+        .await;
+    }
+
+    /// Applies the queue edits sent since the last buffer to `files`, `order` and the playlist
+    /// in the status
+    async fn apply_queue_edits(
+        &self,
+        files: &mut Vec<AudioFile>,
+        order: &mut PlayOrder,
+        fs_guard: &PlaybackGuard<'static>,
+        position: &mut ResumePoint,
+    ) -> QueueChange {
+        let mut edits = Vec::new();
+        while let Ok(edit) = self.context.queue_edits.try_receive() {
+            edits.push(edit);
+        }
+        if edits.is_empty() {
+            return QueueChange::Unchanged;
+        }
+        let Some(mut playlist) = self.context.status.get_current_playlist() else {
+            return QueueChange::Unchanged;
+        };
+
+        // An edited playlist no longer matches the fob's, which resumes where it was left
+        if self.fob.borrow().is_some() {
+            self.save_resume_point(fs_guard, position).await;
+            self.fob.replace(None);
+        }
+
+        let mut change = QueueChange::Edited;
+        for edit in edits {
+            let len = playlist.files.len();
+            match edit {
+                QueueEdit::Append(source) => {
+                    let added = source.into_files(fs_guard).await;
+                    order.insert(len, added.len(), false);
+                    playlist
+                        .files
+                        .extend(files_with_metadata(&added, fs_guard).await);
+                }
+                QueueEdit::InsertNext(source) => {
+                    let added = source.into_files(fs_guard).await;
+                    let at = order.current().map_or(len, |current| current + 1);
+                    order.insert(at, added.len(), true);
+                    let added = files_with_metadata(&added, fs_guard).await;
+                    playlist.files.splice(at..at, added);
+                }
+                QueueEdit::Remove(index) if index < len => {
+                    if order.remove(index) {
+                        change = QueueChange::RemovedCurrent;
+                    }
+                    playlist.files.remove(index);
+                }
+                QueueEdit::Move { from, to } if from < len && to < len => {
+                    order.move_file(from, to);
+                    let file = playlist.files.remove(from);
+                    playlist.files.insert(to, file);
+                }
+                QueueEdit::Clear => {
+                    let current = order.current();
+                    order.keep_current();
+                    playlist.files = current
+                        .map(|current| playlist.files.swap_remove(current))
+                        .into_iter()
+                        .collect();
+                }
+                QueueEdit::Remove(_) | QueueEdit::Move { .. } => {
+                    warn!("Playback: queue index out of range");
+                }
+            }
+        }
+
+        *files = playlist
+            .files
+            .iter()
+            .map(|file| file.file.clone())
+            .collect();
+        self.context.status.update_playlist(Some(playlist));
+        if change == QueueChange::Edited
+            && let Some(index) = order.current()
+        {
+            position.index = index;
+            self.context.status.update_index(index);
+        }
+        change
+    }
+
+    /// Plays `files` from `position`, keeping `position` up to date with what is playing
     async fn playlist_task_inner(
         &self,
-        fs_guard: PlaybackGuard<'static>,
-        files: Vec<AudioFile>,
+        fs_guard: &PlaybackGuard<'static>,
+        mut files: Vec<AudioFile>,
+        position: &mut ResumePoint,
     ) -> Result<(), SendInterrupted> {
-        debug!("Playback: playing start beep");
-        self.play_beep(1).await?;
-
-        let mut current_index: usize = 0;
-        let total_files = files.len();
+        let recognized = self.fob.borrow().is_some();
+        if recognized {
+            self.play_sound(fs_guard, SystemSound::FobRecognized, 1)
+                .await?;
+        }
+        // Seeks, sounds and queue edits sent while nothing was playing are stale
+        self.context.seek_signal.reset();
+        self.context.sound_signal.reset();
+        self.context.queue_edits.clear();
+
+        let seed = embassy_time::Instant::now().as_ticks() as u32;
+        let mut order = PlayOrder::new(
+            files.len(),
+            position.index,
+            self.context.mode().shuffle,
+            seed,
+        );
+        let mut start_sample = position.sample;
+        let mut first = true;
+        let mut failures = 0;
+        let fade_samples = match self.transition {
+            Transition::Crossfade(ms) => ms as u64 * 441 / 10,
+            Transition::Gap(_) => 0,
+        };
+        // The track after the current one, opened before the current one ends
+        let mut next = None;
+        // Playback fades in, and back in after each interruption faded it out
+        let mut ramp = GainRamp::new(0, RAMP_SAMPLES);
+        let mut equalizer: Option<Equalizer> = None;
 
-        while current_index < total_files {
-            if current_index > 0 {
-                self.play_silence(1).await?;
+        loop {
+            // Shuffle may have been switched while the last file played
+            order.set_shuffle(self.context.mode().shuffle);
+            let Some(current_index) = order.current() else {
+                break;
+            };
+            position.index = current_index;
+            position.sample = start_sample;
+            let start = core::mem::take(&mut start_sample);
+
+            // The order may have changed since the next track was opened
+            let pre_opened = next
+                .take()
+                .filter(|track: &Track<_>| track.index == current_index);
+
+            if !first
+                && let Transition::Gap(ms) = self.transition
+                && ms > 0
+            {
+                self.send_packet(AudioPacket::Gap(ms as u32 * 441 / 10))
+                    .await?;
             }
+            first = false;
 
             self.context.status.update_file(current_index);
-            debug!(
-                "Playback: opening file {} (index {})",
-                files[current_index].name(),
-                current_index
-            );
-
-            let file_handle = match files[current_index].data_reader(&fs_guard).await {
-                Ok(fh) => fh,
-                Err(_) => {
-                    warn!("Playback: could not read file at index {}", current_index);
-                    current_index += 1;
-                    continue;
+            let mut track = match pre_opened {
+                Some(track) => track,
+                None => {
+                    debug!(
+                        "Playback: opening file {} (index {})",
+                        files[current_index].name(),
+                        current_index
+                    );
+                    match open_track(&files, current_index, fs_guard, start).await {
+                        Ok(track) => track,
+                        Err(_) => {
+                            warn!("Playback: could not read file at index {}", current_index);
+                            // Repeating all of a playlist without a readable file would never end
+                            failures += 1;
+                            if failures >= files.len() {
+                                self.play_sound(fs_guard, SystemSound::Error, 1).await?;
+                                break;
+                            }
+                            order.next_after_error(self.context.mode().repeat.into());
+                            continue;
+                        }
+                    }
                 }
             };
-            let mut decoder = AudioDecoder::new(file_handle);
+            failures = 0;
 
-            let mut total_samples: u64 = 0;
             let mut last_position_update: u32 = 0;
+            let mut last_save = track.sample;
+            position.sample = track.sample;
+            // Set once the next track was opened for this one
+            let mut looked_ahead = false;
+            // Set when the queue no longer holds this track, it fades out and playback moves on
+            let mut current_removed = false;
+
+            'file: loop {
+                let seek = 'play: {
+                    match self
+                        .apply_queue_edits(&mut files, &mut order, fs_guard, position)
+                        .await
+                    {
+                        QueueChange::Unchanged => {}
+                        change => {
+                            // Indices may have shifted, the next track gets opened again
+                            next = None;
+                            looked_ahead = false;
+                            current_removed |= change == QueueChange::RemovedCurrent;
+                        }
+                    }
+                    if let Some(request) = self.context.fs_request.try_take() {
+                        self.context.fs_reply.signal(request.run(fs_guard).await);
+                    }
+                    if current_removed && ramp.target() == 0 && ramp.is_settled() {
+                        break 'file;
+                    }
 
-            loop {
-                self.handle_pause().await?;
+                    // Faded out already, after seeking while paused. Wait without another buffer.
+                    if ramp.target() == 0
+                        && ramp.is_settled()
+                        && self.context.desired_state.try_get() == Some(State::Paused)
+                    {
+                        match self.handle_pause().await? {
+                            Some(seek) => break 'play seek,
+                            // Woken up by a queue edit or file system request, which get handled
+                            // before waiting again
+                            None if self.context.desired_state.try_get() == Some(State::Paused) => {
+                                continue 'file;
+                            }
+                            None => {}
+                        }
+                    }
 
-                let mut buf = AudioBuffer::alloc();
+                    if !looked_ahead && track.remaining() <= LOOKAHEAD_SAMPLES.max(fade_samples) {
+                        looked_ahead = true;
+                        if let Some(index) = order.peek_after_end(self.context.mode().repeat.into())
+                        {
+                            debug!("Playback: opening next file at index {}", index);
+                            next = open_track(&files, index, fs_guard, 0).await.ok();
+                        }
+                    }
 
-                let n = match select3(
-                    decoder.next_samples(&mut buf.samples),
-                    self.context.skip_signal.wait(),
-                    self.context.wait_for_desired_state(State::Stopped),
-                )
-                .await
-                {
-                    Either3::First(Ok(n)) => n,
-                    Either3::First(Err(_)) => {
-                        warn!("Playback: file read error");
-                        0
+                    let mut buf = AudioBuffer::alloc();
+                    let n = match track.decoder.next_samples(&mut buf.samples).await {
+                        Ok(n) => n,
+                        Err(_) => {
+                            warn!("Playback: file read error");
+                            0
+                        }
+                    };
+
+                    if n == 0 {
+                        debug!("Playback: file {} done, moving to next", current_index);
+                        // Removing the file from the queue already moved the order on
+                        if !current_removed {
+                            order.next_after_end(self.context.mode().repeat.into());
+                        }
+                        break 'file;
                     }
-                    Either3::Second(skip) => {
-                        debug!("Playback: skip {:?} during decode", skip);
-                        self.context.skip_signal.reset();
-                        handle_skip(skip, &mut current_index, total_files);
-                        break;
+
+                    let remaining = track.remaining();
+                    track.sample += n as u64;
+                    buf.len = n;
+
+                    // Crossfades mix the start of the next track into the end of this one
+                    if fade_samples > 0
+                        && remaining <= fade_samples
+                        && let Some(next_track) = next.as_mut()
+                    {
+                        let mut next_buf = AudioBuffer::alloc();
+                        let m = next_track
+                            .decoder
+                            .next_samples(&mut next_buf.samples)
+                            .await
+                            .unwrap_or(0);
+                        next_track.sample += m as u64;
+                        buf.len = crossfade(
+                            &mut buf.samples,
+                            n,
+                            &next_buf.samples[..m],
+                            remaining,
+                            fade_samples,
+                        );
+                    }
+
+                    // The config may have changed the equalizer while playing
+                    let eq = self.context.eq.lock(|cell| cell.get());
+                    if equalizer
+                        .as_ref()
+                        .map(|equalizer| EqConfig::from(*equalizer.settings()))
+                        != eq
+                    {
+                        equalizer = eq.map(|eq| Equalizer::new(eq.into(), 44100.0));
                     }
-                    Either3::Third(_) => {
-                        debug!("Playback: stopped during decode");
-                        return Err(SendInterrupted);
+                    let volume = self.context.volume.load(Ordering::SeqCst);
+                    let mut gain = volume::gain(volume);
+                    if let Some(equalizer) = &mut equalizer {
+                        equalizer.set_volume_attenuation(volume::attenuation_db(volume));
+                        equalizer.process(&mut buf.samples[..buf.len]);
+                        gain = equalizer.output_gain(gain);
                     }
-                };
 
-                if n == 0 {
-                    debug!("Playback: file {} done, moving to next", current_index);
-                    current_index += 1;
-                    break;
-                }
+                    // Requests take effect after this buffer faded out, so they do not click
+                    let interruption = if current_removed {
+                        Some(Interruption::RemovedCurrent)
+                    } else {
+                        self.pending_interruption()
+                    };
+                    if interruption.is_some() {
+                        ramp.ramp_to(0, (buf.len as u32).min(RAMP_SAMPLES));
+                    } else {
+                        ramp.set_target(gain * self.context.sleep_fade_gain() / 256);
+                    }
+                    ramp.apply(&mut buf.samples[..buf.len]);
 
-                buf.len = n;
+                    let seconds = (track.sample / 44100) as u32;
+                    if seconds != last_position_update {
+                        self.context.status.update_position(seconds);
+                        last_position_update = seconds;
+                    }
 
-                let vol = self.context.volume.load(Ordering::SeqCst);
-                for s in buf.samples[..n].iter_mut() {
-                    *s = (*s as i32 * vol as i32 / 16) as i16;
-                }
+                    // Not interrupted by a stop, the fade-out has to reach the output
+                    self.sender.send(AudioPacket::Buffer(buf)).await;
+                    // The buffer is out, resuming continues with the next one
+                    position.sample = track.sample;
+                    if track.sample - last_save >= RESUME_SAVE_INTERVAL {
+                        self.save_resume_point(fs_guard, position).await;
+                        self.save_volume(fs_guard).await;
+                        last_save = track.sample;
+                    }
 
-                total_samples += n as u64;
-                let position = (total_samples / 44100) as u32;
-                if position != last_position_update {
-                    self.context.status.update_position(position);
-                    last_position_update = position;
-                }
+                    match interruption {
+                        None => continue 'file,
+                        Some(Interruption::Stop) => {
+                            debug!("Playback: stopped");
+                            return Err(SendInterrupted);
+                        }
+                        Some(Interruption::Skip(skip)) => {
+                            debug!("Playback: skip {:?}", skip);
+                            next = None;
+                            self.skip(skip, &mut order, fs_guard, position).await;
+                            break 'file;
+                        }
+                        Some(Interruption::Sound(sound, count)) => {
+                            self.play_sound(fs_guard, sound, count).await?;
+                            continue 'file;
+                        }
+                        Some(Interruption::RemovedCurrent) => {
+                            debug!("Playback: file {} removed from the queue", current_index);
+                            break 'file;
+                        }
+                        Some(Interruption::Pause) => {
+                            self.save_resume_point(fs_guard, position).await;
+                            self.save_volume(fs_guard).await;
+                            match self.handle_pause().await? {
+                                Some(seek) => seek,
+                                None => continue 'file,
+                            }
+                        }
+                        Some(Interruption::Seek(seek)) => seek,
+                    }
+                };
 
-                match select3(
-                    self.sender.send(AudioPacket::Buffer(buf)),
-                    self.context.skip_signal.wait(),
-                    self.context.wait_for_desired_state(State::Stopped),
-                )
-                .await
-                {
-                    Either3::First(_) => {}
-                    Either3::Second(skip) => {
-                        debug!("Playback: skip {:?} during send", skip);
-                        self.context.skip_signal.reset();
-                        handle_skip(skip, &mut current_index, total_files);
-                        break;
+                // ADPCM blocks decode independently, so seeking reopens the file at the
+                // block holding the target. Seeking past the end moves on to the next file.
+                let target = seek.target_sample(track.sample);
+                debug!("Playback: seek {:?} to sample {}", seek, target);
+                match open_track(&files, current_index, fs_guard, target).await {
+                    Ok(reopened) => {
+                        track = reopened;
+                        // The next track gets opened again once the end is near
+                        next = None;
+                        looked_ahead = false;
+                        position.sample = track.sample;
+                        last_position_update = (track.sample / 44100) as u32;
+                        self.context.status.update_position(last_position_update);
                     }
-                    Either3::Third(_) => {
-                        debug!("Playback: stopped during send");
-                        return Err(SendInterrupted);
+                    Err(_) => {
+                        warn!(
+                            "Playback: could not seek in file at index {}",
+                            current_index
+                        );
                     }
                 }
             }
@@ -499,23 +1685,75 @@ impl PlaybackStream<'_> {
     }
 }
 
+/// A file of the playlist being decoded
+struct Track<R> {
+    index: usize,
+    decoder: AudioDecoder<R>,
+    /// Sample the decoder continues with
+    sample: u64,
+    /// Samples in the file
+    length: u64,
+}
+
+impl<R> Track<R> {
+    fn remaining(&self) -> u64 {
+        self.length.saturating_sub(self.sample)
+    }
+}
+
+/// Opens the file at `index` at the ADPCM block holding `start_sample`
+async fn open_track<'a>(
+    files: &[AudioFile],
+    index: usize,
+    fs: &'a SdFileSystem,
+    start_sample: u64,
+) -> Result<Track<impl Read<Error = impl defmt::Format> + use<'a>>, ()> {
+    let file = &files[index];
+    let length = file.sample_count(fs).await?;
+    let (reader, sample) = file.data_reader(fs, start_sample).await?;
+    Ok(Track {
+        index,
+        decoder: AudioDecoder::new(reader),
+        sample,
+        length,
+    })
+}
+
+/// Mixes the start of the next track into the current one, whose buffer of `len` samples in
+/// `out` starts `remaining` samples before its end. The current track fades out linearly over
+/// the last `fade` samples while the next fades in. Returns the length of the mixed buffer.
+fn crossfade(out: &mut [i16], len: usize, next: &[i16], remaining: u64, fade: u64) -> usize {
+    let mixed = len.max(next.len());
+    for (i, sample) in out[..mixed].iter_mut().enumerate() {
+        let current = if i < len { *sample as i32 } else { 0 };
+        let incoming = next.get(i).copied().unwrap_or(0) as i32;
+        let weight = (remaining.saturating_sub(i as u64).min(fade) * 32768 / fade) as i32;
+        *sample = ((current * weight + incoming * (32768 - weight)) >> 15) as i16;
+    }
+    mixed
+}
+
 async fn playlist_with_metadata_from_playlist(
     playlist: &Playlist,
     fs: &SdFsWrapper,
 ) -> PlaylistWithMetadata {
-    let mut files_with_metadata = Vec::new();
     let fs_guard = fs.borrow_mut().await;
-    for file in &playlist.files {
-        let metadata = file.metadata(&fs_guard).await.unwrap_or_default();
+    PlaylistWithMetadata {
+        playlist_name: playlist.name.clone(),
+        files: files_with_metadata(&playlist.files, &fs_guard).await,
+    }
+}
+
+async fn files_with_metadata(files: &[AudioFile], fs: &SdFileSystem) -> Vec<AudioFileWithMetadata> {
+    let mut files_with_metadata = Vec::new();
+    for file in files {
+        let metadata = file.metadata(fs).await.unwrap_or_default();
         files_with_metadata.push(AudioFileWithMetadata {
             file: file.clone(),
             metadata,
         });
     }
-    PlaylistWithMetadata {
-        playlist_name: playlist.name.clone(),
-        files: files_with_metadata,
-    }
+    files_with_metadata
 }
 
 // ---- PlaybackHandle ----
@@ -556,6 +1794,17 @@ impl PlaybackHandle {
             .await;
     }
 
+    /// Plays the playlist of a fob put on the reader, see [`PlayerCommand::FobScanned`]
+    pub async fn fob_scanned(&self, playlist_ref: PlayListRef) {
+        self.sender
+            .send(PlayerCommand::FobScanned(playlist_ref))
+            .await;
+    }
+
+    pub async fn edit_queue(&self, edit: QueueEdit) {
+        self.sender.send(PlayerCommand::Queue(edit)).await;
+    }
+
     pub async fn set_volume(&self, volume: u8) {
         self.sender.send(PlayerCommand::SetVolume(volume)).await;
     }
@@ -576,10 +1825,94 @@ impl PlaybackHandle {
         self.sender.send(PlayerCommand::Skip(Skip::Previous)).await;
     }
 
+    pub async fn seek_to(&self, seconds: u32) {
+        self.sender
+            .send(PlayerCommand::Seek(Seek::To(seconds)))
+            .await;
+    }
+
+    pub async fn seek_by(&self, seconds: i32) {
+        self.sender
+            .send(PlayerCommand::Seek(Seek::By(seconds)))
+            .await;
+    }
+
+    pub async fn set_repeat(&self, repeat: Repeat) {
+        self.sender.send(PlayerCommand::SetRepeat(repeat)).await;
+    }
+
+    pub async fn set_shuffle(&self, shuffle: bool) {
+        self.sender.send(PlayerCommand::SetShuffle(shuffle)).await;
+    }
+
+    /// Fades out and stops playback after `minutes`, 0 cancels the timer
+    pub async fn set_sleep_timer(&self, minutes: u16) {
+        self.sender.send(PlayerCommand::SleepTimer(minutes)).await;
+    }
+
+    /// Plays `sound` `count` times, see [`PlayerCommand::Sound`]
+    pub async fn play_sound(&self, sound: SystemSound, count: u8) {
+        self.sender
+            .send(PlayerCommand::Sound { sound, count })
+            .await;
+    }
+
+    /// Whether the sleep timer stopped playback and nothing was played since
+    pub fn sleep_timer_elapsed(&self) -> bool {
+        self.context.sleep_timer_elapsed.load(Ordering::SeqCst)
+    }
+
+    pub async fn set_max_volume(&self, max_volume: u8) {
+        self.sender
+            .send(PlayerCommand::SetMaxVolume(max_volume))
+            .await;
+    }
+
     pub fn get_volume(&self) -> u8 {
         self.context.volume.load(Ordering::SeqCst)
     }
 
+    pub async fn set_eq(&self, eq: Option<EqConfig>) {
+        self.sender.send(PlayerCommand::SetEq(eq)).await;
+    }
+
+    pub fn get_max_volume(&self) -> u8 {
+        self.context.max_volume.load(Ordering::SeqCst)
+    }
+
+    pub async fn set_unknown_fob(&self, action: UnknownFobAction) {
+        self.sender.send(PlayerCommand::SetUnknownFob(action)).await;
+    }
+
+    pub async fn set_tag_presence(&self, tag_presence: Option<TagPresenceConfig>) {
+        self.sender
+            .send(PlayerCommand::SetTagPresence(tag_presence))
+            .await;
+    }
+
+    pub async fn set_rescan(&self, rescan: RescanConfig) {
+        self.sender.send(PlayerCommand::SetRescan(rescan)).await;
+    }
+
+    /// Whether removing the fob pauses its playlist, see [`TagPresenceConfig`]
+    pub fn tag_presence(&self) -> bool {
+        self.context.tag_presence.lock(|cell| cell.get()).is_some()
+    }
+
+    pub async fn tag_left(&self, fob: String<8>) {
+        self.sender.send(PlayerCommand::TagLeft(fob)).await;
+    }
+
+    /// Fob waiting for a playlist in learn mode, see [`UnknownFobAction::Learn`]
+    pub fn learning_fob(&self) -> Option<String<8>> {
+        self.context.learning_fob()
+    }
+
+    /// Saves the playlist playing to the fob in learn mode
+    pub async fn confirm_learning(&self) {
+        self.sender.send(PlayerCommand::ConfirmLearning).await;
+    }
+
     pub fn status(&self) -> &Status {
         self.context.status
     }
@@ -1,10 +1,12 @@
 use alloc::vec::Vec;
 use embassy_sync::watch::Watch;
 use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, watch::Receiver};
+use embassy_time::Instant;
 use heapless::String;
 use serde::Serialize;
 
 use crate::entities::audio_file::{AudioFile, AudioMetadata};
+use crate::entities::playlist::{PlaylistMode, Repeat};
 
 pub struct Status {
     playback_position: Watch<CriticalSectionRawMutex, u32, 1>,
@@ -19,6 +21,38 @@ pub struct PlaybackStatus {
     pub index_in_playlist: usize,
     pub file_name: Option<String<8>>,
     pub playlist_name: Option<String<8>>,
+    pub mode: PlaybackMode,
+    /// When the sleep timer elapses and playback starts fading out
+    #[serde(skip)]
+    pub sleep_deadline: Option<Instant>,
+}
+
+impl PlaybackStatus {
+    /// Seconds until the sleep timer elapses, zero while fading out
+    pub fn sleep_timer_remaining(&self) -> Option<u32> {
+        self.sleep_deadline.map(|deadline| {
+            deadline
+                .checked_duration_since(Instant::now())
+                .map(|remaining| remaining.as_secs() as u32)
+                .unwrap_or(0)
+        })
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Serialize, defmt::Format)]
+pub struct PlaybackMode {
+    pub repeat: Repeat,
+    pub shuffle: bool,
+}
+
+impl PlaybackMode {
+    /// This mode with the settings of a playlist applied
+    pub fn with_playlist_mode(self, playlist_mode: &PlaylistMode) -> Self {
+        Self {
+            repeat: playlist_mode.repeat.unwrap_or(self.repeat),
+            shuffle: playlist_mode.shuffle.unwrap_or(self.shuffle),
+        }
+    }
 }
 
 #[derive(Clone, Copy, Serialize, PartialEq)]
@@ -102,12 +136,19 @@ impl Status {
                 status.state = state;
             }),
             State::Stopped => {
+                let (mode, sleep_deadline) = self
+                    .playback_status
+                    .try_get()
+                    .map(|status| (status.mode, status.sleep_deadline))
+                    .unwrap_or_default();
                 self.playback_status.sender().send(PlaybackStatus {
                     state: State::Stopped,
                     metadata: None,
                     index_in_playlist: 0,
                     file_name: None,
                     playlist_name: None,
+                    mode,
+                    sleep_deadline,
                 });
                 self.update_playlist(None);
                 self.update_position(0);
@@ -141,6 +182,25 @@ impl Status {
         self.update_position(0);
     }
 
+    /// The playlist was edited, the file playing moved to `index_in_playlist`
+    pub fn update_index(&self, index_in_playlist: usize) {
+        self.update_playback_status(|status| {
+            status.index_in_playlist = index_in_playlist;
+        });
+    }
+
+    pub fn update_mode(&self, mode: PlaybackMode) {
+        self.update_playback_status(|status| {
+            status.mode = mode;
+        });
+    }
+
+    pub fn update_sleep_deadline(&self, sleep_deadline: Option<Instant>) {
+        self.update_playback_status(|status| {
+            status.sleep_deadline = sleep_deadline;
+        });
+    }
+
     pub fn update_position(&self, position_seconds: u32) {
         self.playback_position.sender().send(position_seconds);
     }
@@ -13,9 +13,11 @@ use esp_hal::rtc_cntl::Rtc;
 use esp_radio::wifi::{WifiAccessPointState, WifiStationState};
 
 use crate::DeviceConfig;
+use crate::controllers::playback::PlaybackHandle;
 use crate::drivers::charger::ChargerMonitor;
 use crate::drivers::indicator::IndicatorLedHandle;
 use crate::drivers::radio::RadioHandle;
+use crate::entities::system_sound::SystemSound;
 
 pub enum WifiCommand {
     WifiOff,
@@ -73,6 +75,7 @@ impl WifiManager {
         config: Option<DeviceConfig>,
         charger_monitor: ChargerMonitor,
         rtc: &'static RefCell<Rtc<'static>>,
+        player: PlaybackHandle,
     ) -> WifiManagerHandle {
         spawner.must_spawn(wifi_manager_task(
             radio_handle,
@@ -80,6 +83,7 @@ impl WifiManager {
             config,
             charger_monitor,
             rtc,
+            player,
             self.cmd_channel,
             self.stack_signal,
             self.wifi_is_active,
@@ -105,6 +109,7 @@ async fn wifi_manager_task(
     config: Option<DeviceConfig>,
     mut charger_monitor: ChargerMonitor,
     rtc: &'static RefCell<Rtc<'static>>,
+    player: PlaybackHandle,
     cmd_channel: &'static Channel<NoopRawMutex, WifiCommand, 1>,
     stack_signal: &'static Signal<NoopRawMutex, Stack<'static>>,
     wifi_is_active: &'static AtomicBool,
@@ -186,10 +191,12 @@ async fn wifi_manager_task(
             (true, false) => {
                 info!("WifiManager: charger connected — starting WiFi");
                 radio_handle.set_wifi_enabled(true).await;
+                player.play_sound(SystemSound::WifiOn, 1).await;
             }
             (false, true) => {
                 info!("WifiManager: charger disconnected — stopping WiFi");
                 radio_handle.set_wifi_enabled(false).await;
+                player.play_sound(SystemSound::WifiOff, 1).await;
             }
             (false, false) => {
                 info!("WifiManager: off — waiting for charger or command");
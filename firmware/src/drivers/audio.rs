@@ -36,7 +36,10 @@ impl AudioBuffer {
 
 pub enum AudioPacket {
     Buffer(Box<AudioBuffer>),
+    /// Samples of silence, the amplifier goes off once the audio before has played
     Silence(u32),
+    /// Samples of silence between tracks, the amplifier stays on so it does not pop
+    Gap(u32),
     Eof,
 }
 
@@ -45,6 +48,8 @@ pub type AudioSender = EmbSender<'static, NoopRawMutex, AudioPacket, 1>;
 // ---- I2S / Player ----
 
 pub(crate) const DMA_SIZE: usize = 6 * 4096;
+/// Samples the circular DMA buffer holds, each a 16 bit sample on two channels
+const DMA_SAMPLES: u32 = (DMA_SIZE / 4) as u32;
 const DMA_CHUNKS: usize = 7;
 
 pub(crate) struct I2sInner {
@@ -183,6 +188,9 @@ async fn i2s_output_task(
     status.update_state(State::Playing);
 
     debug!("Player: I2S output task started");
+    // Silence pushed since the last audio. The amplifier only goes off once the DMA buffer
+    // holds nothing else, so a fade-out is still heard in full.
+    let mut silent_samples: u32 = 0;
     loop {
         match rx.receive().await {
             AudioPacket::Eof => {
@@ -193,6 +201,7 @@ async fn i2s_output_task(
             AudioPacket::Buffer(buf) => {
                 debug!("Player: playing buffer len={}", buf.len);
                 player.borrow_mut().set_amp(true);
+                silent_samples = 0;
 
                 let n = buf.len.min(BUF_SAMPLES);
                 let mut written = 0;
@@ -219,25 +228,43 @@ async fn i2s_output_task(
 
             AudioPacket::Silence(samples) => {
                 debug!("Player: silence samples={}", samples);
-                player.borrow_mut().set_amp(false);
-
-                let mut remaining = samples as usize;
-                while remaining > 0 {
-                    transfer
-                        .push_with(|out: &mut [u8]| {
-                            let count = remaining.min(out.len() / 4);
-                            out[..count * 4].fill(0);
-                            remaining -= count;
-                            count * 4
-                        })
-                        .await
-                        .print_err("Player: I2S DMA transfer");
+                push_silence(&mut transfer, samples).await;
+                silent_samples = silent_samples.saturating_add(samples);
+                if silent_samples >= DMA_SAMPLES {
+                    player.borrow_mut().set_amp(false);
                 }
             }
+
+            AudioPacket::Gap(samples) => {
+                debug!("Player: gap samples={}", samples);
+                push_silence(&mut transfer, samples).await;
+            }
         }
     }
 
+    // Let the DMA buffer play out before the transfer ends with the task
+    if silent_samples < DMA_SAMPLES {
+        push_silence(&mut transfer, DMA_SAMPLES).await;
+    }
     player.borrow_mut().set_amp(false);
 
     status.update_state(State::Stopped);
 }
+
+async fn push_silence(
+    transfer: &mut I2sWriteDmaTransferAsync<'static, &'static mut [u8; DMA_SIZE]>,
+    samples: u32,
+) {
+    let mut remaining = samples as usize;
+    while remaining > 0 {
+        transfer
+            .push_with(|out: &mut [u8]| {
+                let count = remaining.min(out.len() / 4);
+                out[..count * 4].fill(0);
+                remaining -= count;
+                count * 4
+            })
+            .await
+            .print_err("Player: I2S DMA transfer");
+    }
+}
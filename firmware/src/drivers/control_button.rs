@@ -46,6 +46,20 @@ impl Button {
     /// For buttons with repeat enabled, returns immediately on press
     /// For other buttons, waits for release to determine short/long press
     pub async fn wait_for_press(&mut self) -> PressType {
+        self.wait_for_down().await;
+        self.press_type().await
+    }
+
+    /// Like `wait_for_press`, but gives up when the button is not pressed within `window`
+    pub async fn wait_for_press_within(&mut self, window: Duration) -> Option<PressType> {
+        embassy_time::with_timeout(window, self.wait_for_down())
+            .await
+            .ok()?;
+        Some(self.press_type().await)
+    }
+
+    /// Waits until the button is released, then until it is pressed
+    async fn wait_for_down(&mut self) {
         self.input
             .wait_for_with_options(
                 Event::HighLevel,
@@ -65,6 +79,10 @@ impl Button {
         self.input
             .wakeup_enable(true, WakeEvent::HighLevel)
             .unwrap();
+    }
+
+    /// Short or long, for a press that just started
+    async fn press_type(&mut self) -> PressType {
         embassy_time::Timer::after(Duration::from_millis(50)).await; // Debounce
 
         if let Some(long_press_threshold) = self.long_press_threshold {
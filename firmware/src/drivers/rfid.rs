@@ -1,5 +1,6 @@
 use alloc::format;
 use alloc::rc::Rc;
+use audio_file_utils::presence::{self, Presence, Reading};
 use defmt::{debug, error, info};
 use embassy_executor::Spawner;
 use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
@@ -10,7 +11,7 @@ use embassy_sync::signal::Signal;
 use embassy_time::{Duration, Timer};
 use esp_hal::gpio::{AnyPin, Input, InputConfig, Level, Output, OutputConfig, Pull};
 use esp_hal::time::Rate;
-use heapless::String;
+use heapless::{String, Vec};
 use mfrc522_async::{EnableError, Mfrc522};
 use unwrap_infallible::UnwrapInfallible;
 
@@ -28,9 +29,42 @@ pub enum RfidScanResult {
     Error,
 }
 
+impl RfidScanResult {
+    fn reading(&self) -> Reading<'_, String<8>> {
+        match self {
+            RfidScanResult::Found(fob) => Reading::Found(fob),
+            RfidScanResult::NotFound => Reading::NotFound,
+            RfidScanResult::Error => Reading::Error,
+        }
+    }
+}
+
+/// Change of the fob on the reader, see `audio_file_utils::presence`
+#[derive(defmt::Format)]
+pub enum TagEvent {
+    Arrived(String<8>),
+    Left(String<8>),
+}
+
+impl From<presence::TagEvent<String<8>>> for TagEvent {
+    fn from(event: presence::TagEvent<String<8>>) -> Self {
+        match event {
+            presence::TagEvent::Arrived(fob) => TagEvent::Arrived(fob),
+            presence::TagEvent::Left(fob) => TagEvent::Left(fob),
+        }
+    }
+}
+
+/// Result of a scan and the changes of the fob on the reader it caused. Swapping fobs gives
+/// the removal of the first one, then the arrival of the other.
+pub struct RfidScan {
+    pub result: RfidScanResult,
+    pub events: Vec<TagEvent, 2>,
+}
+
 pub struct RfidHandleInner {
     scan_trigger: Signal<CriticalSectionRawMutex, ()>,
-    scan_result: Channel<NoopRawMutex, RfidScanResult, 1>,
+    scan_result: Channel<NoopRawMutex, RfidScan, 1>,
     last_fob: Mutex<CriticalSectionRawMutex, Option<String<8>>>,
 }
 
@@ -39,7 +73,7 @@ impl RfidHandleInner {
         self.scan_trigger.signal(());
     }
 
-    pub async fn wait_for_scan_result(&self) -> RfidScanResult {
+    pub async fn wait_for_scan_result(&self) -> RfidScan {
         self.scan_result.receive().await
     }
 
@@ -92,6 +126,7 @@ impl Rfid {
 
 #[embassy_executor::task]
 async fn rfid_task(mut rfid: MyMfrc522, handle: RfidHandle) {
+    let mut presence = Presence::default();
     loop {
         handle.scan_trigger.wait().await;
         handle.scan_trigger.reset();
@@ -166,6 +201,14 @@ async fn rfid_task(mut rfid: MyMfrc522, handle: RfidHandle) {
             }
         };
 
-        handle.scan_result.send(result).await;
+        let events: Vec<TagEvent, 2> = presence
+            .update(result.reading())
+            .into_iter()
+            .map(TagEvent::from)
+            .collect();
+        for event in &events {
+            info!("RFID: {}", event);
+        }
+        handle.scan_result.send(RfidScan { result, events }).await;
     }
 }
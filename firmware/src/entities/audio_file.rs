@@ -8,7 +8,7 @@ use serde::Serialize;
 use crate::drivers::sd::{FileHandle, SdFileSystem};
 use crate::entities::basename;
 use crate::{PrintErr, with_extension};
-use audio_codec_algorithms::{AdpcmImaState, decode_adpcm_ima};
+use audio_file_utils::adpcm::{BLOCK_SIZE, SAMPLES_PER_BLOCK, decode_block};
 use audio_file_utils::metadata::{INFO_CHUNK_SIZE, extract_metadata};
 use embedded_io_async::{Read, Seek, SeekFrom};
 use futures::stream::{self, Stream, StreamExt};
@@ -16,6 +16,9 @@ use futures::stream::{self, Stream, StreamExt};
 const FILE_DIR: &str = "FILES";
 const FILE_EXT: &str = ".WAV";
 
+/// Bytes before the audio data: the WAV header and the LIST chunk of the metadata
+pub const HEADER_SIZE: u64 = 48 + 8 + INFO_CHUNK_SIZE as u64;
+
 #[derive(Clone, Serialize)]
 pub struct AudioMetadata {
     pub artist: heapless::String<31>,
@@ -112,6 +115,21 @@ impl AudioFile {
         Ok(meta.len())
     }
 
+    pub async fn remove(&self, fs: &SdFileSystem) -> Result<(), ()> {
+        let root = fs.root_dir();
+        let fname = with_extension(&self.0, FILE_EXT).unwrap();
+        let dir = root
+            .open_dir(FILE_DIR)
+            .await
+            .print_err("AudioFile: Opening files directory")
+            .ok_or(())?;
+
+        dir.remove(&fname)
+            .await
+            .print_err("AudioFile: Removing file")
+            .ok_or(())
+    }
+
     pub async fn append_at<'a>(
         &self,
         fs: &'a SdFileSystem,
@@ -150,17 +168,38 @@ impl AudioFile {
         }
     }
 
+    /// Number of samples the audio data decodes to
+    pub async fn sample_count(&self, fs: &SdFileSystem) -> Result<u64, ()> {
+        let data_size = self.size(fs).await?.saturating_sub(HEADER_SIZE);
+
+        // The last block may be cut short, its header holds one sample
+        let blocks = data_size / BLOCK_SIZE as u64;
+        let rest = data_size % BLOCK_SIZE as u64;
+        let partial = if rest >= 4 { 1 + (rest - 4) * 2 } else { 0 };
+        Ok(blocks * SAMPLES_PER_BLOCK as u64 + partial)
+    }
+
+    /// Reader for the audio data, starting at the ADPCM block containing `start_sample`.
+    /// Returns the reader and the first sample it decodes to.
     pub async fn data_reader<'a>(
-        &'a self,
+        &self,
         fs: &'a SdFileSystem,
-    ) -> Result<impl embedded_io_async::Read<Error = impl defmt::Format> + use<'a>, ()> {
+        start_sample: u64,
+    ) -> Result<
+        (
+            impl embedded_io_async::Read<Error = impl defmt::Format> + use<'a>,
+            u64,
+        ),
+        (),
+    > {
         let mut file = self.open(fs).await?;
 
-        let list_chunk_size = 8 + INFO_CHUNK_SIZE as u64;
-        let header_size = 48 + list_chunk_size;
+        let block = start_sample / SAMPLES_PER_BLOCK as u64;
 
-        file.seek(SeekFrom::Start(header_size)).await.unwrap();
-        Ok(file)
+        file.seek(SeekFrom::Start(HEADER_SIZE + block * BLOCK_SIZE as u64))
+            .await
+            .unwrap();
+        Ok((file, block * SAMPLES_PER_BLOCK as u64))
     }
 
     pub async fn metadata(&self, fs: &SdFileSystem) -> Result<AudioMetadata, ()> {
@@ -186,9 +225,7 @@ impl AudioFile {
             .ok_or(())?;
         let audio_metadata = extract_metadata(&mut file).await.unwrap_or_default();
 
-        let list_chunk_size = 8 + INFO_CHUNK_SIZE as u64;
-        let header_size = 48 + list_chunk_size;
-        let data_size = file_size - header_size;
+        let data_size = file_size - HEADER_SIZE;
 
         // Assume fixed format: 44100 Hz, mono, IMA ADPCM (4 bits/sample, 2 samples/byte)
         let duration = (data_size / 22050) as u32;
@@ -252,12 +289,12 @@ impl<R: Read> AudioDecoder<R> {
     }
 
     /// Read the next ADPCM block from the reader and decode to mono i16 samples.
-    /// `out` should have capacity for at least 2041 samples.
-    /// Returns the number of samples written (0 = EOF, max 2041).
+    /// `out` should have capacity for at least `SAMPLES_PER_BLOCK` samples.
+    /// Returns the number of samples written (0 = EOF, max `SAMPLES_PER_BLOCK`).
     pub async fn next_samples(&mut self, out: &mut [i16]) -> Result<usize, ()> {
-        let mut raw = [0u8; 1024];
+        let mut raw = [0u8; BLOCK_SIZE];
         let mut offset = 0;
-        while offset < 1024 {
+        while offset < BLOCK_SIZE {
             match crate::retry(async || self.reader.read(&mut raw[offset..]).await, 2).await {
                 Ok(0) => break,
                 Ok(n) => offset += n,
@@ -265,34 +302,6 @@ impl<R: Read> AudioDecoder<R> {
             }
         }
 
-        if offset < 4 {
-            return Ok(0);
-        }
-
-        let mut state = AdpcmImaState::new();
-        state.predictor = i16::from_le_bytes([raw[0], raw[1]]);
-        state.step_index = raw[2].min(88);
-
-        let max = out.len();
-        let mut count = 0;
-        if count < max {
-            out[count] = state.predictor;
-            count += 1;
-        }
-
-        for b in &raw[4..offset] {
-            if count >= max {
-                break;
-            }
-            out[count] = decode_adpcm_ima(*b & 0x0f, &mut state);
-            count += 1;
-            if count >= max {
-                break;
-            }
-            out[count] = decode_adpcm_ima(*b >> 4, &mut state);
-            count += 1;
-        }
-
-        Ok(count)
+        Ok(decode_block(&raw[..offset], out))
     }
 }
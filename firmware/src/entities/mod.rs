@@ -1,5 +1,8 @@
 pub mod audio_file;
 pub mod playlist;
+pub mod resume;
+pub mod system_sound;
+pub mod volume;
 
 fn basename(fname: &[u8], ext: &str) -> Option<heapless::String<8>> {
     let fname = str::from_utf8(fname).ok()?;
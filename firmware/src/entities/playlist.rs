@@ -4,18 +4,103 @@ use super::audio_file::AudioFile;
 use crate::drivers::sd::SdFileSystem;
 use crate::entities::basename;
 use crate::{PrintErr, with_extension};
-use alloc::{boxed::Box, string::ToString, vec::Vec};
+use alloc::{boxed::Box, format, string::ToString, vec::Vec};
+use audio_file_utils::order;
 use defmt::error;
 
 use embedded_io_async::{Read, Write};
 use futures::StreamExt;
 use futures::{stream, stream::Stream};
 use heapless::String;
+use serde::{Deserialize, Serialize};
 
 const PLAYLIST_DIR: &str = "FOBS";
 const PLAYLIST_EXT: &str = ".M3U";
+const REPEAT_DIRECTIVE: &str = "#EXT-X-REPEAT:";
+const SHUFFLE_DIRECTIVE: &str = "#EXT-X-SHUFFLE:";
+const TRANSITION_DIRECTIVE: &str = "#EXT-X-TRANSITION:";
+/// Longest gap or crossfade a playlist can ask for
+const MAX_TRANSITION_MS: u16 = 10_000;
 
-#[derive(defmt::Format)]
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize, defmt::Format)]
+#[serde(rename_all = "lowercase")]
+pub enum Repeat {
+    #[default]
+    Off,
+    One,
+    All,
+}
+
+impl Repeat {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "OFF" => Some(Repeat::Off),
+            "ONE" => Some(Repeat::One),
+            "ALL" => Some(Repeat::All),
+            _ => None,
+        }
+    }
+
+    fn directive_value(self) -> &'static str {
+        match self {
+            Repeat::Off => "OFF",
+            Repeat::One => "ONE",
+            Repeat::All => "ALL",
+        }
+    }
+}
+
+impl From<Repeat> for order::Repeat {
+    fn from(repeat: Repeat) -> Self {
+        match repeat {
+            Repeat::Off => order::Repeat::Off,
+            Repeat::One => order::Repeat::One,
+            Repeat::All => order::Repeat::All,
+        }
+    }
+}
+
+/// What plays between two tracks of a playlist
+#[derive(Clone, Copy, PartialEq, defmt::Format)]
+pub enum Transition {
+    /// Silence of this many milliseconds, 0 plays the tracks gapless
+    Gap(u16),
+    /// The end of a track fades into the start of the next over this many milliseconds
+    Crossfade(u16),
+}
+
+impl Default for Transition {
+    fn default() -> Self {
+        Transition::Gap(1000)
+    }
+}
+
+impl Transition {
+    fn parse(value: &str) -> Option<Self> {
+        if value == "GAPLESS" {
+            return Some(Transition::Gap(0));
+        }
+        let (kind, ms) = value.split_once('=')?;
+        let ms = ms.trim().parse::<u16>().ok()?.min(MAX_TRANSITION_MS);
+        match kind.trim() {
+            "GAP" => Some(Transition::Gap(ms)),
+            "CROSSFADE" => Some(Transition::Crossfade(ms)),
+            _ => None,
+        }
+    }
+}
+
+/// Playback mode set by a playlist with `#EXT-X-REPEAT:OFF|ONE|ALL`,
+/// `#EXT-X-SHUFFLE:ON|OFF` and `#EXT-X-TRANSITION:GAPLESS|GAP=<ms>|CROSSFADE=<ms>`, unset
+/// fields use the global mode
+#[derive(Clone, Copy, Default, PartialEq, defmt::Format)]
+pub struct PlaylistMode {
+    pub repeat: Option<Repeat>,
+    pub shuffle: Option<bool>,
+    pub transition: Option<Transition>,
+}
+
+#[derive(Clone, defmt::Format)]
 pub struct PlayListRef(String<8>);
 
 impl PlayListRef {
@@ -27,6 +112,15 @@ impl PlayListRef {
         &self.0
     }
 
+    /// Whether the fob has a playlist
+    pub async fn exists(&self, fs: &SdFileSystem) -> bool {
+        let fname = with_extension(&self.0, PLAYLIST_EXT).unwrap();
+        let Ok(dir) = fs.root_dir().open_dir(PLAYLIST_DIR).await else {
+            return false;
+        };
+        dir.open_meta(&fname).await.is_ok_and(|meta| meta.is_file())
+    }
+
     pub async fn read(self, fs: &SdFileSystem) -> Result<Playlist, ()> {
         let root = fs.root_dir();
         let dir = root
@@ -61,8 +155,25 @@ impl PlayListRef {
             .print_err("Playlist: Invalid UTF-8 in playlist")
             .ok_or(())?;
         let mut files = Vec::new();
+        let mut mode = PlaylistMode::default();
         for line in content.lines() {
             let line = line.trim();
+            if let Some(value) = line.strip_prefix(REPEAT_DIRECTIVE) {
+                mode.repeat = Repeat::parse(value.trim());
+                continue;
+            }
+            if let Some(value) = line.strip_prefix(SHUFFLE_DIRECTIVE) {
+                mode.shuffle = match value.trim() {
+                    "ON" => Some(true),
+                    "OFF" => Some(false),
+                    _ => None,
+                };
+                continue;
+            }
+            if let Some(value) = line.strip_prefix(TRANSITION_DIRECTIVE) {
+                mode.transition = Transition::parse(value.trim());
+                continue;
+            }
             if line.starts_with('#') || line.is_empty() {
                 continue;
             }
@@ -71,25 +182,39 @@ impl PlayListRef {
             }
         }
 
-        Ok(Playlist::new(self.0, files))
+        Ok(Playlist {
+            name: self.0,
+            files,
+            mode,
+        })
     }
 }
 
 pub struct Playlist {
     pub name: String<8>,
     pub files: Vec<AudioFile>,
+    pub mode: PlaylistMode,
 }
 
 impl Playlist {
     pub fn new(name: String<8>, files: Vec<AudioFile>) -> Self {
-        Self { name, files }
+        Self {
+            name,
+            files,
+            mode: PlaylistMode::default(),
+        }
     }
 
     pub fn name(&self) -> &str {
         &self.name
     }
 
-    pub async fn write(fs: &SdFileSystem, name: String<8>, files: &[AudioFile]) -> Result<(), ()> {
+    pub async fn write(
+        fs: &SdFileSystem,
+        name: String<8>,
+        files: &[AudioFile],
+        mode: &PlaylistMode,
+    ) -> Result<(), ()> {
         let root = fs.root_dir();
         let dir = if !root.dir_exists(PLAYLIST_DIR).await.unwrap_or(false) {
             root.create_dir(PLAYLIST_DIR).await.unwrap()
@@ -102,6 +227,31 @@ impl Playlist {
         file.truncate().await.unwrap();
 
         file.write_all(b"#EXTM3U\r\n").await.unwrap();
+        if let Some(repeat) = mode.repeat {
+            file.write_all(REPEAT_DIRECTIVE.as_bytes()).await.unwrap();
+            file.write_all(repeat.directive_value().as_bytes())
+                .await
+                .unwrap();
+            file.write_all(b"\r\n").await.unwrap();
+        }
+        if let Some(shuffle) = mode.shuffle {
+            file.write_all(SHUFFLE_DIRECTIVE.as_bytes()).await.unwrap();
+            let value = if shuffle { "ON" } else { "OFF" };
+            file.write_all(value.as_bytes()).await.unwrap();
+            file.write_all(b"\r\n").await.unwrap();
+        }
+        if let Some(transition) = mode.transition {
+            file.write_all(TRANSITION_DIRECTIVE.as_bytes())
+                .await
+                .unwrap();
+            let value = match transition {
+                Transition::Gap(0) => "GAPLESS".to_string(),
+                Transition::Gap(ms) => format!("GAP={}", ms),
+                Transition::Crossfade(ms) => format!("CROSSFADE={}", ms),
+            };
+            file.write_all(value.as_bytes()).await.unwrap();
+            file.write_all(b"\r\n").await.unwrap();
+        }
 
         for file_entry in files {
             let metadata = file_entry.metadata(fs).await.unwrap();
@@ -0,0 +1,131 @@
+use alloc::vec::Vec;
+use defmt::error;
+use embedded_io_async::{Read, Write};
+use serde::{Deserialize, Serialize};
+
+use crate::drivers::sd::SdFileSystem;
+use crate::{PrintErr, with_extension};
+
+const RESUME_DIR: &str = "RESUME";
+const RESUME_EXT: &str = ".RES";
+
+/// What scanning a fob again does
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize, defmt::Format)]
+#[serde(rename_all = "lowercase")]
+pub enum ResumeMode {
+    /// Continue at the last played track and position
+    #[default]
+    Resume,
+    /// Always start at the first track
+    Restart,
+}
+
+/// Where playback of a fob's playlist stopped, stored as `RESUME/<FOB>.RES`
+#[derive(Clone, Copy, Default, Serialize, Deserialize, defmt::Format)]
+pub struct ResumePoint {
+    #[serde(default)]
+    pub mode: ResumeMode,
+    #[serde(default)]
+    pub index: usize,
+    /// Decoded samples into the file at `index`
+    #[serde(default)]
+    pub sample: u64,
+}
+
+impl ResumePoint {
+    /// The resume point of `fob`, or the default if none was saved yet
+    pub async fn read(fs: &SdFileSystem, fob: &str) -> Self {
+        Self::try_read(fs, fob).await.unwrap_or_default()
+    }
+
+    async fn try_read(fs: &SdFileSystem, fob: &str) -> Result<Self, ()> {
+        let root = fs.root_dir();
+        let dir = root.open_dir(RESUME_DIR).await.map_err(|_| ())?;
+        let fname = with_extension(fob, RESUME_EXT).unwrap();
+        let mut file = dir.open_file(&fname).await.map_err(|_| ())?;
+
+        let mut buffer = Vec::new();
+        let mut temp_buf = [0u8; 64];
+        loop {
+            match file.read(&mut temp_buf).await {
+                Ok(0) => break,
+                Ok(n) => buffer.extend_from_slice(&temp_buf[..n]),
+                Err(_) => {
+                    error!("Resume: error reading resume file");
+                    return Err(());
+                }
+            }
+        }
+
+        serde_json::from_slice(&buffer)
+            .map_err(|_| ())
+            .print_err("Resume: Invalid resume file")
+            .ok_or(())
+    }
+
+    pub async fn write(&self, fs: &SdFileSystem, fob: &str) -> Result<(), ()> {
+        let root = fs.root_dir();
+        let dir = if !root.dir_exists(RESUME_DIR).await.unwrap_or(false) {
+            root.create_dir(RESUME_DIR)
+                .await
+                .print_err("Resume: Creating resume directory")
+                .ok_or(())?
+        } else {
+            root.open_dir(RESUME_DIR)
+                .await
+                .print_err("Resume: Opening resume directory")
+                .ok_or(())?
+        };
+
+        let fname = with_extension(fob, RESUME_EXT).unwrap();
+        let mut file = dir
+            .create_file(&fname)
+            .await
+            .print_err("Resume: Creating resume file")
+            .ok_or(())?;
+        file.truncate()
+            .await
+            .print_err("Resume: Truncating resume file")
+            .ok_or(())?;
+
+        let buffer = serde_json::to_vec(self).unwrap();
+        file.write_all(&buffer)
+            .await
+            .print_err("Resume: Writing resume file")
+            .ok_or(())?;
+        file.flush()
+            .await
+            .print_err("Resume: Flushing resume file")
+            .ok_or(())?;
+        // Playback usually ends with the power switch, so make sure the FAT is on the card
+        fs.flush()
+            .await
+            .print_err("Resume: Flushing file system")
+            .ok_or(())
+    }
+
+    /// Forgets the position saved for `fob`, keeping its mode, when the fob gets other files
+    pub async fn reset_position(fs: &SdFileSystem, fob: &str) {
+        let resume_point = Self::read(fs, fob).await;
+        if resume_point.index != 0 || resume_point.sample != 0 {
+            let _ = ResumePoint {
+                mode: resume_point.mode,
+                ..Default::default()
+            }
+            .write(fs, fob)
+            .await;
+        }
+    }
+
+    /// Where to start playing a playlist with `total_files` files
+    pub fn start(&self, total_files: usize) -> Self {
+        if self.mode == ResumeMode::Restart || self.index >= total_files {
+            Self {
+                mode: self.mode,
+                ..Default::default()
+            }
+        } else {
+            *self
+        }
+    }
+}
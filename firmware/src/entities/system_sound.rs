@@ -0,0 +1,185 @@
+use core::str::FromStr;
+
+use embedded_io_async::{Seek, SeekFrom};
+use serde::Serialize;
+
+use crate::drivers::sd::{FileHandle, SdFileSystem};
+use crate::entities::audio_file::HEADER_SIZE;
+use crate::{PrintErr, with_extension};
+
+const SOUND_DIR: &str = "SYSTEM";
+const SOUND_EXT: &str = ".WAV";
+
+/// A tone of a synthesized sound, frequency 0 is a rest
+pub struct Tone {
+    pub frequency: u16,
+    pub duration_ms: u16,
+}
+
+const fn tone(frequency: u16, duration_ms: u16) -> Tone {
+    Tone {
+        frequency,
+        duration_ms,
+    }
+}
+
+/// Event the device gives audible feedback for. A clip in `SYSTEM/` replaces the tones of an
+/// event, clips have the same format as the files in `FILES/`.
+#[derive(Clone, Copy, PartialEq, Serialize, defmt::Format)]
+#[serde(rename_all = "snake_case")]
+pub enum SystemSound {
+    Boot,
+    FobRecognized,
+    UnknownFob,
+    /// The volume is at its maximum and cannot go up
+    VolumeLimit,
+    WifiOn,
+    WifiOff,
+    /// The board has no battery monitor yet, nothing plays this
+    LowBattery,
+    /// Played once per 15 minutes of the timer
+    SleepTimer,
+    SleepTimerOff,
+    Error,
+}
+
+impl SystemSound {
+    pub const ALL: [SystemSound; 10] = [
+        SystemSound::Boot,
+        SystemSound::FobRecognized,
+        SystemSound::UnknownFob,
+        SystemSound::VolumeLimit,
+        SystemSound::WifiOn,
+        SystemSound::WifiOff,
+        SystemSound::LowBattery,
+        SystemSound::SleepTimer,
+        SystemSound::SleepTimerOff,
+        SystemSound::Error,
+    ];
+
+    /// Name in the API
+    pub fn name(self) -> &'static str {
+        match self {
+            SystemSound::Boot => "boot",
+            SystemSound::FobRecognized => "fob_recognized",
+            SystemSound::UnknownFob => "unknown_fob",
+            SystemSound::VolumeLimit => "volume_limit",
+            SystemSound::WifiOn => "wifi_on",
+            SystemSound::WifiOff => "wifi_off",
+            SystemSound::LowBattery => "low_battery",
+            SystemSound::SleepTimer => "sleep_timer",
+            SystemSound::SleepTimerOff => "sleep_timer_off",
+            SystemSound::Error => "error",
+        }
+    }
+
+    /// Name of the clip in `SYSTEM/`, without extension
+    fn file_name(self) -> &'static str {
+        match self {
+            SystemSound::Boot => "BOOT",
+            SystemSound::FobRecognized => "FOB",
+            SystemSound::UnknownFob => "UNKNOWN",
+            SystemSound::VolumeLimit => "VOLMAX",
+            SystemSound::WifiOn => "WIFION",
+            SystemSound::WifiOff => "WIFIOFF",
+            SystemSound::LowBattery => "LOWBATT",
+            SystemSound::SleepTimer => "SLEEP",
+            SystemSound::SleepTimerOff => "SLEEPOFF",
+            SystemSound::Error => "ERROR",
+        }
+    }
+
+    /// Tones played without a clip
+    pub fn tones(self) -> &'static [Tone] {
+        match self {
+            SystemSound::Boot => &[tone(523, 100), tone(659, 100), tone(784, 200)],
+            SystemSound::FobRecognized => &[tone(1000, 100)],
+            SystemSound::UnknownFob => &[tone(440, 150), tone(330, 300)],
+            SystemSound::VolumeLimit => &[tone(2000, 40)],
+            SystemSound::WifiOn => &[tone(660, 100), tone(880, 150)],
+            SystemSound::WifiOff => &[tone(880, 100), tone(660, 150)],
+            SystemSound::LowBattery => &[tone(440, 200), tone(0, 100), tone(440, 200)],
+            SystemSound::SleepTimer => &[tone(1000, 100)],
+            SystemSound::SleepTimerOff => &[tone(1000, 500)],
+            SystemSound::Error => &[tone(220, 200), tone(0, 100), tone(220, 400)],
+        }
+    }
+
+    /// Reader for the audio data of the clip, `Err` without a clip
+    pub async fn data_reader<'a>(
+        self,
+        fs: &'a SdFileSystem,
+    ) -> Result<impl embedded_io_async::Read<Error = impl defmt::Format> + use<'a>, ()> {
+        let fname = with_extension(self.file_name(), SOUND_EXT).unwrap();
+        // Most events have no clip, that is not worth a warning
+        let dir = fs.root_dir().open_dir(SOUND_DIR).await.map_err(|_| ())?;
+        let mut file = dir.open_file(&fname).await.map_err(|_| ())?;
+        file.seek(SeekFrom::Start(HEADER_SIZE))
+            .await
+            .print_err("SystemSound: Seeking to audio data")
+            .ok_or(())?;
+        Ok(file)
+    }
+
+    pub async fn exists(self, fs: &SdFileSystem) -> bool {
+        let fname = with_extension(self.file_name(), SOUND_EXT).unwrap();
+        let Ok(dir) = fs.root_dir().open_dir(SOUND_DIR).await else {
+            return false;
+        };
+        dir.open_meta(&fname).await.is_ok_and(|meta| meta.is_file())
+    }
+
+    /// Creates or truncates the clip
+    pub async fn create<'a>(self, fs: &'a SdFileSystem) -> Result<FileHandle<'a>, ()> {
+        let root = fs.root_dir();
+        let fname = with_extension(self.file_name(), SOUND_EXT).unwrap();
+        let dir = if !root.dir_exists(SOUND_DIR).await.unwrap_or(false) {
+            root.create_dir(SOUND_DIR)
+                .await
+                .print_err("SystemSound: Creating sound directory")
+                .ok_or(())?
+        } else {
+            root.open_dir(SOUND_DIR)
+                .await
+                .print_err("SystemSound: Opening sound directory")
+                .ok_or(())?
+        };
+
+        let mut file = dir
+            .create_file(&fname)
+            .await
+            .print_err("SystemSound: Creating clip")
+            .ok_or(())?;
+        file.truncate()
+            .await
+            .print_err("SystemSound: Truncating clip")
+            .ok_or(())?;
+        Ok(file)
+    }
+
+    /// Removes the clip, the event falls back to its tones
+    pub async fn remove(self, fs: &SdFileSystem) -> Result<(), ()> {
+        let fname = with_extension(self.file_name(), SOUND_EXT).unwrap();
+        let dir = fs
+            .root_dir()
+            .open_dir(SOUND_DIR)
+            .await
+            .print_err("SystemSound: Opening sound directory")
+            .ok_or(())?;
+        dir.remove(&fname)
+            .await
+            .print_err("SystemSound: Removing clip")
+            .ok_or(())
+    }
+}
+
+impl FromStr for SystemSound {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SystemSound::ALL
+            .into_iter()
+            .find(|sound| sound.name() == s)
+            .ok_or(())
+    }
+}
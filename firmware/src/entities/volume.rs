@@ -0,0 +1,85 @@
+use alloc::vec::Vec;
+use embedded_io_async::{Read, Write};
+use serde::{Deserialize, Serialize};
+
+use crate::PrintErr;
+use crate::drivers::sd::SdFileSystem;
+
+const VOLUME_FILE: &str = "VOLUME.JSN";
+
+/// Highest volume step, full scale
+pub const MAX_VOLUME: u8 = 32;
+/// Volume without a saved or configured one, -6 dB
+pub const DEFAULT_VOLUME: u8 = 28;
+
+/// Gain of each volume step in Q15. Steps are 1.5 dB apart, step 0 is muted.
+const GAIN_TABLE: [i32; MAX_VOLUME as usize + 1] = [
+    0, 155, 184, 219, 260, 309, 368, 437, 519, 617, 734, 872, 1036, 1232, 1464, 1740, 2067, 2457,
+    2920, 3471, 4125, 4903, 5827, 6925, 8231, 9782, 11626, 13818, 16422, 19518, 23197, 27570,
+    32767,
+];
+
+/// Gain of volume step `volume` in Q15
+pub fn gain(volume: u8) -> i32 {
+    GAIN_TABLE[volume.min(MAX_VOLUME) as usize]
+}
+
+/// Attenuation of volume step `volume` below full scale in dB
+pub fn attenuation_db(volume: u8) -> f32 {
+    (MAX_VOLUME - volume.min(MAX_VOLUME)) as f32 * 1.5
+}
+
+/// Last volume, stored as `VOLUME.JSN` so it survives a reboot
+#[derive(Clone, Copy, Serialize, Deserialize, defmt::Format)]
+pub struct SavedVolume {
+    pub volume: u8,
+}
+
+impl SavedVolume {
+    /// The saved volume, `None` if none was saved yet
+    pub async fn read(fs: &SdFileSystem) -> Option<Self> {
+        let root = fs.root_dir();
+        let mut file = root.open_file(VOLUME_FILE).await.ok()?;
+
+        let mut buffer = Vec::new();
+        let mut temp_buf = [0u8; 32];
+        loop {
+            match file.read(&mut temp_buf).await {
+                Ok(0) => break,
+                Ok(n) => buffer.extend_from_slice(&temp_buf[..n]),
+                Err(_) => return None,
+            }
+        }
+
+        serde_json::from_slice(&buffer)
+            .map_err(|_| ())
+            .print_err("Volume: Invalid volume file")
+    }
+
+    pub async fn write(&self, fs: &SdFileSystem) -> Result<(), ()> {
+        let root = fs.root_dir();
+        let mut file = root
+            .create_file(VOLUME_FILE)
+            .await
+            .print_err("Volume: Creating volume file")
+            .ok_or(())?;
+        file.truncate()
+            .await
+            .print_err("Volume: Truncating volume file")
+            .ok_or(())?;
+
+        let buffer = serde_json::to_vec(self).unwrap();
+        file.write_all(&buffer)
+            .await
+            .print_err("Volume: Writing volume file")
+            .ok_or(())?;
+        file.flush()
+            .await
+            .print_err("Volume: Flushing volume file")
+            .ok_or(())?;
+        fs.flush()
+            .await
+            .print_err("Volume: Flushing file system")
+            .ok_or(())
+    }
+}
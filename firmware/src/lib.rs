@@ -7,6 +7,7 @@
 use core::future::Future;
 
 use alloc::string::String;
+use audio_file_utils::eq::EqSettings;
 use defmt::{error, warn};
 use serde::{Deserialize, Serialize};
 
@@ -19,6 +20,144 @@ pub struct DeviceConfig {
 
     #[serde(alias = "PASSWORD")]
     pub password: String,
+
+    /// Highest volume step the buttons and the API can set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_volume: Option<u8>,
+
+    /// Volume step after power-on, the last volume is restored if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub startup_volume: Option<u8>,
+
+    /// Equalizer tuned for the enclosure, playback is unfiltered if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eq: Option<EqConfig>,
+
+    /// What scanning a fob without playlist does, `sound` if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unknown_fob: Option<UnknownFobAction>,
+
+    /// Plays fobs only while they are on the reader, fobs start playback only if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag_presence: Option<TagPresenceConfig>,
+
+    /// What scanning the fob playing again does, ignored after a cooldown if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rescan: Option<RescanConfig>,
+}
+
+/// Tag presence mode of `DeviceConfig`: removing the fob pauses its playlist, putting it back
+/// resumes it
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, defmt::Format)]
+#[serde(default)]
+pub struct TagPresenceConfig {
+    /// Seconds after the removal at which the paused playlist stops
+    pub stop_after_seconds: u16,
+}
+
+/// Second swipe settings of `DeviceConfig`
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, defmt::Format)]
+#[serde(default)]
+pub struct RescanConfig {
+    pub action: RescanAction,
+    /// Seconds after a scan of the fob playing in which scanning it again does nothing, so a
+    /// fob that is read again and again does not repeat the action
+    pub cooldown_seconds: u16,
+}
+
+impl Default for RescanConfig {
+    fn default() -> Self {
+        Self {
+            action: RescanAction::default(),
+            cooldown_seconds: 10,
+        }
+    }
+}
+
+/// Reaction to scanning the fob whose playlist is playing. Scanning the fob of a paused
+/// playlist resumes it with `Pause`, otherwise it plays from its resume point like any other
+/// scan.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, defmt::Format)]
+#[serde(rename_all = "lowercase")]
+pub enum RescanAction {
+    /// The playlist goes on
+    #[default]
+    Ignore,
+    /// Pauses, or resumes while paused
+    Pause,
+    /// Skips to the next track
+    Next,
+    /// Starts the playlist over at its first track
+    Restart,
+}
+
+impl Default for TagPresenceConfig {
+    fn default() -> Self {
+        Self {
+            stop_after_seconds: 300,
+        }
+    }
+}
+
+/// Reaction to a fob without playlist, see `DeviceConfig::unknown_fob`
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, defmt::Format)]
+#[serde(rename_all = "lowercase")]
+pub enum UnknownFobAction {
+    /// Nothing happens
+    Ignore,
+    /// Plays the unknown fob sound, playback goes on
+    #[default]
+    Sound,
+    /// Plays the unknown fob sound and waits for a playlist to save to the fob. The next
+    /// playlist started, or the one playing, is saved once play/pause is pressed.
+    Learn,
+}
+
+/// Equalizer settings of `DeviceConfig`, see `audio_file_utils::eq`
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, defmt::Format)]
+#[serde(default)]
+pub struct EqConfig {
+    /// Low shelf gain in dB (-12 to 12), negative values cut the lows
+    pub bass_db: i8,
+    pub bass_hz: u16,
+    /// Presence peak gain in dB (-12 to 12)
+    pub presence_db: i8,
+    pub presence_hz: u16,
+    pub presence_q: f32,
+    /// Boosts the bass at low volume
+    pub loudness: bool,
+}
+
+impl Default for EqConfig {
+    fn default() -> Self {
+        EqSettings::default().into()
+    }
+}
+
+impl From<EqSettings> for EqConfig {
+    fn from(settings: EqSettings) -> Self {
+        Self {
+            bass_db: settings.bass_db,
+            bass_hz: settings.bass_hz,
+            presence_db: settings.presence_db,
+            presence_hz: settings.presence_hz,
+            presence_q: settings.presence_q,
+            loudness: settings.loudness,
+        }
+    }
+}
+
+impl From<EqConfig> for EqSettings {
+    fn from(config: EqConfig) -> Self {
+        Self {
+            bass_db: config.bass_db,
+            bass_hz: config.bass_hz,
+            presence_db: config.presence_db,
+            presence_hz: config.presence_hz,
+            presence_q: config.presence_q,
+            loudness: config.loudness,
+        }
+    }
 }
 
 // When you are okay with using a nightly compiler it's better to use https://docs.rs/static_cell/2.1.0/static_cell/macro.make_static.html
@@ -36,9 +175,15 @@ impl defmt::Format for DeviceConfig {
     fn format(&self, fmt: defmt::Formatter) {
         defmt::write!(
             fmt,
-            "DeviceConfig {{ SSID: {}, PASSWORD: {} }}",
+            "DeviceConfig {{ SSID: {}, PASSWORD: {}, MAX_VOLUME: {}, STARTUP_VOLUME: {}, EQ: {}, UNKNOWN_FOB: {}, TAG_PRESENCE: {}, RESCAN: {} }}",
             self.ssid.as_str(),
             self.password.as_str(),
+            self.max_volume,
+            self.startup_volume,
+            self.eq,
+            self.unknown_fob,
+            self.tag_presence,
+            self.rescan,
         )
     }
 }
@@ -1,19 +1,95 @@
+use alloc::string::String;
+use alloc::vec::Vec;
 use defmt::info;
-use embedded_io_async::Write;
+use embedded_io_async::{Read, Write};
 use picoserve::{
     extract,
-    response::{IntoResponse, Response, StatusCode},
+    response::{IntoResponse, Json, Response, StatusCode},
 };
+use serde::Serialize;
 
-use crate::DeviceConfig;
+use crate::entities::volume::MAX_VOLUME;
 use crate::services::web::AppState;
+use crate::{DeviceConfig, EqConfig, RescanConfig, TagPresenceConfig, UnknownFobAction};
+
+/// Device configuration without secrets
+#[derive(Serialize)]
+pub struct PublicConfig {
+    ssid: String,
+    max_volume: Option<u8>,
+    startup_volume: Option<u8>,
+    eq: Option<EqConfig>,
+    unknown_fob: Option<UnknownFobAction>,
+    tag_presence: Option<TagPresenceConfig>,
+    rescan: Option<RescanConfig>,
+}
+
+pub async fn get(extract::State(state): extract::State<AppState>) -> impl IntoResponse {
+    state.wifi_handle.wifi_on().await;
+    let fs_guard = state.fs.borrow_mut().await;
+    let root = fs_guard.root_dir();
+
+    let Ok(mut file) = root.open_file("config.jsn").await else {
+        return Err(Response::new(StatusCode::NOT_FOUND, "not found"));
+    };
+
+    let mut bytes = Vec::new();
+    let mut buffer = [0u8; 128];
+    loop {
+        match file.read(&mut buffer).await {
+            Ok(0) => break,
+            Ok(n) => bytes.extend_from_slice(&buffer[..n]),
+            Err(_) => {
+                return Err(Response::new(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "read error",
+                ));
+            }
+        }
+    }
+
+    match serde_json::from_slice::<DeviceConfig>(&bytes) {
+        Ok(config) => Ok(Json(PublicConfig {
+            ssid: config.ssid,
+            max_volume: config.max_volume,
+            startup_volume: config.startup_volume,
+            eq: config.eq,
+            unknown_fob: config.unknown_fob,
+            tag_presence: config.tag_presence,
+            rescan: config.rescan,
+        })),
+        Err(_) => Err(Response::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "invalid config",
+        )),
+    }
+}
 
 pub async fn put(
     extract::State(state): extract::State<AppState>,
-    extract::Json(req): extract::Json<DeviceConfig>,
+    extract::Json(mut req): extract::Json<DeviceConfig>,
 ) -> impl IntoResponse {
     state.wifi_handle.wifi_on().await;
     info!("WebAPI: config saved");
+    req.max_volume = req
+        .max_volume
+        .map(|max_volume| max_volume.clamp(1, MAX_VOLUME));
+    // Wi-Fi settings apply after a restart, everything else right away
+    state
+        .commands
+        .set_max_volume(req.max_volume.unwrap_or(MAX_VOLUME))
+        .await;
+    state.commands.set_eq(req.eq).await;
+    state
+        .commands
+        .set_unknown_fob(req.unknown_fob.unwrap_or_default())
+        .await;
+    state.commands.set_tag_presence(req.tag_presence).await;
+    state
+        .commands
+        .set_rescan(req.rescan.unwrap_or_default())
+        .await;
+
     let fs_guard = state.fs.borrow_mut().await;
     let root = fs_guard.root_dir();
 
@@ -31,6 +107,15 @@ pub async fn put(
 pub async fn delete(extract::State(state): extract::State<AppState>) -> impl IntoResponse {
     state.wifi_handle.wifi_on().await;
     info!("WebAPI: config deleted");
+    state.commands.set_max_volume(MAX_VOLUME).await;
+    state.commands.set_eq(None).await;
+    state
+        .commands
+        .set_unknown_fob(UnknownFobAction::default())
+        .await;
+    state.commands.set_tag_presence(None).await;
+    state.commands.set_rescan(RescanConfig::default()).await;
+
     let fs_guard = state.fs.borrow_mut().await;
     let root = fs_guard.root_dir();
 
@@ -1,5 +1,7 @@
 use core::str::FromStr;
 
+use defmt::{error, info};
+use embedded_io_async::Read;
 use futures::stream::StreamExt;
 use heapless::String;
 use picoserve::{
@@ -16,6 +18,8 @@ use serde_json;
 use crate::entities::audio_file::AudioFile;
 use crate::services::web::{AppState, AudioMetadata, FileEntry};
 
+const DOWNLOAD_BUFFER_SIZE: usize = 1024;
+
 pub struct AudioFileName(pub String<8>);
 
 impl FromStr for AudioFileName {
@@ -121,3 +125,114 @@ impl RequestHandlerService<AppState, (AudioFileName,)> for GetMetadataService {
         }
     }
 }
+
+struct StreamingFileContent {
+    state: AppState,
+    audio_file: AudioFile,
+}
+
+impl Chunks for StreamingFileContent {
+    async fn write_chunks<W: picoserve::io::Write>(
+        self,
+        mut writer: ChunkWriter<W>,
+    ) -> Result<ChunksWritten, W::Error> {
+        let fs_guard = self.state.fs.borrow_mut().await;
+        let Ok(mut file) = self.audio_file.open(&fs_guard).await else {
+            return writer.finalize().await;
+        };
+
+        let mut buffer = alloc::vec![0u8; DOWNLOAD_BUFFER_SIZE];
+        loop {
+            match file.read(&mut buffer).await {
+                Ok(0) => break,
+                Ok(n) => writer.write_chunk(&buffer[..n]).await?,
+                Err(e) => {
+                    // The client detects the truncated download by comparing with the file size
+                    error!("Download: read error: {:?}", e);
+                    break;
+                }
+            }
+        }
+
+        writer.finalize().await
+    }
+
+    fn content_type(&self) -> &'static str {
+        "audio/wav"
+    }
+}
+
+pub struct DownloadService;
+
+impl RequestHandlerService<AppState, (AudioFileName,)> for DownloadService {
+    async fn call_request_handler_service<
+        R: picoserve::io::Read,
+        W: ResponseWriter<Error = R::Error>,
+    >(
+        &self,
+        state: &AppState,
+        path_parameters: (AudioFileName,),
+        request: Request<'_, R>,
+        response_writer: W,
+    ) -> Result<ResponseSent, W::Error> {
+        state.wifi_handle.wifi_on().await;
+        let name = path_parameters.0.0;
+        let connection = request.body_connection.finalize().await?;
+        info!("WebAPI: download {}", name);
+
+        let audio_file = AudioFile::new(name);
+        let exists = {
+            let fs_guard = state.fs.borrow_mut().await;
+            audio_file.exists(&fs_guard).await.unwrap_or(false)
+        };
+
+        if exists {
+            ChunkedResponse::new(StreamingFileContent {
+                state: state.clone(),
+                audio_file,
+            })
+            .write_to(connection, response_writer)
+            .await
+        } else {
+            Response::new(StatusCode::NOT_FOUND, "")
+                .write_to(connection, response_writer)
+                .await
+        }
+    }
+}
+
+pub struct DeleteService;
+
+impl RequestHandlerService<AppState, (AudioFileName,)> for DeleteService {
+    async fn call_request_handler_service<
+        R: picoserve::io::Read,
+        W: ResponseWriter<Error = R::Error>,
+    >(
+        &self,
+        state: &AppState,
+        path_parameters: (AudioFileName,),
+        request: Request<'_, R>,
+        response_writer: W,
+    ) -> Result<ResponseSent, W::Error> {
+        state.wifi_handle.wifi_on().await;
+        let name = path_parameters.0.0;
+        let connection = request.body_connection.finalize().await?;
+        info!("WebAPI: delete {}", name);
+
+        let audio_file = AudioFile::new(name);
+        let fs_guard = state.fs.borrow_mut().await;
+        if !audio_file.exists(&fs_guard).await.unwrap_or(false) {
+            return Response::new(StatusCode::NOT_FOUND, "")
+                .write_to(connection, response_writer)
+                .await;
+        }
+
+        let status = match audio_file.remove(&fs_guard).await {
+            Ok(()) if fs_guard.flush().await.is_ok() => StatusCode::NO_CONTENT,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        Response::new(status, "")
+            .write_to(connection, response_writer)
+            .await
+    }
+}
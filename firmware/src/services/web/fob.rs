@@ -19,6 +19,7 @@ use crate::drivers::sd::SdFileSystem;
 use crate::entities::{
     audio_file::{AudioFile, AudioMetadata},
     playlist::{PlayListRef, Playlist},
+    resume::{ResumeMode, ResumePoint},
 };
 use crate::services::web::{AppState, FileEntry};
 
@@ -39,6 +40,25 @@ pub struct AssociationRequest {
     files: Vec<String<8>>,
 }
 
+#[derive(Deserialize)]
+pub struct ResumeQuery {
+    fob: String<8>,
+}
+
+#[derive(Serialize)]
+pub struct ResumeResponse {
+    fob: String<8>,
+    mode: ResumeMode,
+    index_in_playlist: usize,
+    position_seconds: u32,
+}
+
+#[derive(Deserialize)]
+pub struct ResumeRequest {
+    fob: String<8>,
+    mode: ResumeMode,
+}
+
 pub async fn last(extract::State(state): extract::State<AppState>) -> impl IntoResponse {
     state.wifi_handle.wifi_on().await;
     let last_fob = state.get_last_fob().await;
@@ -53,11 +73,59 @@ pub async fn associate(
     info!("WebAPI: associate FOB {}", req.fob);
     let audio_files: Vec<AudioFile> = req.files.into_iter().map(AudioFile::new).collect();
     let fs_guard = state.fs.borrow_mut().await;
-    Playlist::write(&fs_guard, req.fob, &audio_files)
+
+    // The saved position belongs to the old playlist
+    ResumePoint::reset_position(&fs_guard, &req.fob).await;
+
+    // Keep the playback directives of the playlist being replaced
+    let mode = PlayListRef::new(req.fob.clone())
+        .read(&fs_guard)
+        .await
+        .map(|playlist| playlist.mode)
+        .unwrap_or_default();
+
+    Playlist::write(&fs_guard, req.fob, &audio_files, &mode)
         .await
         .unwrap();
 }
 
+pub async fn get_resume(
+    extract::State(state): extract::State<AppState>,
+    extract::Query(query): extract::Query<ResumeQuery>,
+) -> impl IntoResponse {
+    state.wifi_handle.wifi_on().await;
+    let fs_guard = state.fs.borrow_mut().await;
+    let resume_point = ResumePoint::read(&fs_guard, &query.fob).await;
+
+    Json(ResumeResponse {
+        fob: query.fob,
+        mode: resume_point.mode,
+        index_in_playlist: resume_point.index,
+        position_seconds: (resume_point.sample / 44100) as u32,
+    })
+}
+
+pub async fn put_resume(
+    extract::State(state): extract::State<AppState>,
+    extract::Json(req): extract::Json<ResumeRequest>,
+) -> impl IntoResponse {
+    state.wifi_handle.wifi_on().await;
+    info!("WebAPI: resume mode of FOB {}: {}", req.fob, req.mode);
+    let fs_guard = state.fs.borrow_mut().await;
+    let resume_point = ResumePoint::read(&fs_guard, &req.fob).await;
+
+    match (ResumePoint {
+        mode: req.mode,
+        ..resume_point
+    })
+    .write(&fs_guard, &req.fob)
+    .await
+    {
+        Ok(()) => Response::new(StatusCode::NO_CONTENT, ""),
+        Err(()) => Response::new(StatusCode::INTERNAL_SERVER_ERROR, "write error"),
+    }
+}
+
 #[derive(Deserialize, Default)]
 struct ListQuery {
     fob: Option<String<8>>,
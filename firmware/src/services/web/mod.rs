@@ -16,6 +16,7 @@ use serde::Serialize;
 
 use crate::controllers::wifi::WifiManagerHandle;
 use crate::drivers::rfid::RfidHandle;
+use crate::entities::system_sound::SystemSound;
 use crate::{
     controllers::playback::PlaybackHandle, controllers::playback::status::Status,
     entities::audio_file::AudioMetadata,
@@ -28,6 +29,7 @@ mod config;
 mod files;
 mod fob;
 mod playback;
+mod sounds;
 mod upload;
 
 #[derive(Clone)]
@@ -81,6 +83,31 @@ impl PathRouterService<AppState, ()> for FileMethods {
         request: Request<'_, R>,
         response_writer: W,
     ) -> Result<ResponseSent, W::Error> {
+        if let Ok(path_parameters) = (
+            routing::parse_path_segment::<files::AudioFileName>(),
+            "/data",
+        )
+            .parse_entire_path(current_path_parameters, path)
+        {
+            return match request.parts.method() {
+                "GET" => {
+                    files::DownloadService
+                        .call_request_handler_service(
+                            state,
+                            path_parameters,
+                            request,
+                            response_writer,
+                        )
+                        .await
+                }
+                _ => {
+                    routing::MethodNotAllowed
+                        .call_request_handler(state, path_parameters, request, response_writer)
+                        .await
+                }
+            };
+        }
+
         // workaround for https://github.com/sammhicks/picoserve/issues/101
         let Ok(path_parameters) =
             routing::parse_path_segment().parse_entire_path(current_path_parameters, path)
@@ -122,6 +149,63 @@ impl PathRouterService<AppState, ()> for FileMethods {
                     .call_request_handler_service(state, path_parameters, request, response_writer)
                     .await
             }
+            "DELETE" => {
+                files::DeleteService
+                    .call_request_handler_service(state, path_parameters, request, response_writer)
+                    .await
+            }
+            _ => {
+                routing::MethodNotAllowed
+                    .call_request_handler(state, path_parameters, request, response_writer)
+                    .await
+            }
+        }
+    }
+}
+
+pub struct SystemSoundMethods;
+impl PathRouterService<AppState, ()> for SystemSoundMethods {
+    async fn call_path_router_service<
+        R: picoserve::io::Read,
+        W: ResponseWriter<Error = R::Error>,
+    >(
+        &self,
+        state: &AppState,
+        current_path_parameters: (),
+        path: picoserve::request::Path<'_>,
+        request: Request<'_, R>,
+        response_writer: W,
+    ) -> Result<ResponseSent, W::Error> {
+        let Ok(path_parameters) = routing::parse_path_segment::<SystemSound>()
+            .parse_entire_path(current_path_parameters, path)
+        else {
+            return picoserve::routing::NotFound
+                .call_path_router(
+                    state,
+                    current_path_parameters,
+                    path,
+                    request,
+                    response_writer,
+                )
+                .await;
+        };
+
+        match request.parts.method() {
+            "PUT" => {
+                sounds::UploadSoundService
+                    .call_request_handler_service(state, path_parameters, request, response_writer)
+                    .await
+            }
+            "DELETE" => {
+                sounds::DeleteSoundService
+                    .call_request_handler_service(state, path_parameters, request, response_writer)
+                    .await
+            }
+            "POST" => {
+                sounds::PlaySoundService
+                    .call_request_handler_service(state, path_parameters, request, response_writer)
+                    .await
+            }
             _ => {
                 routing::MethodNotAllowed
                     .call_request_handler(state, path_parameters, request, response_writer)
@@ -178,17 +262,24 @@ impl AppWithStateBuilder for Application {
         let router = picoserve::Router::from_service(Fallback)
             .nest_service("/api/files", FileMethods)
             .route("/api/files", routing::get(files::list))
+            .nest_service("/api/system_sounds", SystemSoundMethods)
+            .route("/api/system_sounds", routing::get(sounds::list))
             .route("/api/last_fob", routing::get(fob::last))
             .route(
                 "/api/associations",
                 routing::get_service(fob::ListAssociationsService).post(fob::associate),
             )
+            .route(
+                "/api/resume",
+                routing::get(fob::get_resume).put(fob::put_resume),
+            )
             .route("/api/playback/status", routing::get(playback::status))
             .route(
                 "/api/playback/current_playlist",
                 routing::get(playback::current_playlist),
             )
             .route("/api/playback/play", routing::post(playback::play))
+            .route("/api/playback/queue", routing::post(playback::queue))
             .route("/api/playback/stop", routing::post(playback::stop))
             .route("/api/playback/pause", routing::post(playback::pause))
             .route(
@@ -201,9 +292,17 @@ impl AppWithStateBuilder for Application {
             )
             .route("/api/playback/next", routing::post(playback::next))
             .route("/api/playback/previous", routing::post(playback::previous))
+            .route("/api/playback/seek", routing::post(playback::seek))
+            .route("/api/playback/sleep", routing::post(playback::sleep))
+            .route(
+                "/api/playback/mode",
+                routing::get(playback::get_mode).put(playback::put_mode),
+            )
             .route(
                 "/api/config",
-                routing::put(config::put).delete(config::delete),
+                routing::get(config::get)
+                    .put(config::put)
+                    .delete(config::delete),
             )
             .route("/generate_204", routing::get(captive_handler))
             .route("/hotspot-detect.html", routing::get(captive_handler));
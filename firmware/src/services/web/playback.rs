@@ -8,9 +8,10 @@ use picoserve::{
 use serde::{Deserialize, Serialize};
 
 use crate::controllers::playback::status::{PlaylistWithMetadata, State};
+use crate::controllers::playback::{QueueEdit, QueueSource};
 use crate::entities::{
     audio_file::AudioFile,
-    playlist::{PlayListRef, Playlist},
+    playlist::{PlayListRef, Playlist, Repeat},
 };
 use crate::services::web::AppState;
 
@@ -22,12 +23,66 @@ pub enum PlayRequest {
     PlaylistRef(String<8>),
 }
 
+impl PlayRequest {
+    fn into_queue_source(self) -> QueueSource {
+        match self {
+            PlayRequest::File(file) => QueueSource::Files(vec![AudioFile::new(file)]),
+            PlayRequest::Playlist(files) => {
+                QueueSource::Files(files.into_iter().map(AudioFile::new).collect())
+            }
+            PlayRequest::PlaylistRef(playlist_ref) => {
+                QueueSource::PlaylistRef(PlayListRef::new(playlist_ref))
+            }
+        }
+    }
+}
+
+/// Edits the playlist playing, indices are positions in the playlist
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueRequest {
+    Append(PlayRequest),
+    InsertNext(PlayRequest),
+    Remove(usize),
+    Move { from: usize, to: usize },
+    Clear,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SeekRequest {
+    To(u32),
+    By(i32),
+}
+
+/// Changes the global playback mode, omitted fields stay as they are
+#[derive(Deserialize)]
+pub struct ModeRequest {
+    #[serde(default)]
+    pub repeat: Option<Repeat>,
+    #[serde(default)]
+    pub shuffle: Option<bool>,
+}
+
+#[derive(Deserialize)]
+pub struct SleepRequest {
+    /// 0 cancels the timer
+    pub minutes: u16,
+}
+
 #[derive(Serialize)]
 pub struct StatusResponse {
     pub position_seconds: u32,
     pub state: State,
     pub index_in_playlist: usize,
     pub playlist_name: Option<String<8>>,
+    pub repeat: Repeat,
+    pub shuffle: bool,
+    pub sleep_timer_seconds: Option<u32>,
+    pub volume: u8,
+    pub max_volume: u8,
+    /// Fob without playlist waiting for one, see `UnknownFobAction::Learn`
+    pub learning_fob: Option<String<8>>,
 }
 
 #[derive(Serialize)]
@@ -49,6 +104,12 @@ pub async fn status(extract::State(state): extract::State<AppState>) -> impl Int
         state,
         index_in_playlist,
         playlist_name,
+        repeat: current_file.mode.repeat,
+        shuffle: current_file.mode.shuffle,
+        sleep_timer_seconds: current_file.sleep_timer_remaining(),
+        volume: state.commands.get_volume(),
+        max_volume: state.commands.get_max_volume(),
+        learning_fob: state.commands.learning_fob(),
     })
 }
 
@@ -87,6 +148,37 @@ pub async fn play(
     Response::new(StatusCode::NO_CONTENT, "")
 }
 
+pub async fn queue(
+    extract::State(state): extract::State<AppState>,
+    extract::Json(req): extract::Json<QueueRequest>,
+) -> impl IntoResponse {
+    state.wifi_handle.wifi_on().await;
+    let len = state
+        .status()
+        .get_current_playlist()
+        .map(|playlist| playlist.files.len());
+    let edit = match req {
+        QueueRequest::Append(source) => QueueEdit::Append(source.into_queue_source()),
+        QueueRequest::InsertNext(source) => QueueEdit::InsertNext(source.into_queue_source()),
+        QueueRequest::Remove(index) => match len {
+            None => return Response::new(StatusCode::CONFLICT, "not playing"),
+            Some(len) if index >= len => return Response::new(StatusCode::NOT_FOUND, "not found"),
+            Some(_) => QueueEdit::Remove(index),
+        },
+        QueueRequest::Move { from, to } => match len {
+            None => return Response::new(StatusCode::CONFLICT, "not playing"),
+            Some(len) if from >= len || to >= len => {
+                return Response::new(StatusCode::NOT_FOUND, "not found");
+            }
+            Some(_) => QueueEdit::Move { from, to },
+        },
+        QueueRequest::Clear => QueueEdit::Clear,
+    };
+    info!("WebAPI: queue {}", edit);
+    state.commands.edit_queue(edit).await;
+    Response::new(StatusCode::NO_CONTENT, "")
+}
+
 pub async fn stop(extract::State(state): extract::State<AppState>) -> impl IntoResponse {
     state.wifi_handle.wifi_on().await;
     info!("WebAPI: stop");
@@ -128,3 +220,53 @@ pub async fn previous(extract::State(state): extract::State<AppState>) -> impl I
     let _ = state.commands.skip_previous().await;
     Response::new(StatusCode::NO_CONTENT, "")
 }
+
+pub async fn seek(
+    extract::State(state): extract::State<AppState>,
+    extract::Json(req): extract::Json<SeekRequest>,
+) -> impl IntoResponse {
+    state.wifi_handle.wifi_on().await;
+    match req {
+        SeekRequest::To(seconds) => {
+            info!("WebAPI: seek to {}s", seconds);
+            state.commands.seek_to(seconds).await;
+        }
+        SeekRequest::By(seconds) => {
+            info!("WebAPI: seek by {}s", seconds);
+            state.commands.seek_by(seconds).await;
+        }
+    }
+    Response::new(StatusCode::NO_CONTENT, "")
+}
+
+pub async fn get_mode(extract::State(state): extract::State<AppState>) -> impl IntoResponse {
+    state.wifi_handle.wifi_on().await;
+    let mode = state.status().get_playback_status().mode;
+    Json(mode)
+}
+
+pub async fn put_mode(
+    extract::State(state): extract::State<AppState>,
+    extract::Json(req): extract::Json<ModeRequest>,
+) -> impl IntoResponse {
+    state.wifi_handle.wifi_on().await;
+    if let Some(repeat) = req.repeat {
+        info!("WebAPI: set repeat {:?}", repeat);
+        state.commands.set_repeat(repeat).await;
+    }
+    if let Some(shuffle) = req.shuffle {
+        info!("WebAPI: set shuffle {}", shuffle);
+        state.commands.set_shuffle(shuffle).await;
+    }
+    Response::new(StatusCode::NO_CONTENT, "")
+}
+
+pub async fn sleep(
+    extract::State(state): extract::State<AppState>,
+    extract::Json(req): extract::Json<SleepRequest>,
+) -> impl IntoResponse {
+    state.wifi_handle.wifi_on().await;
+    info!("WebAPI: sleep timer {} min", req.minutes);
+    state.commands.set_sleep_timer(req.minutes).await;
+    Response::new(StatusCode::NO_CONTENT, "")
+}
@@ -0,0 +1,130 @@
+use alloc::vec::Vec;
+use defmt::info;
+use picoserve::{
+    ResponseSent, extract,
+    io::ReadExt,
+    request::Request,
+    response::{IntoResponse, Json, Response, ResponseWriter, StatusCode},
+    routing::RequestHandlerService,
+};
+use serde::Serialize;
+
+use crate::entities::system_sound::SystemSound;
+use crate::services::web::{AppState, upload::write_body};
+
+#[derive(Serialize)]
+pub struct SystemSoundEntry {
+    pub sound: SystemSound,
+    /// Whether a clip replaces the synthesized tones
+    pub custom: bool,
+}
+
+pub async fn list(extract::State(state): extract::State<AppState>) -> impl IntoResponse {
+    state.wifi_handle.wifi_on().await;
+    let fs_guard = state.fs.borrow_mut().await;
+    let mut sounds = Vec::new();
+    for sound in SystemSound::ALL {
+        sounds.push(SystemSoundEntry {
+            sound,
+            custom: sound.exists(&fs_guard).await,
+        });
+    }
+    Json(sounds)
+}
+
+pub struct UploadSoundService;
+pub struct DeleteSoundService;
+pub struct PlaySoundService;
+
+impl RequestHandlerService<AppState, (SystemSound,)> for UploadSoundService {
+    async fn call_request_handler_service<
+        R: picoserve::io::Read,
+        W: ResponseWriter<Error = R::Error>,
+    >(
+        &self,
+        state: &AppState,
+        path_parameters: (SystemSound,),
+        mut request: Request<'_, R>,
+        response_writer: W,
+    ) -> Result<ResponseSent, W::Error> {
+        state.wifi_handle.wifi_on().await;
+        let sound = path_parameters.0;
+        info!("WebAPI: upload system sound {}", sound);
+
+        let fs_guard = state.fs.borrow_mut().await;
+        let mut file_handle = match sound.create(&fs_guard).await {
+            Ok(handle) => handle,
+            Err(_) => {
+                let connection = request.body_connection.finalize().await?;
+                return Response::new(StatusCode::INTERNAL_SERVER_ERROR, "")
+                    .write_to(connection, response_writer)
+                    .await;
+            }
+        };
+
+        let mut body = request.body_connection.body().reader();
+        write_body(&mut body, &mut file_handle).await?;
+
+        body.discard_all_data().await?;
+        let connection = request.body_connection.finalize().await?;
+        Response::new(StatusCode::NO_CONTENT, "")
+            .write_to(connection, response_writer)
+            .await
+    }
+}
+
+impl RequestHandlerService<AppState, (SystemSound,)> for DeleteSoundService {
+    async fn call_request_handler_service<
+        R: picoserve::io::Read,
+        W: ResponseWriter<Error = R::Error>,
+    >(
+        &self,
+        state: &AppState,
+        path_parameters: (SystemSound,),
+        request: Request<'_, R>,
+        response_writer: W,
+    ) -> Result<ResponseSent, W::Error> {
+        state.wifi_handle.wifi_on().await;
+        let sound = path_parameters.0;
+        let connection = request.body_connection.finalize().await?;
+        info!("WebAPI: delete system sound {}", sound);
+
+        let fs_guard = state.fs.borrow_mut().await;
+        if !sound.exists(&fs_guard).await {
+            return Response::new(StatusCode::NOT_FOUND, "")
+                .write_to(connection, response_writer)
+                .await;
+        }
+
+        let status = match sound.remove(&fs_guard).await {
+            Ok(()) if fs_guard.flush().await.is_ok() => StatusCode::NO_CONTENT,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        Response::new(status, "")
+            .write_to(connection, response_writer)
+            .await
+    }
+}
+
+impl RequestHandlerService<AppState, (SystemSound,)> for PlaySoundService {
+    async fn call_request_handler_service<
+        R: picoserve::io::Read,
+        W: ResponseWriter<Error = R::Error>,
+    >(
+        &self,
+        state: &AppState,
+        path_parameters: (SystemSound,),
+        request: Request<'_, R>,
+        response_writer: W,
+    ) -> Result<ResponseSent, W::Error> {
+        state.wifi_handle.wifi_on().await;
+        let sound = path_parameters.0;
+        let connection = request.body_connection.finalize().await?;
+        info!("WebAPI: play system sound {}", sound);
+
+        state.commands.play_sound(sound, 1).await;
+        Response::new(StatusCode::NO_CONTENT, "")
+            .write_to(connection, response_writer)
+            .await
+    }
+}
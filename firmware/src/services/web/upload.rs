@@ -79,35 +79,8 @@ impl RequestHandlerService<AppState, (AudioFileName,)> for UploadService {
         };
 
         let mut body = request.body_connection.body().reader();
-        let mut buffer1 = vec![0u8; BUFFER_SIZE];
-        let mut buffer2 = vec![0u8; BUFFER_SIZE];
-
-        let mut read_buffer = &mut buffer1;
-        let mut write_buffer = &mut buffer2;
-        let mut last_read_size = 0;
-
-        loop {
-            // TODO delete file on error
-            let (read_res, write_res) = join(
-                read_max(&mut body, read_buffer),
-                write_all(&mut file_handle, write_buffer, last_read_size),
-            )
-            .await;
-            write_res.unwrap();
-            last_read_size = read_res?;
-            debug!("Upload: read OK");
-
-            (write_buffer, read_buffer) = (read_buffer, write_buffer);
-
-            if last_read_size != BUFFER_SIZE {
-                file_handle
-                    .write_all(&write_buffer[..last_read_size])
-                    .await
-                    .unwrap();
-                break;
-            }
-        }
-        file_handle.flush().await.unwrap();
+        // TODO delete file on error
+        write_body(&mut body, &mut file_handle).await?;
 
         body.discard_all_data().await?;
         let connection = request.body_connection.finalize().await?;
@@ -117,6 +90,43 @@ impl RequestHandlerService<AppState, (AudioFileName,)> for UploadService {
     }
 }
 
+/// Streams the request body into `file`, reading the next buffer while the last one is written
+pub(super) async fn write_body<B: Read, F: Write>(
+    body: &mut B,
+    file: &mut F,
+) -> Result<(), B::Error>
+where
+    F::Error: defmt::Format,
+{
+    let mut buffer1 = vec![0u8; BUFFER_SIZE];
+    let mut buffer2 = vec![0u8; BUFFER_SIZE];
+
+    let mut read_buffer = &mut buffer1;
+    let mut write_buffer = &mut buffer2;
+    let mut last_read_size = 0;
+
+    loop {
+        let (read_res, write_res) = join(
+            read_max(body, read_buffer),
+            write_all(file, write_buffer, last_read_size),
+        )
+        .await;
+        write_res.unwrap();
+        last_read_size = read_res?;
+
+        (write_buffer, read_buffer) = (read_buffer, write_buffer);
+
+        if last_read_size != BUFFER_SIZE {
+            file.write_all(&write_buffer[..last_read_size])
+                .await
+                .unwrap();
+            break;
+        }
+    }
+    file.flush().await.unwrap();
+    Ok(())
+}
+
 async fn write_all<W: Write>(w: &mut W, buf: &[u8], size: usize) -> Result<(), W::Error>
 where
     W::Error: defmt::Format,
@@ -241,33 +251,8 @@ impl RequestHandlerService<AppState, (AudioFileName,)> for PatchUploadService {
         };
 
         let mut body = request.body_connection.body().reader();
-        let mut buffer1 = vec![0u8; BUFFER_SIZE];
-        let mut buffer2 = vec![0u8; BUFFER_SIZE];
-
-        let mut read_buffer = &mut buffer1;
-        let mut write_buffer = &mut buffer2;
-        let mut last_read_size = 0;
-
-        loop {
-            let (read_res, write_res) = join(
-                read_max(&mut body, read_buffer),
-                write_all(&mut file_handle, write_buffer, last_read_size),
-            )
-            .await;
-            write_res.unwrap();
-            last_read_size = read_res?;
-
-            (write_buffer, read_buffer) = (read_buffer, write_buffer);
-
-            if last_read_size != BUFFER_SIZE {
-                file_handle
-                    .write_all(&write_buffer[..last_read_size])
-                    .await
-                    .unwrap();
-                break;
-            }
-        }
-        file_handle.flush().await.unwrap();
+        // TODO delete file on error
+        write_body(&mut body, &mut file_handle).await?;
 
         body.discard_all_data().await?;
         let connection = request.body_connection.finalize().await?;
@@ -16,4 +16,8 @@ pub enum TranscodeError {
     UnknownSampleRate,
     #[error("unknown channels count")]
     UnknownChannelsCount,
+    #[error("invalid device file: {0}")]
+    InvalidDeviceFile(&'static str),
+    #[error("FLAC encoding failed: {0}")]
+    FlacEncode(String),
 }
@@ -0,0 +1,170 @@
+use audio_file_utils::adpcm::decode_block;
+use audio_file_utils::metadata::{INFO_CHUNK_SIZE, Metadata, extract_metadata, write_info_chunk};
+use flacenc::bitsink::ByteSink;
+use flacenc::component::{BitRepr, MetadataBlockData};
+use flacenc::error::Verify;
+use std::io::{Cursor, Write};
+
+use crate::error::TranscodeError;
+
+const IMA_ADPCM_FORMAT: u16 = 0x0011;
+const FLAC_VORBIS_COMMENT: u8 = 4;
+
+/// PCM audio decoded from a device file
+#[derive(Debug, Clone)]
+pub struct DecodedAudio {
+    pub samples: Vec<i16>,
+    pub sample_rate: u32,
+    pub metadata: Metadata,
+}
+
+/// Decode a device file (mono IMA ADPCM WAV) back to PCM.
+///
+/// Blocks are decoded exactly like the firmware's `AudioDecoder` does, so the result is what
+/// the speaker will output.
+pub async fn decode_ima_adpcm_wav(input: &[u8]) -> Result<DecodedAudio, TranscodeError> {
+    if input.len() < 12 || &input[0..4] != b"RIFF" || &input[8..12] != b"WAVE" {
+        return Err(TranscodeError::InvalidDeviceFile("not a RIFF/WAVE file"));
+    }
+
+    let mut format = None;
+    let mut data = None;
+    let mut offset = 12;
+    while offset + 8 <= input.len() {
+        let id = &input[offset..offset + 4];
+        let size = u32::from_le_bytes(input[offset + 4..offset + 8].try_into().unwrap()) as usize;
+        let body = &input[offset + 8..(offset + 8 + size).min(input.len())];
+        match id {
+            b"fmt " => format = Some(body),
+            b"data" => data = Some(body),
+            _ => {}
+        }
+        offset += 8 + size + (size & 1);
+    }
+
+    let format = format.ok_or(TranscodeError::InvalidDeviceFile("missing fmt chunk"))?;
+    let data = data.ok_or(TranscodeError::InvalidDeviceFile("missing data chunk"))?;
+    if format.len() < 16 {
+        return Err(TranscodeError::InvalidDeviceFile("fmt chunk too short"));
+    }
+
+    let format_tag = u16::from_le_bytes([format[0], format[1]]);
+    let channels = u16::from_le_bytes([format[2], format[3]]);
+    let sample_rate = u32::from_le_bytes(format[4..8].try_into().unwrap());
+    let block_align = u16::from_le_bytes([format[12], format[13]]) as usize;
+    if format_tag != IMA_ADPCM_FORMAT || channels != 1 || block_align <= 4 {
+        return Err(TranscodeError::InvalidDeviceFile(
+            "not mono IMA ADPCM, only device files can be decoded",
+        ));
+    }
+
+    let samples_per_block = (block_align - 4) * 2 + 1;
+    let mut samples = Vec::with_capacity(data.len().div_ceil(block_align) * samples_per_block);
+    for block in data.chunks(block_align) {
+        // A trailing block without a complete header ends playback on the device
+        if block.len() < 4 {
+            break;
+        }
+        let start = samples.len();
+        samples.resize(start + samples_per_block, 0);
+        let decoded = decode_block(block, &mut samples[start..]);
+        samples.truncate(start + decoded);
+    }
+
+    let metadata = extract_metadata(input).await.unwrap_or_default();
+
+    Ok(DecodedAudio {
+        samples,
+        sample_rate,
+        metadata,
+    })
+}
+
+/// Encode PCM samples as a 16 bit mono PCM WAV file with a LIST/INFO chunk
+pub async fn encode_pcm_wav(audio: &DecodedAudio) -> Result<Box<[u8]>, TranscodeError> {
+    let data_size = (audio.samples.len() * 2) as u32;
+    let fmt_chunk_size: u32 = 16;
+    let riff_chunk_size = 4 + (8 + fmt_chunk_size) + (8 + INFO_CHUNK_SIZE as u32) + (8 + data_size);
+
+    let mut writer = Cursor::new(Vec::with_capacity(riff_chunk_size as usize + 8));
+    writer.write_all(b"RIFF")?;
+    writer.write_all(&riff_chunk_size.to_le_bytes())?;
+    writer.write_all(b"WAVE")?;
+
+    writer.write_all(b"fmt ")?;
+    writer.write_all(&fmt_chunk_size.to_le_bytes())?;
+    writer.write_all(&1u16.to_le_bytes())?; // PCM
+    writer.write_all(&1u16.to_le_bytes())?; // mono
+    writer.write_all(&audio.sample_rate.to_le_bytes())?;
+    writer.write_all(&(audio.sample_rate * 2).to_le_bytes())?;
+    writer.write_all(&2u16.to_le_bytes())?; // block align
+    writer.write_all(&16u16.to_le_bytes())?; // bits per sample
+
+    writer.write_all(b"LIST")?;
+    writer.write_all(&(INFO_CHUNK_SIZE as u32).to_le_bytes())?;
+    let mut info = [0u8; INFO_CHUNK_SIZE];
+    write_info_chunk(&mut info[..], &audio.metadata)
+        .await
+        .map_err(|_| std::io::Error::other("write_info_chunk failed"))?;
+    writer.write_all(&info)?;
+
+    writer.write_all(b"data")?;
+    writer.write_all(&data_size.to_le_bytes())?;
+    for sample in &audio.samples {
+        writer.write_all(&sample.to_le_bytes())?;
+    }
+
+    Ok(writer.into_inner().into())
+}
+
+/// Encode PCM samples as a 16 bit mono FLAC file with a Vorbis comment block
+pub fn encode_flac(audio: &DecodedAudio) -> Result<Box<[u8]>, TranscodeError> {
+    let config = flacenc::config::Encoder::default()
+        .into_verified()
+        .map_err(|(_, e)| TranscodeError::FlacEncode(e.to_string()))?;
+
+    let samples: Vec<i32> = audio.samples.iter().map(|&s| s as i32).collect();
+    let source =
+        flacenc::source::MemSource::from_samples(&samples, 1, 16, audio.sample_rate as usize);
+    let mut stream = flacenc::encode_with_fixed_block_size(&config, source, config.block_size)
+        .map_err(|e| TranscodeError::FlacEncode(e.to_string()))?;
+
+    // The encoder counts the shorter last block as minimum block size, which makes the stream
+    // look variable-blocksize to strict decoders. The spec excludes the last block.
+    stream
+        .stream_info_mut()
+        .set_block_sizes(config.block_size, config.block_size)
+        .map_err(|e| TranscodeError::FlacEncode(e.to_string()))?;
+
+    let comment = vorbis_comment(&audio.metadata);
+    stream.add_metadata_block(
+        MetadataBlockData::new_unknown(FLAC_VORBIS_COMMENT, &comment)
+            .map_err(|e| TranscodeError::FlacEncode(e.to_string()))?,
+    );
+
+    let mut sink = ByteSink::new();
+    stream
+        .write(&mut sink)
+        .map_err(|e| TranscodeError::FlacEncode(e.to_string()))?;
+
+    Ok(sink.as_slice().into())
+}
+
+fn vorbis_comment(metadata: &Metadata) -> Vec<u8> {
+    let vendor = concat!("phoniesp32 transcoder ", env!("CARGO_PKG_VERSION"));
+    let comments = [
+        format!("ARTIST={}", metadata.artist),
+        format!("TITLE={}", metadata.title),
+        format!("ALBUM={}", metadata.album),
+    ];
+
+    let mut data = Vec::new();
+    data.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+    data.extend_from_slice(vendor.as_bytes());
+    data.extend_from_slice(&(comments.len() as u32).to_le_bytes());
+    for comment in comments {
+        data.extend_from_slice(&(comment.len() as u32).to_le_bytes());
+        data.extend_from_slice(comment.as_bytes());
+    }
+    data
+}
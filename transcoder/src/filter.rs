@@ -0,0 +1,15 @@
+/// First order high-pass filter, removes rumble and DC offset below `cutoff_hz`
+pub(crate) fn highpass(samples: &mut [f32], sample_rate: u32, cutoff_hz: f32) {
+    let rc = 1.0 / (2.0 * core::f32::consts::PI * cutoff_hz);
+    let dt = 1.0 / sample_rate as f32;
+    let alpha = rc / (rc + dt);
+
+    let mut previous_input = 0.0;
+    let mut previous_output = 0.0;
+    for sample in samples.iter_mut() {
+        let output = alpha * (previous_output + *sample - previous_input);
+        previous_input = *sample;
+        previous_output = output;
+        *sample = output;
+    }
+}
@@ -1,14 +1,45 @@
 mod decode;
 mod encode;
 mod error;
+mod export;
+mod filter;
 mod normalize;
 mod resample;
 
 pub use error::TranscodeError;
+pub use export::{DecodedAudio, decode_ima_adpcm_wav, encode_flac, encode_pcm_wav};
 
 use base32::encode;
 use sha1::{Digest, Sha1};
 
+/// Processing applied before encoding, depending on the kind of content
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Profile {
+    #[default]
+    Music,
+    /// Spoken word (stories, podcasts): slightly quieter target and no low frequency rumble
+    Speech,
+}
+
+impl Profile {
+    /// Integrated loudness target in LUFS
+    fn target_loudness(self) -> f64 {
+        match self {
+            // Spotify -14 lufs
+            Profile::Music => -14.0,
+            // Common target for podcasts
+            Profile::Speech => -16.0,
+        }
+    }
+
+    fn highpass_cutoff(self) -> Option<f32> {
+        match self {
+            Profile::Music => None,
+            Profile::Speech => Some(80.0),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct TranscodeResult {
     pub filename: String,
@@ -29,7 +60,7 @@ pub fn compute_filename(content: &[u8]) -> String {
 use audio_file_utils::metadata::Metadata;
 use symphonia::core::formats::FormatOptions;
 use symphonia::core::io::MediaSourceStream;
-use symphonia::core::meta::{MetadataOptions, StandardTagKey};
+use symphonia::core::meta::{MetadataOptions, StandardTagKey, StandardVisualKey};
 use symphonia::core::probe::Hint;
 use symphonia::default;
 
@@ -84,8 +115,75 @@ pub fn extract_metadata(input: &[u8]) -> Metadata {
     }
 }
 
+/// Track number from the tags of the input file (e.g. `3` for "3/12"), if present
+pub fn extract_track_number(input: &[u8]) -> Option<u32> {
+    let cursor = std::io::Cursor::new(input.to_vec());
+    let mss = MediaSourceStream::new(Box::new(cursor), Default::default());
+    let meta_opts: MetadataOptions = Default::default();
+    let fmt_opts: FormatOptions = Default::default();
+    let mut probed = default::get_probe()
+        .format(&Hint::new(), mss, &fmt_opts, &meta_opts)
+        .ok()?;
+
+    let parse = |revision: &symphonia::core::meta::MetadataRevision| {
+        revision
+            .tags()
+            .iter()
+            .find(|tag| tag.std_key == Some(StandardTagKey::TrackNumber))
+            .and_then(|tag| tag.value.to_string().split('/').next()?.trim().parse().ok())
+    };
+
+    // Tags before the stream (ID3v2) or inside the container (Vorbis comments, RIFF INFO)
+    if let Some(track) = probed
+        .metadata
+        .get()
+        .and_then(|m| m.current().and_then(parse))
+    {
+        return Some(track);
+    }
+    probed.format.metadata().current().and_then(parse)
+}
+
+/// Embedded cover art of the input file (the front cover if tagged as such, otherwise the first
+/// picture), in its original encoding
+pub fn extract_cover_art(input: &[u8]) -> Option<Vec<u8>> {
+    let cursor = std::io::Cursor::new(input.to_vec());
+    let mss = MediaSourceStream::new(Box::new(cursor), Default::default());
+    let meta_opts: MetadataOptions = Default::default();
+    let fmt_opts: FormatOptions = Default::default();
+    let mut probed = default::get_probe()
+        .format(&Hint::new(), mss, &fmt_opts, &meta_opts)
+        .ok()?;
+
+    let pick = |revision: &symphonia::core::meta::MetadataRevision| {
+        let visuals = revision.visuals();
+        visuals
+            .iter()
+            .find(|visual| visual.usage == Some(StandardVisualKey::FrontCover))
+            .or(visuals.first())
+            .map(|visual| visual.data.to_vec())
+    };
+
+    if let Some(cover) = probed
+        .metadata
+        .get()
+        .and_then(|m| m.current().and_then(pick))
+    {
+        return Some(cover);
+    }
+    probed.format.metadata().current().and_then(pick)
+}
+
 pub async fn decode_and_normalize(
     input: Box<[u8]>,
+    progress: impl FnMut(usize, usize) + Clone,
+) -> Result<TranscodeResult, TranscodeError> {
+    decode_and_normalize_with_profile(input, Profile::Music, progress).await
+}
+
+pub async fn decode_and_normalize_with_profile(
+    input: Box<[u8]>,
+    profile: Profile,
     mut progress: impl FnMut(usize, usize) + Clone,
 ) -> Result<TranscodeResult, TranscodeError> {
     let metadata = extract_metadata(&input);
@@ -111,8 +209,16 @@ pub async fn decode_and_normalize(
         make_progress(34, 67),
     )?;
 
-    // Spotify -14 lufs
-    normalize::loudness_normalize(&mut samples, OUT_RATE, 1, -14.0, make_progress(68, 85));
+    if let Some(cutoff) = profile.highpass_cutoff() {
+        filter::highpass(&mut samples, OUT_RATE, cutoff);
+    }
+    normalize::loudness_normalize(
+        &mut samples,
+        OUT_RATE,
+        1,
+        profile.target_loudness(),
+        make_progress(68, 85),
+    );
 
     let samples = to_i16(samples.into());
     progress(90, 100);
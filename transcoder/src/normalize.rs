@@ -16,10 +16,10 @@ pub(crate) fn loudness_normalize(
         let loudness_gain_db = target_lufs - loudness;
         let mut gain_db = loudness_gain_db;
 
-        if let Ok(true_peak) = state.true_peak(0) {
-            if true_peak < loudness_gain_db {
-                gain_db = true_peak;
-            }
+        if let Ok(true_peak) = state.true_peak(0)
+            && true_peak < loudness_gain_db
+        {
+            gain_db = true_peak;
         }
 
         let gain = 10f32.powf((gain_db as f32) / 20.0);
@@ -1,4 +1,8 @@
-use crate::{decode_and_normalize, extract_metadata};
+use crate::{
+    Profile, decode_and_normalize, decode_and_normalize_with_profile, decode_ima_adpcm_wav,
+    encode_flac, encode_pcm_wav, extract_cover_art, extract_metadata, extract_track_number,
+};
+use audio_file_utils::metadata::Metadata;
 use std::sync::{Arc, Mutex};
 
 // Helper function to get duration from IMA ADPCM WAV file
@@ -383,3 +387,189 @@ fn test_metadata_extraction_fallback() {
     assert_eq!(metadata.title.to_string(), "Unknown");
     assert_eq!(metadata.album.to_string(), "Unknown");
 }
+
+#[tokio::test]
+async fn test_decode_device_file_matches_block_layout() {
+    let mp3_data = include_bytes!("test_data/test_metadata.mp3");
+    let transcoded = decode_and_normalize(mp3_data.as_slice().into(), |_, _| {})
+        .await
+        .unwrap();
+
+    let decoded = decode_ima_adpcm_wav(&transcoded.data).await.unwrap();
+
+    let blocks = (transcoded.data.len() - 180) / 1024;
+    assert_eq!(decoded.samples.len(), blocks * 2041);
+    assert_eq!(decoded.sample_rate, 44100);
+    assert_eq!(decoded.metadata, extract_metadata(mp3_data));
+    assert!(decoded.samples.iter().any(|&s| s != 0));
+}
+
+#[tokio::test]
+async fn test_decode_rejects_non_device_files() {
+    let wav_data = include_bytes!("test_data/test_22050hz.wav");
+    assert!(decode_ima_adpcm_wav(wav_data).await.is_err());
+    assert!(decode_ima_adpcm_wav(b"not a wav file").await.is_err());
+}
+
+/// Vorbis comments of a FLAC file
+fn flac_tags(flac: &[u8]) -> Vec<(String, String)> {
+    use symphonia::core::io::MediaSourceStream;
+
+    let cursor = std::io::Cursor::new(flac.to_vec());
+    let mss = MediaSourceStream::new(Box::new(cursor), Default::default());
+    let mut probed = symphonia::default::get_probe()
+        .format(
+            &Default::default(),
+            mss,
+            &Default::default(),
+            &Default::default(),
+        )
+        .unwrap();
+
+    let metadata = probed.format.metadata();
+    metadata
+        .current()
+        .unwrap()
+        .tags()
+        .iter()
+        .map(|tag| (tag.key.clone(), tag.value.to_string()))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_export_pcm_wav_and_flac() {
+    let mp3_data = include_bytes!("test_data/test_metadata.mp3");
+    let transcoded = decode_and_normalize(mp3_data.as_slice().into(), |_, _| {})
+        .await
+        .unwrap();
+    let mut decoded = decode_ima_adpcm_wav(&transcoded.data).await.unwrap();
+    decoded.metadata = Metadata {
+        artist: "Artist".try_into().unwrap(),
+        title: "Title".try_into().unwrap(),
+        album: "Album".try_into().unwrap(),
+    };
+
+    // PCM WAV: INFO chunk is preserved and the samples are stored verbatim
+    let wav = encode_pcm_wav(&decoded).await.unwrap();
+    let wav_metadata = audio_file_utils::metadata::extract_metadata(&wav[..])
+        .await
+        .unwrap();
+    assert_eq!(wav_metadata, decoded.metadata);
+    assert_eq!(wav.len(), 12 + 24 + 8 + 124 + 8 + decoded.samples.len() * 2);
+    assert_eq!(
+        i16::from_le_bytes([wav[wav.len() - 2], wav[wav.len() - 1]]),
+        *decoded.samples.last().unwrap()
+    );
+
+    // FLAC: lossless, with the metadata as Vorbis comments
+    let flac = encode_flac(&decoded).unwrap();
+    assert_eq!(&flac[0..4], b"fLaC");
+    assert_eq!(
+        flac_tags(&flac),
+        vec![
+            ("ARTIST".to_string(), "Artist".to_string()),
+            ("TITLE".to_string(), "Title".to_string()),
+            ("ALBUM".to_string(), "Album".to_string()),
+        ]
+    );
+
+    let (samples, sample_rate) = crate::decode::decode_to_mono(flac, |_, _| {}).unwrap();
+    assert_eq!(sample_rate, 44100);
+    assert_eq!(samples.len(), decoded.samples.len());
+    for (flac_sample, sample) in samples.iter().zip(&decoded.samples) {
+        assert_eq!((flac_sample * 32768.0).round() as i16, *sample);
+    }
+}
+
+fn syncsafe(size: usize) -> [u8; 4] {
+    [
+        (size >> 21) as u8 & 0x7f,
+        (size >> 14) as u8 & 0x7f,
+        (size >> 7) as u8 & 0x7f,
+        size as u8 & 0x7f,
+    ]
+}
+
+/// Replaces the ID3v2.4 tag of the test MP3 with one that only contains the given frame
+fn with_id3_frame(id: &[u8; 4], value: &[u8]) -> Vec<u8> {
+    let mp3_data = include_bytes!("test_data/test_metadata.mp3");
+    let tag_size = mp3_data[6..10]
+        .iter()
+        .fold(0usize, |size, b| (size << 7) | *b as usize);
+    let audio = &mp3_data[10 + tag_size..];
+
+    let mut frame = id.to_vec();
+    frame.extend_from_slice(&syncsafe(value.len()));
+    frame.extend_from_slice(&[0, 0]);
+    frame.extend_from_slice(value);
+
+    let mut tagged = b"ID3\x04\x00\x00".to_vec();
+    tagged.extend_from_slice(&syncsafe(frame.len()));
+    tagged.extend_from_slice(&frame);
+    tagged.extend_from_slice(audio);
+    tagged
+}
+
+#[test]
+fn test_extract_track_number() {
+    let mp3_data = include_bytes!("test_data/test_metadata.mp3");
+    assert_eq!(extract_track_number(mp3_data), None);
+
+    let tagged = with_id3_frame(b"TRCK", b"\x033/12");
+    assert_eq!(extract_track_number(&tagged), Some(3));
+}
+
+#[test]
+fn test_extract_cover_art() {
+    let mp3_data = include_bytes!("test_data/test_metadata.mp3");
+    assert_eq!(extract_cover_art(mp3_data), None);
+
+    // UTF-8 encoding, MIME type, front cover, empty description, picture data
+    let mut picture = b"\x03image/jpeg\x00\x03\x00".to_vec();
+    picture.extend_from_slice(b"\xff\xd8 not really a jpeg");
+    let tagged = with_id3_frame(b"APIC", &picture);
+    assert_eq!(
+        extract_cover_art(&tagged).as_deref(),
+        Some(&b"\xff\xd8 not really a jpeg"[..])
+    );
+}
+
+#[test]
+fn test_speech_highpass_removes_rumble() {
+    let rms = |samples: &[f32]| {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    };
+    let tone = |frequency: f32| -> Vec<f32> {
+        (0..44100)
+            .map(|i| {
+                0.2 + 0.5 * (2.0 * std::f32::consts::PI * frequency * i as f32 / 44100.0).sin()
+            })
+            .collect()
+    };
+
+    let mut rumble = tone(20.0);
+    crate::filter::highpass(&mut rumble, 44100, 80.0);
+    let mut voice = tone(1000.0);
+    crate::filter::highpass(&mut voice, 44100, 80.0);
+
+    // DC offset and 20 Hz rumble are attenuated, the voice band passes
+    assert!(rms(&rumble[22050..]) < 0.15);
+    assert!((rms(&voice[22050..]) - 0.5 / 2f32.sqrt()).abs() < 0.02);
+}
+
+#[tokio::test]
+async fn test_speech_profile_keeps_file_layout() {
+    let wav_data = include_bytes!("test_data/test_22050hz.wav");
+
+    let music = decode_and_normalize(wav_data.as_slice().into(), |_, _| {})
+        .await
+        .unwrap();
+    let speech =
+        decode_and_normalize_with_profile(wav_data.as_slice().into(), Profile::Speech, |_, _| {})
+            .await
+            .unwrap();
+
+    assert_eq!(speech.filename, music.filename);
+    assert_eq!(speech.data.len(), music.data.len());
+    assert_ne!(speech.data, music.data);
+}
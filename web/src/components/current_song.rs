@@ -1,8 +1,12 @@
 use dioxus::prelude::*;
 use dioxus_bulma as b;
 
-use crate::components::PlaybackControls;
-use crate::services::playback::{CurrentPlaylistResponse, PlaybackState, StatusResponse};
+use crate::components::{use_toast, PlaybackControls};
+use crate::services::playback::{seek_to, CurrentPlaylistResponse, PlaybackState, StatusResponse};
+
+fn format_time(seconds: u32) -> String {
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
 
 #[component]
 pub fn CurrentSong(
@@ -34,19 +38,37 @@ pub fn CurrentSong(
         PlaybackState::Stopped => ("primary", "⏹"),
     };
 
-    let position_display = use_memo(move || {
-        status()
-            .as_ref()
-            .map(|s| {
-                if s.position_seconds > 0 {
-                    format!("{}:{:02}", s.position_seconds / 60, s.position_seconds % 60)
-                } else {
-                    "--:--".to_string()
-                }
-            })
-            .unwrap_or_else(|| "--:--".to_string())
+    let mut toast = use_toast();
+    // Position while the progress bar is dragged, so polling does not move it back
+    let mut scrub_position = use_signal(|| None::<u32>);
+
+    let duration = use_memo(move || {
+        let result: Option<u32> = try {
+            let index = status().as_ref()?.index_in_playlist;
+            current_playlist()?
+                .files
+                .get(index)?
+                .metadata
+                .as_ref()?
+                .duration
+        };
+
+        result.unwrap_or(0)
+    });
+
+    let position = use_memo(move || {
+        scrub_position().or_else(|| status().as_ref().map(|s| s.position_seconds as u32))
+    });
+
+    let position_display = use_memo(move || match position() {
+        Some(position) if position > 0 => format_time(position),
+        _ => "--:--".to_string(),
     });
 
+    let is_stopped = status()
+        .as_ref()
+        .is_none_or(|s| s.state == PlaybackState::Stopped);
+
     rsx! {
         div {
             b::Columns {
@@ -64,6 +86,32 @@ pub fn CurrentSong(
                     }
                 }
             }
+            div { class: "mt-4 is-flex is-align-items-center",
+                input {
+                    r#type: "range",
+                    style: "flex-grow: 1;",
+                    min: "0",
+                    max: "{duration}",
+                    value: "{position().unwrap_or(0)}",
+                    disabled: is_stopped || duration() == 0,
+                    oninput: move |e: Event<FormData>| {
+                        if let Ok(seconds) = e.value().parse::<u32>() {
+                            scrub_position.set(Some(seconds));
+                        }
+                    },
+                    onchange: move |e: Event<FormData>| {
+                        if let Ok(seconds) = e.value().parse::<u32>() {
+                            spawn(async move {
+                                if let Err(_e) = seek_to(seconds).await {
+                                    toast.show_error("Failed to seek");
+                                }
+                                scrub_position.set(None);
+                            });
+                        }
+                    },
+                }
+                span { class: "ml-2", "{format_time(duration())}" }
+            }
             div { class: "mt-4",
                 PlaybackControls { status }
             }
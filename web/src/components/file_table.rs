@@ -1,6 +1,6 @@
 use dioxus::prelude::*;
 use dioxus_bulma as b;
-use dioxus_free_icons::icons::fa_solid_icons::FaPlay;
+use dioxus_free_icons::icons::fa_solid_icons::{FaForwardStep, FaPlay, FaPlus};
 
 use crate::components::ControlsButton;
 use crate::services;
@@ -33,20 +33,61 @@ pub fn FileTable() -> Element {
                     for entry in vec {
                         tr {
                             td {
-                                ControlsButton {
-                                    icon: FaPlay,
-                                    label: "Play".to_string(),
-                                    size: Some(b::BulmaSize::Small),
-                                    onclick: Some(
-                                        EventHandler::new(move |_| {
-                                            let name = entry.name.clone();
-                                            spawn(async move {
-                                                if let Err(e) = services::playback::play_file(&name).await {
-                                                    eprintln!("Failed to play file: {:?}", e);
+                                b::Buttons {
+                                    ControlsButton {
+                                        icon: FaPlay,
+                                        label: "Play".to_string(),
+                                        size: Some(b::BulmaSize::Small),
+                                        onclick: Some(
+                                            EventHandler::new({
+                                                let name = entry.name.clone();
+                                                move |_| {
+                                                    let name = name.clone();
+                                                    spawn(async move {
+                                                        if let Err(e) = services::playback::play_file(&name).await {
+                                                            eprintln!("Failed to play file: {:?}", e);
+                                                        }
+                                                    });
                                                 }
-                                            });
-                                        }),
-                                    ),
+                                            }),
+                                        ),
+                                    }
+                                    ControlsButton {
+                                        icon: FaForwardStep,
+                                        label: "Play next".to_string(),
+                                        size: Some(b::BulmaSize::Small),
+                                        onclick: Some(
+                                            EventHandler::new({
+                                                let name = entry.name.clone();
+                                                move |_| {
+                                                    let name = name.clone();
+                                                    spawn(async move {
+                                                        if let Err(e) = services::playback::queue_file(&name, true).await {
+                                                            eprintln!("Failed to queue file: {:?}", e);
+                                                        }
+                                                    });
+                                                }
+                                            }),
+                                        ),
+                                    }
+                                    ControlsButton {
+                                        icon: FaPlus,
+                                        label: "Add to queue".to_string(),
+                                        size: Some(b::BulmaSize::Small),
+                                        onclick: Some(
+                                            EventHandler::new({
+                                                let name = entry.name.clone();
+                                                move |_| {
+                                                    let name = name.clone();
+                                                    spawn(async move {
+                                                        if let Err(e) = services::playback::queue_file(&name, false).await {
+                                                            eprintln!("Failed to queue file: {:?}", e);
+                                                        }
+                                                    });
+                                                }
+                                            }),
+                                        ),
+                                    }
                                 }
                             }
                             td { "{entry.metadata.artist}" }
@@ -25,6 +25,9 @@ pub use current_playlist::CurrentPlaylist;
 mod playback_controls;
 pub use playback_controls::PlaybackControls;
 
+mod system_sounds;
+pub use system_sounds::SystemSounds;
+
 mod notification;
 pub use notification::Notification;
 
@@ -4,11 +4,12 @@ use dioxus_bulma as b;
 use crate::components::use_toast;
 use crate::components::ControlsButton;
 use crate::services::playback::{
-    next, previous, stop, toggle_pause, volume_down, volume_up, PlaybackState, StatusResponse,
+    next, previous, set_mode, set_sleep_timer, stop, toggle_pause, volume_down, volume_up,
+    ModeRequest, PlaybackState, Repeat, StatusResponse,
 };
 use dioxus_free_icons::icons::fa_solid_icons::{
-    FaCircleLeft, FaCirclePause, FaCirclePlay, FaCircleRight, FaCircleStop, FaVolumeHigh,
-    FaVolumeLow,
+    FaCircleLeft, FaCirclePause, FaCirclePlay, FaCircleRight, FaCircleStop, FaMoon, FaRepeat,
+    FaShuffle, FaVolumeHigh, FaVolumeLow,
 };
 
 #[component]
@@ -19,6 +20,38 @@ pub fn PlaybackControls(status: ReadSignal<Option<StatusResponse>>) -> Element {
         .map(|s| &s.state)
         .unwrap_or(&PlaybackState::Stopped);
     let is_playing = matches!(state, PlaybackState::Playing);
+    let repeat = current_status
+        .as_ref()
+        .map(|s| s.repeat)
+        .unwrap_or_default();
+    let shuffle = current_status.as_ref().is_some_and(|s| s.shuffle);
+    let mode_text = match (repeat, shuffle) {
+        (Repeat::Off, false) => "In order",
+        (Repeat::Off, true) => "Shuffle",
+        (Repeat::One, _) => "Repeat track",
+        (Repeat::All, false) => "Repeat all",
+        (Repeat::All, true) => "Repeat all, shuffle",
+    };
+    let sleep_timer_seconds = current_status.as_ref().and_then(|s| s.sleep_timer_seconds);
+    // Like the play/pause button on the device: 15, 30, 45 minutes, then off
+    let next_sleep_minutes = match sleep_timer_seconds {
+        None => 15,
+        Some(seconds) if seconds < 15 * 60 => 30,
+        Some(seconds) if seconds < 30 * 60 => 45,
+        Some(_) => 0,
+    };
+    let sleep_text = match sleep_timer_seconds {
+        Some(0) => "Fading out".to_string(),
+        Some(seconds) => format!("Sleep in {} min", seconds.div_ceil(60)),
+        None => "No sleep timer".to_string(),
+    };
+    let volume_text = match current_status.as_ref().and_then(|s| s.volume) {
+        Some(volume) => match current_status.as_ref().and_then(|s| s.max_volume) {
+            Some(max_volume) => format!("Volume {}/{}", volume, max_volume),
+            None => format!("Volume {}", volume),
+        },
+        None => String::new(),
+    };
     let mut toast = use_toast();
 
     rsx! {
@@ -128,6 +161,64 @@ pub fn PlaybackControls(status: ReadSignal<Option<StatusResponse>>) -> Element {
                         },
                     }
                 }
+                p { class: "help", "{volume_text}" }
+            }
+            b::Column { size: b::ColumnSize::OneQuarter,
+                b::Buttons {
+                    ControlsButton {
+                        icon: FaRepeat,
+                        label: "Repeat".to_string(),
+                        onclick: {
+                            move |_| {
+                                spawn(async move {
+                                    let mode = ModeRequest {
+                                        repeat: Some(repeat.next()),
+                                        ..Default::default()
+                                    };
+                                    if let Err(_e) = set_mode(mode).await {
+                                        toast.show_error("Failed to change repeat mode");
+                                    }
+                                });
+                            }
+                        },
+                    }
+                    ControlsButton {
+                        icon: FaShuffle,
+                        label: "Shuffle".to_string(),
+                        onclick: {
+                            move |_| {
+                                spawn(async move {
+                                    let mode = ModeRequest {
+                                        shuffle: Some(!shuffle),
+                                        ..Default::default()
+                                    };
+                                    if let Err(_e) = set_mode(mode).await {
+                                        toast.show_error("Failed to change shuffle mode");
+                                    }
+                                });
+                            }
+                        },
+                    }
+                }
+                p { class: "help", "{mode_text}" }
+            }
+            b::Column { size: b::ColumnSize::OneQuarter,
+                b::Buttons {
+                    ControlsButton {
+                        icon: FaMoon,
+                        label: "Sleep Timer".to_string(),
+                        onclick: {
+                            move |_| {
+                                spawn(async move {
+                                    if let Err(_e) = set_sleep_timer(next_sleep_minutes).await {
+                                        toast.show_error("Failed to set sleep timer");
+                                    }
+                                });
+                            }
+                        },
+                    }
+                }
+                p { class: "help", "{sleep_text}" }
             }
         }
     }
@@ -0,0 +1,166 @@
+use dioxus::html::FileData;
+use dioxus::prelude::*;
+use dioxus_bulma as b;
+use dioxus_free_icons::icons::fa_solid_icons::{FaPlay, FaTrash};
+
+use crate::components::{use_toast, ControlsButton};
+use crate::services;
+
+/// What the device plays a system sound for
+fn event_label(sound: &str) -> &str {
+    match sound {
+        "boot" => "Device started",
+        "fob_recognized" => "Tag recognized",
+        "unknown_fob" => "Unknown tag",
+        "volume_limit" => "Volume limit reached",
+        "wifi_on" => "Wi-Fi on",
+        "wifi_off" => "Wi-Fi off",
+        "low_battery" => "Low battery",
+        "sleep_timer" => "Sleep timer set",
+        "sleep_timer_off" => "Sleep timer off",
+        "error" => "Error",
+        other => other,
+    }
+}
+
+/// Transcodes a recording and uploads it as the clip of `sound`
+async fn upload_clip(sound: &str, file: FileData) -> anyhow::Result<()> {
+    let data = file
+        .read_bytes()
+        .await
+        .map_err(|e| anyhow::anyhow!("reading file: {e:?}"))?;
+    let transcoded = services::transcoder::transcode(data.to_vec().into(), |_, _| {}).await?;
+    services::sounds::upload_sound(sound, transcoded.data).await
+}
+
+#[component]
+pub fn SystemSounds() -> Element {
+    let mut toast = use_toast();
+    let mut sounds_resource = use_resource(|| async {
+        match services::sounds::list_sounds().await {
+            Ok(sounds) => sounds,
+            Err(e) => {
+                eprintln!("Failed to list system sounds: {:?}", e);
+                vec![]
+            }
+        }
+    });
+    // Sound whose clip is being transcoded and uploaded
+    let mut uploading = use_signal(|| None::<String>);
+
+    rsx! {
+        b::Table { fullwidth: true,
+            thead {
+                tr {
+                    th { "Event" }
+                    th { "Sound" }
+                    th { "" }
+                }
+            }
+            tbody {
+                if let Some(sounds) = sounds_resource.read().as_ref().map(|r| r.to_vec()) {
+                    for entry in sounds {
+                        tr {
+                            td { "{event_label(&entry.sound)}" }
+                            td {
+                                if entry.custom {
+                                    span { class: "tag is-primary", "Custom" }
+                                } else {
+                                    span { class: "tag", "Built-in tones" }
+                                }
+                            }
+                            td {
+                                b::Buttons {
+                                    ControlsButton {
+                                        icon: FaPlay,
+                                        label: "Play on the device".to_string(),
+                                        size: Some(b::BulmaSize::Small),
+                                        onclick: Some(
+                                            EventHandler::new({
+                                                let sound = entry.sound.clone();
+                                                move |_| {
+                                                    let sound = sound.clone();
+                                                    spawn(async move {
+                                                        if let Err(e) = services::sounds::play_sound(&sound).await {
+                                                            eprintln!("Failed to play system sound: {:?}", e);
+                                                        }
+                                                    });
+                                                }
+                                            }),
+                                        ),
+                                    }
+                                    div { class: "file is-small is-primary mb-2 mr-2",
+                                        label { class: "file-label",
+                                            input {
+                                                class: "file-input",
+                                                r#type: "file",
+                                                accept: "audio/*",
+                                                disabled: uploading.read().is_some(),
+                                                onchange: {
+                                                    let sound = entry.sound.clone();
+                                                    move |e: Event<FormData>| {
+                                                        let Some(file) = e.files().into_iter().next() else {
+                                                            return;
+                                                        };
+                                                        let sound = sound.clone();
+                                                        spawn(async move {
+                                                            uploading.set(Some(sound.clone()));
+                                                            match upload_clip(&sound, file).await {
+                                                                Ok(()) => {
+                                                                    toast.show_success("Sound uploaded");
+                                                                    sounds_resource.restart();
+                                                                }
+                                                                Err(e) => {
+                                                                    toast.show_error(format!("Failed to upload sound: {e:?}"));
+                                                                }
+                                                            }
+                                                            uploading.set(None);
+                                                        });
+                                                    }
+                                                },
+                                            }
+                                            span { class: "file-cta",
+                                                span { class: "file-label",
+                                                    if uploading.read().as_deref() == Some(entry.sound.as_str()) {
+                                                        "Uploading…"
+                                                    } else {
+                                                        "Upload or record…"
+                                                    }
+                                                }
+                                            }
+                                        }
+                                    }
+                                    ControlsButton {
+                                        icon: FaTrash,
+                                        label: "Back to the built-in tones".to_string(),
+                                        size: Some(b::BulmaSize::Small),
+                                        disabled: !entry.custom,
+                                        onclick: Some(
+                                            EventHandler::new({
+                                                let sound = entry.sound.clone();
+                                                move |_| {
+                                                    let sound = sound.clone();
+                                                    spawn(async move {
+                                                        if let Err(e) = services::sounds::delete_sound(&sound).await {
+                                                            toast.show_error(format!("Failed to reset sound: {e:?}"));
+                                                        } else {
+                                                            sounds_resource.restart();
+                                                        }
+                                                    });
+                                                }
+                                            }),
+                                        ),
+                                    }
+                                }
+                            }
+                        }
+                    }
+                } else {
+                    tr {
+                        td { colspan: 3, "Loading system sounds..." }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -2,7 +2,7 @@ use dioxus::prelude::*;
 use dioxus_bulma as b;
 
 use crate::components::use_toast;
-use crate::components::{CurrentPlaylist, CurrentSong};
+use crate::components::{CurrentPlaylist, CurrentSong, Notification};
 use crate::services::playback::{self, get_status, StatusResponse};
 
 #[component]
@@ -52,9 +52,19 @@ pub fn Playback() -> Element {
         }
     });
 
+    let learning_fob = status().and_then(|s| s.learning_fob);
+
     rsx! {
         b::Section {
             b::Container {
+                if let Some(fob) = learning_fob {
+                    Notification {
+                        color: b::BulmaColor::Warning,
+                        "Fob "
+                        strong { "{fob}" }
+                        " has no playlist yet. Start a playlist, then press play/pause on the device to save it to the fob."
+                    }
+                }
                 b::Columns {
                     b::Column {
                         b::Card {
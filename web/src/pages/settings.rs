@@ -1,5 +1,6 @@
 use crate::components::use_toast;
 use crate::components::Notification;
+use crate::components::SystemSounds;
 use crate::services;
 use dioxus::prelude::*;
 use dioxus_bulma::{self as b, InputType};
@@ -8,13 +9,86 @@ use dioxus_bulma::{self as b, InputType};
 pub fn Settings() -> Element {
     let mut ssid = use_signal(String::new);
     let mut password = use_signal(String::new);
+    let mut max_volume = use_signal(String::new);
+    let mut startup_volume = use_signal(String::new);
+    let mut bass = use_signal(String::new);
+    let mut presence = use_signal(String::new);
+    // Settings not on this page, like the filter frequencies, are kept as loaded
+    let mut eq = use_signal(|| None::<services::config::EqConfig>);
+    let mut unknown_fob = use_signal(services::config::UnknownFobAction::default);
+    let mut stop_after = use_signal(String::new);
+    let mut rescan_action = use_signal(services::config::RescanAction::default);
+    let mut rescan_cooldown = use_signal(String::new);
     let mut toast = use_toast();
 
+    use_effect(move || {
+        spawn(async move {
+            if let Ok(Some(config)) = services::config::get_config().await {
+                ssid.set(config.ssid);
+                max_volume.set(config.max_volume.map(|v| v.to_string()).unwrap_or_default());
+                startup_volume.set(
+                    config
+                        .startup_volume
+                        .map(|v| v.to_string())
+                        .unwrap_or_default(),
+                );
+                if let Some(config) = config.eq {
+                    bass.set(config.bass_db.to_string());
+                    presence.set(config.presence_db.to_string());
+                }
+                eq.set(config.eq);
+                unknown_fob.set(config.unknown_fob.unwrap_or_default());
+                stop_after.set(
+                    config
+                        .tag_presence
+                        .map(|v| v.stop_after_seconds.to_string())
+                        .unwrap_or_default(),
+                );
+                if let Some(rescan) = config.rescan {
+                    rescan_action.set(rescan.action);
+                    rescan_cooldown.set(rescan.cooldown_seconds.to_string());
+                }
+            }
+        });
+    });
+
     let set_config = {
         move |_| {
+            let tag_presence = stop_after
+                .read()
+                .trim()
+                .parse()
+                .ok()
+                .map(|stop_after_seconds| services::config::TagPresenceConfig {
+                    stop_after_seconds,
+                });
+            let rescan = services::config::RescanConfig {
+                action: *rescan_action.read(),
+                cooldown_seconds: rescan_cooldown
+                    .read()
+                    .trim()
+                    .parse()
+                    .unwrap_or(services::config::RescanConfig::default().cooldown_seconds),
+            };
             let config = services::config::DeviceConfig {
                 ssid: ssid.read().clone(),
                 password: password.read().clone(),
+                max_volume: max_volume.read().trim().parse().ok(),
+                startup_volume: startup_volume.read().trim().parse().ok(),
+                eq: match (
+                    bass.read().trim().parse::<i8>(),
+                    presence.read().trim().parse::<i8>(),
+                ) {
+                    (Err(_), Err(_)) => None,
+                    (bass_db, presence_db) => Some(services::config::EqConfig {
+                        bass_db: bass_db.unwrap_or(0).clamp(-12, 12),
+                        presence_db: presence_db.unwrap_or(0).clamp(-12, 12),
+                        ..eq.read().unwrap_or_default()
+                    }),
+                },
+                unknown_fob: Some(*unknown_fob.read()),
+                tag_presence,
+                rescan: Some(rescan),
             };
 
             spawn(async move {
@@ -35,6 +109,15 @@ pub fn Settings() -> Element {
                 } else {
                     ssid.set(String::new());
                     password.set(String::new());
+                    max_volume.set(String::new());
+                    startup_volume.set(String::new());
+                    bass.set(String::new());
+                    presence.set(String::new());
+                    eq.set(None);
+                    unknown_fob.set(Default::default());
+                    stop_after.set(String::new());
+                    rescan_action.set(Default::default());
+                    rescan_cooldown.set(String::new());
                     toast.show_success("WiFi credentials deleted successfully");
                 }
             });
@@ -80,6 +163,137 @@ pub fn Settings() -> Element {
                     }
                 }
 
+                b::Field {
+                    b::Label { "Maximum Volume" }
+                    b::Control {
+                        b::Input {
+                            input_type: InputType::Text,
+                            placeholder: "1 to 32, empty for no limit",
+                            value: "{max_volume.read()}",
+                            oninput: move |e: Event<FormData>| max_volume.set(e.value()),
+                        }
+                    }
+                }
+
+                b::Field {
+                    b::Label { "Startup Volume" }
+                    b::Control {
+                        b::Input {
+                            input_type: InputType::Text,
+                            placeholder: "1 to 32, empty for the last volume",
+                            value: "{startup_volume.read()}",
+                            oninput: move |e: Event<FormData>| startup_volume.set(e.value()),
+                        }
+                    }
+                }
+
+                b::Field {
+                    b::Label { "Bass (dB)" }
+                    b::Control {
+                        b::Input {
+                            input_type: InputType::Text,
+                            placeholder: "-12 to 12, negative values cut the lows",
+                            value: "{bass.read()}",
+                            oninput: move |e: Event<FormData>| bass.set(e.value()),
+                        }
+                    }
+                }
+
+                b::Field {
+                    b::Label { "Presence (dB)" }
+                    b::Control {
+                        b::Input {
+                            input_type: InputType::Text,
+                            placeholder: "-12 to 12, empty for no equalizer",
+                            value: "{presence.read()}",
+                            oninput: move |e: Event<FormData>| presence.set(e.value()),
+                        }
+                    }
+                }
+
+                b::Field {
+                    b::Label { "Unknown Fobs" }
+                    b::Control {
+                        div { class: "select",
+                            select {
+                                onchange: move |e: Event<FormData>| {
+                                    if let Some(action) = services::config::UnknownFobAction::ALL
+                                        .into_iter()
+                                        .find(|action| action.name() == e.value())
+                                    {
+                                        unknown_fob.set(action);
+                                    }
+                                },
+                                for action in services::config::UnknownFobAction::ALL {
+                                    option {
+                                        value: action.name(),
+                                        selected: *unknown_fob.read() == action,
+                                        "{action.label()}"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    p { class: "help",
+                        "When learning, scan the new fob, start a playlist and press play/pause on the device within two minutes."
+                    }
+                }
+
+                b::Field {
+                    b::Label { "Pause When the Fob Is Removed" }
+                    b::Control {
+                        b::Input {
+                            input_type: InputType::Text,
+                            placeholder: "Seconds until playback stops, empty to keep playing",
+                            value: "{stop_after.read()}",
+                            oninput: move |e: Event<FormData>| stop_after.set(e.value()),
+                        }
+                    }
+                    p { class: "help",
+                        "Music plays only while the fob is on the device. Putting it back resumes, another fob switches playlists."
+                    }
+                }
+
+                b::Field {
+                    b::Label { "Scanning the Playing Fob Again" }
+                    b::Control {
+                        div { class: "select",
+                            select {
+                                onchange: move |e: Event<FormData>| {
+                                    if let Some(action) = services::config::RescanAction::ALL
+                                        .into_iter()
+                                        .find(|action| action.name() == e.value())
+                                    {
+                                        rescan_action.set(action);
+                                    }
+                                },
+                                for action in services::config::RescanAction::ALL {
+                                    option {
+                                        value: action.name(),
+                                        selected: *rescan_action.read() == action,
+                                        "{action.label()}"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+
+                b::Field {
+                    b::Label { "Second Scan Cooldown (seconds)" }
+                    b::Control {
+                        b::Input {
+                            input_type: InputType::Text,
+                            placeholder: "10",
+                            value: "{rescan_cooldown.read()}",
+                            oninput: move |e: Event<FormData>| rescan_cooldown.set(e.value()),
+                        }
+                    }
+                    p { class: "help",
+                        "Scans of the fob playing within this time after the last one do nothing, so a fob left on the device does not repeat the action."
+                    }
+                }
+
                 b::Field { grouped: true,
                     b::Control {
                         b::Button {
@@ -99,5 +313,14 @@ pub fn Settings() -> Element {
                 }
             }
         }
+        b::Section {
+            b::Container {
+                b::Title { size: b::TitleSize::Is4, "System Sounds" }
+                p { class: "mb-4",
+                    "The device plays a short sound for these events. Upload or record a clip to replace the built-in tones."
+                }
+                SystemSounds {}
+            }
+        }
     }
 }
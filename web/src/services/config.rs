@@ -1,7 +1,8 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
 use super::utils::resolve_relative_url;
+use super::REQUEST_TIMEOUT;
 
 #[derive(Serialize, Deserialize)]
 pub struct DeviceConfig {
@@ -10,6 +11,191 @@ pub struct DeviceConfig {
 
     #[serde(alias = "PASSWORD")]
     pub password: String,
+
+    /// Highest volume step (1 to 32), unlimited if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_volume: Option<u8>,
+
+    /// Volume step after power-on, the last volume if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub startup_volume: Option<u8>,
+
+    /// Equalizer tuned for the enclosure, unfiltered if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eq: Option<EqConfig>,
+
+    /// What scanning a fob without playlist does, a sound if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unknown_fob: Option<UnknownFobAction>,
+
+    /// Pauses when the fob is removed, fobs only start playback if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag_presence: Option<TagPresenceConfig>,
+
+    /// What scanning the fob playing again does, ignored after a cooldown if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rescan: Option<RescanConfig>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct TagPresenceConfig {
+    /// Seconds after the removal at which the paused playlist stops
+    pub stop_after_seconds: u16,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct RescanConfig {
+    pub action: RescanAction,
+    /// Seconds after a scan of the fob playing in which scanning it again does nothing
+    pub cooldown_seconds: u16,
+}
+
+impl Default for RescanConfig {
+    fn default() -> Self {
+        Self {
+            action: RescanAction::default(),
+            cooldown_seconds: 10,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RescanAction {
+    #[default]
+    Ignore,
+    Pause,
+    Next,
+    Restart,
+}
+
+impl RescanAction {
+    pub const ALL: [RescanAction; 4] = [
+        RescanAction::Ignore,
+        RescanAction::Pause,
+        RescanAction::Next,
+        RescanAction::Restart,
+    ];
+
+    /// Value in the config
+    pub fn name(self) -> &'static str {
+        match self {
+            RescanAction::Ignore => "ignore",
+            RescanAction::Pause => "pause",
+            RescanAction::Next => "next",
+            RescanAction::Restart => "restart",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RescanAction::Ignore => "Keep playing",
+            RescanAction::Pause => "Pause",
+            RescanAction::Next => "Skip to the next track",
+            RescanAction::Restart => "Start the playlist over",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct EqConfig {
+    pub bass_db: i8,
+    pub bass_hz: u16,
+    pub presence_db: i8,
+    pub presence_hz: u16,
+    pub presence_q: f32,
+    pub loudness: bool,
+}
+
+impl Default for EqConfig {
+    fn default() -> Self {
+        Self {
+            bass_db: 0,
+            bass_hz: 150,
+            presence_db: 0,
+            presence_hz: 3000,
+            presence_q: 1.0,
+            loudness: true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum UnknownFobAction {
+    Ignore,
+    #[default]
+    Sound,
+    /// Plays the sound and saves the playlist playing to the fob on the next play/pause press
+    Learn,
+}
+
+impl UnknownFobAction {
+    pub const ALL: [UnknownFobAction; 3] = [
+        UnknownFobAction::Ignore,
+        UnknownFobAction::Sound,
+        UnknownFobAction::Learn,
+    ];
+
+    /// Value in the config
+    pub fn name(self) -> &'static str {
+        match self {
+            UnknownFobAction::Ignore => "ignore",
+            UnknownFobAction::Sound => "sound",
+            UnknownFobAction::Learn => "learn",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            UnknownFobAction::Ignore => "Do nothing",
+            UnknownFobAction::Sound => "Play the unknown fob sound",
+            UnknownFobAction::Learn => "Play the sound and learn a playlist",
+        }
+    }
+}
+
+/// Device configuration as reported by the device, without the password
+#[derive(Deserialize, Clone)]
+pub struct PublicConfig {
+    pub ssid: String,
+    #[serde(default)]
+    pub max_volume: Option<u8>,
+    #[serde(default)]
+    pub startup_volume: Option<u8>,
+    #[serde(default)]
+    pub eq: Option<EqConfig>,
+    #[serde(default)]
+    pub unknown_fob: Option<UnknownFobAction>,
+    #[serde(default)]
+    pub tag_presence: Option<TagPresenceConfig>,
+    #[serde(default)]
+    pub rescan: Option<RescanConfig>,
+}
+
+/// The device's configuration, `None` if it is not configured
+pub(crate) async fn get_config() -> Result<Option<PublicConfig>> {
+    let url = resolve_relative_url("/api/config")?;
+    let client = reqwest::Client::default();
+    let response = client
+        .get(url)
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+        .context("getting config")?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    let config = response
+        .error_for_status()
+        .context("getting config")?
+        .json()
+        .await
+        .context("reading response")?;
+
+    Ok(Some(config))
 }
 
 pub(crate) async fn put_config(config: &DeviceConfig) -> Result<()> {
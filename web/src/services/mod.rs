@@ -4,6 +4,7 @@ pub(crate) mod config;
 pub(crate) mod files;
 pub(crate) mod fob;
 pub(crate) mod playback;
+pub(crate) mod sounds;
 pub(crate) mod transcoder;
 pub(crate) mod utils;
 
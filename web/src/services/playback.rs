@@ -10,6 +10,39 @@ pub struct StatusResponse {
     pub state: PlaybackState,
     pub index_in_playlist: usize,
     pub playlist_name: Option<String>,
+    #[serde(default)]
+    pub repeat: Repeat,
+    #[serde(default)]
+    pub shuffle: bool,
+    #[serde(default)]
+    pub sleep_timer_seconds: Option<u32>,
+    #[serde(default)]
+    pub volume: Option<u8>,
+    #[serde(default)]
+    pub max_volume: Option<u8>,
+    /// Fob without playlist waiting for one in learn mode
+    #[serde(default)]
+    pub learning_fob: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Repeat {
+    #[default]
+    Off,
+    One,
+    All,
+}
+
+impl Repeat {
+    /// The mode the repeat button switches to
+    pub fn next(self) -> Self {
+        match self {
+            Repeat::Off => Repeat::All,
+            Repeat::All => Repeat::One,
+            Repeat::One => Repeat::Off,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -40,6 +73,33 @@ pub enum PlayRequest {
     PlaylistRef(String),
 }
 
+/// Adds to the playlist playing, or plays when stopped
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueRequest {
+    Append(PlayRequest),
+    InsertNext(PlayRequest),
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SeekRequest {
+    To(u32),
+}
+
+#[derive(Serialize)]
+pub struct SleepRequest {
+    pub minutes: u16,
+}
+
+#[derive(Serialize, Default)]
+pub struct ModeRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repeat: Option<Repeat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shuffle: Option<bool>,
+}
+
 pub async fn get_status() -> Result<StatusResponse> {
     let url = resolve_relative_url("/api/playback/status")?;
     let client = reqwest::Client::default();
@@ -86,6 +146,28 @@ pub(crate) async fn play_file(file: &str) -> Result<()> {
     Ok(())
 }
 
+/// Adds `file` right after the one playing with `next`, otherwise at the end of the playlist
+pub(crate) async fn queue_file(file: &str, next: bool) -> Result<()> {
+    let url = resolve_relative_url("/api/playback/queue")?;
+    let source = PlayRequest::File(file.to_string());
+    let request = if next {
+        QueueRequest::InsertNext(source)
+    } else {
+        QueueRequest::Append(source)
+    };
+    let client = reqwest::Client::default();
+    client
+        .post(url)
+        .timeout(REQUEST_TIMEOUT)
+        .json(&request)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .context("queueing file")?;
+
+    Ok(())
+}
+
 #[allow(dead_code)]
 pub(crate) async fn play_playlist(files: Vec<String>) -> Result<()> {
     let url = resolve_relative_url("/api/playback/play")?;
@@ -194,3 +276,45 @@ pub async fn volume_down() -> Result<()> {
 
     Ok(())
 }
+
+pub async fn seek_to(seconds: u32) -> Result<()> {
+    let url = resolve_relative_url("/api/playback/seek")?;
+    reqwest::Client::default()
+        .post(url)
+        .timeout(REQUEST_TIMEOUT)
+        .json(&SeekRequest::To(seconds))
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .context("seeking")?;
+
+    Ok(())
+}
+
+pub async fn set_mode(mode: ModeRequest) -> Result<()> {
+    let url = resolve_relative_url("/api/playback/mode")?;
+    reqwest::Client::default()
+        .put(url)
+        .timeout(REQUEST_TIMEOUT)
+        .json(&mode)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .context("setting playback mode")?;
+
+    Ok(())
+}
+
+pub async fn set_sleep_timer(minutes: u16) -> Result<()> {
+    let url = resolve_relative_url("/api/playback/sleep")?;
+    reqwest::Client::default()
+        .post(url)
+        .timeout(REQUEST_TIMEOUT)
+        .json(&SleepRequest { minutes })
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .context("setting sleep timer")?;
+
+    Ok(())
+}
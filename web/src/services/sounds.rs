@@ -0,0 +1,68 @@
+use anyhow::{Context, Result};
+use reqwest::Response;
+use serde::Deserialize;
+
+use super::utils::resolve_relative_url;
+use super::REQUEST_TIMEOUT;
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct SystemSoundEntry {
+    pub sound: String,
+    /// Whether a clip replaces the built-in tones
+    pub custom: bool,
+}
+
+pub(crate) async fn list_sounds() -> Result<Vec<SystemSoundEntry>> {
+    let url = resolve_relative_url("/api/system_sounds")?;
+    let client = reqwest::Client::default();
+    let response = client
+        .get(url)
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+        .and_then(Response::error_for_status)
+        .context("listing system sounds")?;
+    response.json().await.context("reading response")
+}
+
+/// Replaces the tones of `sound` with a transcoded clip
+pub(crate) async fn upload_sound(sound: &str, data: Box<[u8]>) -> Result<()> {
+    let url = resolve_relative_url(&format!("/api/system_sounds/{sound}"))?;
+    let client = reqwest::Client::default();
+    client
+        .put(url)
+        .timeout(REQUEST_TIMEOUT)
+        .body(data.into_vec())
+        .send()
+        .await
+        .and_then(Response::error_for_status)
+        .context("uploading system sound")?;
+    Ok(())
+}
+
+/// Removes the clip of `sound`, the device plays its built-in tones again
+pub(crate) async fn delete_sound(sound: &str) -> Result<()> {
+    let url = resolve_relative_url(&format!("/api/system_sounds/{sound}"))?;
+    let client = reqwest::Client::default();
+    client
+        .delete(url)
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+        .and_then(Response::error_for_status)
+        .context("deleting system sound")?;
+    Ok(())
+}
+
+pub(crate) async fn play_sound(sound: &str) -> Result<()> {
+    let url = resolve_relative_url(&format!("/api/system_sounds/{sound}"))?;
+    let client = reqwest::Client::default();
+    client
+        .post(url)
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+        .and_then(Response::error_for_status)
+        .context("playing system sound")?;
+    Ok(())
+}